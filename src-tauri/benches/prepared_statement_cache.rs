@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use duckdb::Connection;
+
+const QUERY: &str = "SELECT id, id * 2 AS doubled FROM bench_items WHERE id % 7 = 0 LIMIT 100";
+
+// Note: Each run prepares the query twice, like query_result_json does for column names and rows
+const RUNS_PER_QUERY: usize = 2;
+
+fn setup() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE bench_items AS SELECT range AS id FROM range(10000)")
+        .unwrap();
+    conn
+}
+
+fn run_prepared(conn: &Connection) -> usize {
+    (0..RUNS_PER_QUERY)
+        .map(|_| {
+            let mut stmt = conn.prepare(QUERY).unwrap();
+            let rows = stmt.query_map([], |row| row.get::<_, i64>(0)).unwrap();
+            rows.count()
+        })
+        .sum()
+}
+
+fn run_prepared_cached(conn: &Connection) -> usize {
+    (0..RUNS_PER_QUERY)
+        .map(|_| {
+            let mut stmt = conn.prepare_cached(QUERY).unwrap();
+            let rows = stmt.query_map([], |row| row.get::<_, i64>(0)).unwrap();
+            rows.count()
+        })
+        .sum()
+}
+
+fn prepared_statement_cache(c: &mut Criterion) {
+    let conn = setup();
+
+    c.bench_function("query_result_json/prepare", |b| {
+        b.iter(|| run_prepared(&conn))
+    });
+    c.bench_function("query_result_json/prepare_cached", |b| {
+        b.iter(|| run_prepared_cached(&conn))
+    });
+}
+
+criterion_group!(benches, prepared_statement_cache);
+criterion_main!(benches);
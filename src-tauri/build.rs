@@ -1,3 +1,27 @@
+// Note: Cargo.lock pins the duckdb binding actually compiled in, which CARGO_PKG_VERSION cannot see
+fn duckdb_crate_version() -> Option<String> {
+    let lock_path =
+        std::path::Path::new(&std::env::var("CARGO_MANIFEST_DIR").ok()?).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+
+    let lock = std::fs::read_to_string(lock_path).ok()?;
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line == "name = \"duckdb\"" {
+            let version = lines
+                .next()?
+                .strip_prefix("version = \"")?
+                .strip_suffix('"')?;
+            return Some(version.to_string());
+        }
+    }
+    None
+}
+
 fn main() {
+    println!(
+        "cargo:rustc-env=DUCKDB_CRATE_VERSION={}",
+        duckdb_crate_version().unwrap_or_else(|| "unknown".to_string())
+    );
     tauri_build::build()
 }
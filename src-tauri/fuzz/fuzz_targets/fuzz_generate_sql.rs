@@ -0,0 +1,142 @@
+#![no_main]
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+
+// Note: query_builder is included by path so that the fuzz build does not pull in Tauri
+#[allow(dead_code)]
+#[path = "../../src/query_builder.rs"]
+mod query_builder;
+
+use query_builder::{generate_sql, Edge, Node, NodeGraph};
+
+const MAX_NODES: usize = 8;
+const MAX_EDGES: usize = 8;
+const MAX_ITEMS: usize = 4;
+
+const NODE_TYPES: &[&str] = &[
+    "table",
+    "table_function",
+    "select",
+    "sort",
+    "limit",
+    "filter",
+    "aggregation",
+    "unknown",
+];
+const COLUMNS: &[&str] = &["id", "name", "price", "category", ""];
+const DIRECTIONS: &[&str] = &["asc", "desc", "sideways"];
+const OPERATORS: &[&str] = &["==", "!=", ">", "<", ">=", "<=", "in", "like"];
+const FUNCTIONS: &[&str] = &["COUNT(*)", "COUNT", "SUM", "AVG", "MAX", "MIN", "MEDIAN"];
+
+fn node_id(u: &mut Unstructured) -> Result<String> {
+    Ok(u.int_in_range(0..=MAX_NODES - 1)?.to_string())
+}
+
+fn pick_or_string(u: &mut Unstructured, choices: &[&str]) -> Result<String> {
+    if u.ratio(1, 4)? {
+        String::arbitrary(u)
+    } else {
+        Ok(u.choose(choices)?.to_string())
+    }
+}
+
+fn bounded_vec<T>(
+    u: &mut Unstructured,
+    max: usize,
+    mut f: impl FnMut(&mut Unstructured) -> Result<T>,
+) -> Result<Vec<T>> {
+    let len = u.int_in_range(0..=max)?;
+    (0..len).map(|_| f(u)).collect()
+}
+
+fn scalar(u: &mut Unstructured) -> Result<serde_json::Value> {
+    Ok(match u.int_in_range(0..=4)? {
+        0 => serde_json::Value::Null,
+        1 => serde_json::json!(bool::arbitrary(u)?),
+        2 => serde_json::json!(i64::arbitrary(u)?),
+        3 => serde_json::Number::from_f64(f64::arbitrary(u)?)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        _ => serde_json::json!(String::arbitrary(u)?),
+    })
+}
+
+fn filter_value(u: &mut Unstructured) -> Result<serde_json::Value> {
+    if u.ratio(1, 3)? {
+        Ok(serde_json::Value::Array(bounded_vec(u, MAX_ITEMS, scalar)?))
+    } else {
+        scalar(u)
+    }
+}
+
+fn node_data(u: &mut Unstructured, node_type: &str) -> Result<serde_json::Value> {
+    if u.ratio(1, 16)? {
+        return scalar(u);
+    }
+
+    Ok(match node_type {
+        "table" => serde_json::json!({ "table_name": pick_or_string(u, &["users", "products"])? }),
+        "table_function" => serde_json::json!({
+            "function_name": pick_or_string(u, &["read_csv_auto", "range", "read_text"])?,
+            "args": bounded_vec(u, MAX_ITEMS, scalar)?
+        }),
+        "select" => serde_json::json!({
+            "columns": bounded_vec(u, MAX_ITEMS, |u| pick_or_string(u, COLUMNS))?
+        }),
+        "sort" => serde_json::json!({
+            "order": bounded_vec(u, MAX_ITEMS, |u| Ok(serde_json::json!({
+                "column": pick_or_string(u, COLUMNS)?,
+                "direction": u.choose(DIRECTIONS)?
+            })))?
+        }),
+        "limit" => serde_json::json!({ "limit": Option::<i64>::arbitrary(u)? }),
+        "filter" => serde_json::json!({
+            "conditions": bounded_vec(u, MAX_ITEMS, |u| Ok(serde_json::json!({
+                "column": pick_or_string(u, COLUMNS)?,
+                "operator": u.choose(OPERATORS)?,
+                "value": filter_value(u)?,
+                "negate": bool::arbitrary(u)?
+            })))?
+        }),
+        "aggregation" => serde_json::json!({
+            "dimensions": bounded_vec(u, MAX_ITEMS, |u| pick_or_string(u, COLUMNS))?,
+            "metrics": bounded_vec(u, MAX_ITEMS, |u| Ok(serde_json::json!({
+                "function": u.choose(FUNCTIONS)?,
+                "column": pick_or_string(u, COLUMNS)?
+            })))?
+        }),
+        _ => serde_json::json!({}),
+    })
+}
+
+impl<'a> Arbitrary<'a> for NodeGraph {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let nodes = bounded_vec(u, MAX_NODES, |u| {
+            let node_type = u.choose(NODE_TYPES)?.to_string();
+            Ok(Node {
+                id: node_id(u)?,
+                data: node_data(u, &node_type)?,
+                node_type,
+            })
+        })?;
+
+        let edges = bounded_vec(u, MAX_EDGES, |u| {
+            Ok(Edge {
+                source: node_id(u)?,
+                target: node_id(u)?,
+            })
+        })?;
+
+        Ok(NodeGraph {
+            selected_node_ids: vec![node_id(u)?],
+            nodes,
+            edges,
+        })
+    }
+}
+
+fuzz_target!(|graph: NodeGraph| {
+    let _ = generate_sql(&graph, None);
+    let _ = generate_sql(&graph, Some((100, 0)));
+});
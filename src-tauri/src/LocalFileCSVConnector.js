@@ -1,25 +1,137 @@
 export function config() {
-  return [{ name: 'filePath', type: 'file', default: '', nullable: false }];
+  return [
+    { name: 'filePath', type: 'file', default: '', nullable: false },
+    { name: 'filePattern', type: 'string', default: '', nullable: true },
+    { name: 'encoding', type: 'string', default: 'utf-8', nullable: true },
+    {
+      name: 'largeObjectThreshold',
+      type: 'string',
+      default: '',
+      nullable: true
+    }
+  ];
+}
+
+// Note: filePattern is matched against the files next to the selected file
+async function matchFilePaths(config) {
+  const pattern = (config.filePattern ?? '').trim();
+  if (pattern === '') {
+    return [config.filePath];
+  }
+
+  const separator = Math.max(
+    config.filePath.lastIndexOf('/'),
+    config.filePath.lastIndexOf('\\')
+  );
+  const dir = separator >= 0 ? config.filePath.slice(0, separator) || '/' : '.';
+  const paths = await streaksight.glob(dir, pattern);
+  if (paths.length === 0) {
+    throw new Error(`No files match pattern: ${pattern}`);
+  }
+  return paths;
+}
+
+// Note: DuckDB reads UTF-8, so other encodings are transcoded to a temp copy that
+// is removed once read_csv is done. Each file is decoded in memory as a whole, so
+// large files in another encoding are not supported.
+async function withFilePaths(config, name, read) {
+  const paths = await matchFilePaths(config);
+  const encoding = (config.encoding ?? '').trim().toLowerCase();
+  if (encoding === '' || encoding === 'utf-8' || encoding === 'utf8') {
+    return await read(paths);
+  }
+
+  // Note: Discoveries run in parallel, so each call gets its own temp file names
+  const tempDir = streaksight.tempDir();
+  const runId = `${Date.now()}_${Math.random().toString(36).slice(2)}`;
+  const tempPaths = [];
+  try {
+    for (const [i, path] of paths.entries()) {
+      const contents = await streaksight.readFileWithEncoding(path, encoding);
+      const tempPath = `${tempDir}/streaksight_utf8_${name}_${runId}_${i}.csv`;
+      tempPaths.push(tempPath);
+      await streaksight.writeFile(tempPath, contents);
+    }
+    return await read(tempPaths);
+  } finally {
+    await Promise.all(tempPaths.map((path) => streaksight.removeFile(path)));
+  }
+}
+
+function csvSource(paths) {
+  const literals = paths.map((path) => `'${path.replace(/'/g, "''")}'`);
+  return literals.length === 1 ? literals[0] : `[${literals.join(', ')}]`;
 }
 
 export async function discovery(config) {
-  const escapedPath = config.filePath.replace(/'/g, "''");
-
-  return await streaksight.inferSchemaFromSQL(`
-    SELECT * FROM read_csv_auto(
-      '${escapedPath}'
-    )
-    LIMIT 100
-  `);
+  return await withFilePaths(config, 'discovery', async (filePaths) => {
+    const [filePath] = filePaths;
+    const source = csvSource(filePaths);
+
+    let schema;
+    try {
+      schema = await streaksight.inferSchemaFromSQL(`
+        SELECT * FROM read_csv_auto(
+          ${source}
+        )
+        LIMIT 100
+      `);
+    } catch (e) {
+      console.warn('Falling back to Rust CSV schema inference:', e.message);
+      schema = await streaksight.inferCsvSchema(
+        filePath,
+        config.delimiter ?? ',',
+        config.hasHeader ?? true
+      );
+    }
+
+    return await streaksight.flagLargeColumns(
+      schema,
+      filePath,
+      'csv',
+      config.largeObjectThreshold
+    );
+  });
+}
+
+export async function sync(name, config, schema, options = {}) {
+  return await withFilePaths(config, name, async (filePaths) =>
+    syncFiles(name, csvSource(filePaths), schema, options)
+  );
 }
 
-export async function sync(name, config, _schema) {
-  const escapedPath = config.filePath.replace(/'/g, "''");
+async function syncFiles(name, source, schema, options) {
+  const columns = streaksight.selectColumns(schema);
+  const onError = options.onError ?? 'abort';
+
+  if (onError === 'abort') {
+    const sql = `
+      CREATE TABLE IF NOT EXISTS "${name}" AS
+      SELECT ${columns} FROM read_csv_auto(${source})
+    `;
+
+    await streaksight.runSql(sql);
+    return { rowsFailed: 0 };
+  }
+
+  // Note: reject_errors is a temporary table, so everything must run on the same connection
+  const statements = [
+    `
+      CREATE TABLE IF NOT EXISTS "${name}" AS
+      SELECT ${columns} FROM read_csv_auto(${source}, store_rejects = true)
+    `
+  ];
+
+  if (onError === 'collect_and_report') {
+    statements.push(`
+      CREATE OR REPLACE TABLE "_streaksight_sync_errors_${name}" AS
+      SELECT line, column_name, error_type, error_message, csv_line
+      FROM reject_errors
+    `);
+  }
 
-  const sql = `
-    CREATE TABLE IF NOT EXISTS "${name}" AS
-    SELECT * FROM read_csv_auto('${escapedPath}')
-  `;
+  statements.push('SELECT COUNT(*) AS rows_failed FROM reject_errors');
 
-  await streaksight.runSql(sql);
+  const result = await streaksight.runSqlBatch(statements);
+  return { rowsFailed: result[0].rows_failed };
 }
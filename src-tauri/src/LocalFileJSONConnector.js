@@ -1,23 +1,76 @@
 export function config() {
-  return [{ name: 'filePath', type: 'file', default: '', nullable: false }];
+  return [
+    { name: 'filePath', type: 'file', default: '', nullable: true },
+    { name: 'files', type: 'string', default: '', nullable: true },
+    {
+      name: 'largeObjectThreshold',
+      type: 'string',
+      default: '',
+      nullable: true
+    }
+  ];
+}
+
+// Note: files is an array or a newline-separated list and takes precedence over filePath
+function resolveFilePaths(config) {
+  const files = Array.isArray(config.files)
+    ? config.files
+    : String(config.files ?? '').split('\n');
+  const paths = files.map((path) => path.trim()).filter((path) => path !== '');
+  if (paths.length > 0) {
+    return paths;
+  }
+
+  const filePath = String(config.filePath ?? '').trim();
+  if (filePath === '') {
+    throw new Error('filePath or files is required');
+  }
+  return [filePath];
+}
+
+function jsonSource(paths) {
+  const literals = paths.map((path) => `'${path.replace(/'/g, "''")}'`);
+  return literals.length === 1 ? literals[0] : `[${literals.join(', ')}]`;
 }
 
 export async function discovery(config) {
-  const escapedPath = config.filePath.replace(/'/g, "''");
+  const filePaths = resolveFilePaths(config);
+
+  const schema =
+    filePaths.length === 1
+      ? await streaksight.inferSchemaFromSQL(`
+          SELECT * FROM read_json_auto(${jsonSource(filePaths)})
+          LIMIT 100
+        `)
+      : await streaksight.readJsonFiles(filePaths);
 
-  return await streaksight.inferSchemaFromSQL(`
-    SELECT * FROM read_json_auto('${escapedPath}')
-    LIMIT 100
-  `);
+  return await streaksight.flagLargeColumns(
+    schema,
+    filePaths,
+    'json',
+    config.largeObjectThreshold
+  );
 }
 
-export async function sync(name, config, _schema) {
-  const escapedPath = config.filePath.replace(/'/g, "''");
+export async function sync(name, config, schema, options = {}) {
+  const filePaths = resolveFilePaths(config);
+  const columns = streaksight.selectColumns(schema);
+  const onError = options.onError ?? 'abort';
+
+  if (onError === 'collect_and_report') {
+    throw new Error(
+      'collect_and_report is not supported by the JSON connector'
+    );
+  }
 
+  const unionByName = filePaths.length > 1 ? ', union_by_name = true' : '';
+  const ignoreErrors = onError === 'skip' ? ', ignore_errors = true' : '';
   const sql = `
     CREATE TABLE IF NOT EXISTS "${name}" AS
-    SELECT * FROM read_json_auto('${escapedPath}')
+    SELECT ${columns} FROM read_json_auto(${jsonSource(filePaths)}${unionByName}${ignoreErrors})
   `;
 
   await streaksight.runSql(sql);
+  // Note: read_json_auto does not report skipped records
+  return { rowsFailed: onError === 'skip' ? null : 0 };
 }
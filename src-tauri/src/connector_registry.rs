@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const CONNECTOR_FILE_SUFFIX: &str = "Connector.js";
+const MANIFEST_FILE_NAME: &str = "connector.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConnectorPermissions {
+    pub http: bool,
+    pub file_read: bool,
+    pub file_write: bool,
+    pub system_info: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectorManifest {
+    pub id: String,
+    pub path: PathBuf,
+    pub label: String,
+    pub icon: Option<String>,
+    pub version: Option<String>,
+    pub permissions: ConnectorPermissions,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestFile {
+    id: String,
+    label: String,
+    #[serde(default)]
+    icon: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    permissions: ConnectorPermissions,
+}
+
+// Note: "MyAPISource" becomes "My API Source"
+fn label_from_id(id: &str) -> String {
+    let chars: Vec<char> = id.chars().collect();
+    let mut label = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let word_start = i > 0
+            && c.is_uppercase()
+            && (chars[i - 1].is_lowercase()
+                || (chars[i - 1].is_uppercase()
+                    && chars.get(i + 1).is_some_and(|next| next.is_lowercase())));
+        if word_start {
+            label.push(' ');
+        }
+        label.push(c);
+    }
+    label
+}
+
+fn connector_id(path: &Path) -> Option<&str> {
+    let file_name = path.file_name()?.to_str()?;
+    file_name
+        .strip_suffix(CONNECTOR_FILE_SUFFIX)
+        .filter(|id| !id.is_empty())
+}
+
+fn inferred_manifest(path: PathBuf) -> Option<ConnectorManifest> {
+    let id = connector_id(&path)?.to_string();
+    Some(ConnectorManifest {
+        label: label_from_id(&id),
+        id,
+        path,
+        icon: None,
+        version: None,
+        permissions: ConnectorPermissions::default(),
+    })
+}
+
+pub fn parse_manifest(json: &str, path: PathBuf) -> Result<ConnectorManifest, String> {
+    let manifest: ManifestFile =
+        serde_json::from_str(json).map_err(|e| format!("Invalid connector manifest: {}", e))?;
+
+    if manifest.id.trim().is_empty() {
+        return Err("Invalid connector manifest: id must not be empty".to_string());
+    }
+    if manifest.label.trim().is_empty() {
+        return Err("Invalid connector manifest: label must not be empty".to_string());
+    }
+
+    Ok(ConnectorManifest {
+        id: manifest.id,
+        path,
+        label: manifest.label,
+        icon: manifest.icon,
+        version: manifest.version,
+        permissions: manifest.permissions,
+    })
+}
+
+// Note: A connector in its own directory may declare its metadata in a connector.json next to it
+fn scan_connector_dir(dir: &Path) -> Option<ConnectorManifest> {
+    let mut scripts: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && connector_id(path).is_some())
+        .collect();
+    scripts.sort();
+    let script = scripts.into_iter().next()?;
+
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    if !manifest_path.is_file() {
+        return inferred_manifest(script);
+    }
+
+    let parsed = std::fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("Failed to read connector manifest: {}", e))
+        .and_then(|json| parse_manifest(&json, script));
+    match parsed {
+        Ok(manifest) => Some(manifest),
+        Err(e) => {
+            eprintln!("Skipping connector {}: {}", dir.display(), e);
+            None
+        }
+    }
+}
+
+pub fn scan_connectors(dir: &Path) -> Vec<ConnectorManifest> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut manifests: Vec<ConnectorManifest> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            if path.is_dir() {
+                scan_connector_dir(&path)
+            } else {
+                inferred_manifest(path)
+            }
+        })
+        .collect();
+    manifests.sort_by(|a, b| a.id.cmp(&b.id));
+
+    manifests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_from_id() {
+        assert_eq!(label_from_id("Stripe"), "Stripe");
+        assert_eq!(label_from_id("LocalFileCSV"), "Local File CSV");
+        assert_eq!(label_from_id("MyAPISource"), "My API Source");
+        assert_eq!(label_from_id("S3Parquet"), "S3Parquet");
+    }
+
+    #[test]
+    fn test_scan_connectors() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "StripeConnector.js",
+            "MyAPISourceConnector.js",
+            "Connector.js",
+            "helpers.js",
+            "NotesConnector.ts",
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        std::fs::create_dir(dir.path().join("empty")).unwrap();
+
+        let manifests = scan_connectors(dir.path());
+
+        assert_eq!(
+            manifests,
+            vec![
+                ConnectorManifest {
+                    id: "MyAPISource".to_string(),
+                    path: dir.path().join("MyAPISourceConnector.js"),
+                    label: "My API Source".to_string(),
+                    icon: None,
+                    version: None,
+                    permissions: ConnectorPermissions::default(),
+                },
+                ConnectorManifest {
+                    id: "Stripe".to_string(),
+                    path: dir.path().join("StripeConnector.js"),
+                    label: "Stripe".to_string(),
+                    icon: None,
+                    version: None,
+                    permissions: ConnectorPermissions::default(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_connectors_missing_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(scan_connectors(&dir.path().join("connectors")).is_empty());
+    }
+
+    #[test]
+    fn test_scan_connectors_with_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let github = dir.path().join("github");
+        std::fs::create_dir(&github).unwrap();
+        std::fs::write(github.join("GithubIssuesConnector.js"), "").unwrap();
+        std::fs::write(
+            github.join("connector.json"),
+            r#"{
+                "id": "GithubIssues",
+                "label": "GitHub Issues",
+                "icon": "github.svg",
+                "version": "1.2.0",
+                "permissions": { "http": true }
+            }"#,
+        )
+        .unwrap();
+
+        let notion = dir.path().join("notion");
+        std::fs::create_dir(&notion).unwrap();
+        std::fs::write(notion.join("NotionPagesConnector.js"), "").unwrap();
+
+        let broken = dir.path().join("broken");
+        std::fs::create_dir(&broken).unwrap();
+        std::fs::write(broken.join("BrokenConnector.js"), "").unwrap();
+        std::fs::write(broken.join("connector.json"), r#"{ "id": "Broken" }"#).unwrap();
+
+        let manifests = scan_connectors(dir.path());
+
+        assert_eq!(
+            manifests,
+            vec![
+                ConnectorManifest {
+                    id: "GithubIssues".to_string(),
+                    path: github.join("GithubIssuesConnector.js"),
+                    label: "GitHub Issues".to_string(),
+                    icon: Some("github.svg".to_string()),
+                    version: Some("1.2.0".to_string()),
+                    permissions: ConnectorPermissions {
+                        http: true,
+                        file_read: false,
+                        file_write: false,
+                        system_info: false,
+                    },
+                },
+                ConnectorManifest {
+                    id: "NotionPages".to_string(),
+                    path: notion.join("NotionPagesConnector.js"),
+                    label: "Notion Pages".to_string(),
+                    icon: None,
+                    version: None,
+                    permissions: ConnectorPermissions::default(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_invalid() {
+        let path = PathBuf::from("StripeConnector.js");
+
+        let result = parse_manifest("not json", path.clone());
+        assert!(result
+            .unwrap_err()
+            .starts_with("Invalid connector manifest: expected ident"));
+
+        let result = parse_manifest(r#"{ "id": "Stripe" }"#, path.clone());
+        assert!(result
+            .unwrap_err()
+            .starts_with("Invalid connector manifest: missing field `label`"));
+
+        let result = parse_manifest(r#"{ "id": " ", "label": "Stripe" }"#, path.clone());
+        assert_eq!(
+            result.unwrap_err(),
+            "Invalid connector manifest: id must not be empty"
+        );
+
+        let result = parse_manifest(
+            r#"{ "id": "Stripe", "label": "Stripe", "permissions": { "http": "yes" } }"#,
+            path,
+        );
+        assert!(result
+            .unwrap_err()
+            .starts_with("Invalid connector manifest: invalid type"));
+    }
+}
@@ -0,0 +1,88 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum AppError {
+    DbError(String),
+    ConnectorError(String),
+    ValidationError(String),
+    IoError(String),
+    TimeoutError,
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::DbError(_) => "DB_ERROR",
+            AppError::ConnectorError(_) => "CONNECTOR_ERROR",
+            AppError::ValidationError(_) => "VALIDATION_ERROR",
+            AppError::IoError(_) => "IO_ERROR",
+            AppError::TimeoutError => "TIMEOUT_ERROR",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::DbError(message)
+            | AppError::ConnectorError(message)
+            | AppError::ValidationError(message)
+            | AppError::IoError(message) => write!(f, "{}", message),
+            AppError::TimeoutError => write!(f, "Operation timed out"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_db_error() {
+        let error = AppError::DbError("Failed to prepare statement".to_string());
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "code": "DB_ERROR",
+                "message": "Failed to prepare statement"
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialize_timeout_error() {
+        assert_eq!(
+            serde_json::to_value(AppError::TimeoutError).unwrap(),
+            serde_json::json!({
+                "code": "TIMEOUT_ERROR",
+                "message": "Operation timed out"
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(
+            AppError::ConnectorError(String::new()).code(),
+            "CONNECTOR_ERROR"
+        );
+        assert_eq!(
+            AppError::ValidationError(String::new()).code(),
+            "VALIDATION_ERROR"
+        );
+        assert_eq!(AppError::IoError(String::new()).code(), "IO_ERROR");
+    }
+}
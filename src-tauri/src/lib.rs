@@ -1,10 +1,15 @@
 use chrono::NaiveDate;
-use deno_core::{extension, op2};
+use deno_core::{extension, op2, AsyncRefCell, OpState, RcRef, Resource, ResourceId};
 use deno_error::JsErrorBox;
 
+mod connector_registry;
+mod error;
 mod query_builder;
+pub mod schema_inference;
 
-fn duckdb_connect() -> Result<Connection, JsErrorBox> {
+pub use error::AppError;
+
+fn duckdb_path() -> Result<PathBuf, JsErrorBox> {
     let app_data_path = APP_DATA_PATH.get().ok_or_else(|| {
         JsErrorBox::from_err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
@@ -12,8 +17,32 @@ fn duckdb_connect() -> Result<Connection, JsErrorBox> {
         ))
     })?;
 
-    let db_path = app_data_path.join("database.duckdb");
-    let conn = Connection::open(&db_path).map_err(|e| {
+    Ok(app_data_path.join("database.duckdb"))
+}
+
+fn duckdb_connect() -> Result<Connection, JsErrorBox> {
+    let db_path = duckdb_path()?;
+    let conn = retry_on_lock(|| Connection::open(&db_path)).map_err(|e| {
+        JsErrorBox::from_err(std::io::Error::other(format!(
+            "Failed to open DuckDB: {}",
+            e
+        )))
+    })?;
+    Ok(conn)
+}
+
+// Note: A read-only connection cannot create the database file, so a fresh one is created first
+fn duckdb_connect_readonly() -> Result<Connection, JsErrorBox> {
+    let db_path = duckdb_path()?;
+    if !db_path.exists() {
+        drop(duckdb_connect()?);
+    }
+
+    let conn = retry_on_lock(|| {
+        let config = duckdb::Config::default().access_mode(duckdb::AccessMode::ReadOnly)?;
+        Connection::open_with_flags(&db_path, config)
+    })
+    .map_err(|e| {
         JsErrorBox::from_err(std::io::Error::other(format!(
             "Failed to open DuckDB: {}",
             e
@@ -22,6 +51,49 @@ fn duckdb_connect() -> Result<Connection, JsErrorBox> {
     Ok(conn)
 }
 
+const LOCK_RETRY_LIMIT: u32 = 5;
+const LOCK_RETRY_INITIAL_DELAY_MS: u64 = 50;
+
+// Note: DuckDB reports a database held by another process as "Could not set lock on file",
+// while Windows refuses to open it with a sharing violation
+fn is_lock_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("could not set lock on file")
+        || (message.contains("cannot open file")
+            && message.contains("being used by another process"))
+}
+
+// Note: Connections are opened from async commands, so on a multi-threaded runtime the wait is
+// handed to block_in_place instead of stalling a worker thread
+fn wait_for_lock_retry(delay: std::time::Duration) {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| std::thread::sleep(delay))
+        }
+        _ => std::thread::sleep(delay),
+    }
+}
+
+fn retry_on_lock<T, E: std::fmt::Display>(mut f: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+    let mut delay = std::time::Duration::from_millis(LOCK_RETRY_INITIAL_DELAY_MS);
+    let mut attempt = 0;
+
+    loop {
+        match f() {
+            Err(e) if attempt < LOCK_RETRY_LIMIT && is_lock_error(&e.to_string()) => {
+                attempt += 1;
+                eprintln!(
+                    "DuckDB database is locked, retrying in {:?} (attempt {}/{}): {}",
+                    delay, attempt, LOCK_RETRY_LIMIT, e
+                );
+                wait_for_lock_retry(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
 #[op2(async)]
 #[string]
 async fn op_read_file(#[string] path: String) -> Result<String, JsErrorBox> {
@@ -31,6 +103,136 @@ async fn op_read_file(#[string] path: String) -> Result<String, JsErrorBox> {
     Ok(s)
 }
 
+#[op2(async)]
+#[string]
+async fn op_read_file_with_encoding(
+    #[string] path: String,
+    #[string] encoding: String,
+) -> Result<String, JsErrorBox> {
+    let encoding = encoding_rs::Encoding::for_label(encoding.trim().as_bytes())
+        .ok_or_else(|| JsErrorBox::type_error(format!("Unsupported encoding: {}", encoding)))?;
+    let bytes = tokio::fs::read(path).await.map_err(JsErrorBox::from_err)?;
+    let (contents, _, _) = encoding.decode(&bytes);
+    Ok(contents.into_owned())
+}
+
+// Note: Chunks end on a character boundary, so the bytes consumed can be fewer than chunk_size
+async fn read_file_chunk(
+    path: &str,
+    offset: u64,
+    chunk_size: usize,
+) -> Result<(String, u64), JsErrorBox> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(JsErrorBox::from_err)?;
+    file.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .map_err(JsErrorBox::from_err)?;
+    let mut buf = Vec::new();
+    file.take(chunk_size as u64)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(JsErrorBox::from_err)?;
+
+    match std::str::from_utf8(&buf) {
+        Ok(_) => {}
+        Err(e) if e.error_len().is_none() && e.valid_up_to() > 0 => buf.truncate(e.valid_up_to()),
+        Err(e) if e.error_len().is_none() => {
+            return Err(JsErrorBox::type_error(
+                "Chunk size is too small to hold a complete character",
+            ));
+        }
+        Err(e) => return Err(JsErrorBox::from_err(std::io::Error::other(e))),
+    }
+    let consumed = buf.len() as u64;
+    let chunk =
+        String::from_utf8(buf).map_err(|e| JsErrorBox::from_err(std::io::Error::other(e)))?;
+
+    Ok((chunk, consumed))
+}
+
+#[derive(Default)]
+struct FileReadOffsets(HashMap<String, u64>);
+
+// Note: The stored offset is cleared once the end of the file is reached, so the next call starts over
+#[op2(async)]
+#[string]
+async fn op_read_file_chunked(
+    state: std::rc::Rc<std::cell::RefCell<OpState>>,
+    #[string] path: String,
+    #[number] chunk_size: usize,
+) -> Result<String, JsErrorBox> {
+    let offset = state
+        .borrow()
+        .try_borrow::<FileReadOffsets>()
+        .and_then(|offsets| offsets.0.get(&path).copied())
+        .unwrap_or(0);
+
+    let (chunk, consumed) = read_file_chunk(&path, offset, chunk_size).await?;
+
+    let mut state = state.borrow_mut();
+    if !state.has::<FileReadOffsets>() {
+        state.put(FileReadOffsets::default());
+    }
+    let offsets = &mut state.borrow_mut::<FileReadOffsets>().0;
+    if consumed == 0 {
+        offsets.remove(&path);
+    } else {
+        offsets.insert(path, offset + consumed);
+    }
+
+    Ok(chunk)
+}
+
+#[op2(async)]
+#[serde]
+async fn op_read_file_chunked_offset(
+    #[string] path: String,
+    #[number] chunk_size: usize,
+    #[number] offset: u64,
+) -> Result<serde_json::Value, JsErrorBox> {
+    let (chunk, consumed) = read_file_chunk(&path, offset, chunk_size).await?;
+    Ok(serde_json::json!({
+        "chunk": chunk,
+        "nextOffset": offset + consumed
+    }))
+}
+
+#[op2]
+#[string]
+fn op_temp_dir() -> String {
+    temp_dir().to_string_lossy().replace('\\', "/")
+}
+
+fn system_info() -> serde_json::Value {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+
+    serde_json::json!({
+        "cpu_count": num_cpus::get(),
+        "available_memory_bytes": system.available_memory(),
+        "os": std::env::consts::OS
+    })
+}
+
+// Note: Runtimes without connector permissions in their state are denied
+#[op2]
+#[serde]
+fn op_get_system_info(state: &mut OpState) -> Result<serde_json::Value, JsErrorBox> {
+    let allowed = state
+        .try_borrow::<connector_registry::ConnectorPermissions>()
+        .is_some_and(|permissions| permissions.system_info);
+    if !allowed {
+        return Err(JsErrorBox::generic(
+            "Connector does not have the system_info permission",
+        ));
+    }
+
+    Ok(system_info())
+}
+
 #[op2(async)]
 async fn op_write_file(
     #[string] path: String,
@@ -42,6 +244,15 @@ async fn op_write_file(
     Ok(())
 }
 
+// Note: A missing file counts as removed, so cleanup can run after a partial write
+#[op2(async)]
+async fn op_remove_file(#[string] path: String) -> Result<(), JsErrorBox> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(JsErrorBox::from_err(e)),
+        _ => Ok(()),
+    }
+}
+
 #[op2(async)]
 #[serde]
 async fn op_run_sql(#[string] sql: String) -> Result<serde_json::Value, JsErrorBox> {
@@ -96,202 +307,722 @@ async fn op_run_sql(#[string] sql: String) -> Result<serde_json::Value, JsErrorB
     Ok(serde_json::Value::Array(rows_data))
 }
 
-extension!(
-    streaksight_ext,
-    ops = [op_read_file, op_write_file, op_run_sql],
-    esm_entry_point = "ext:streaksight_ext/src/runtime.js",
-    esm = ["src/runtime.js"],
-);
+#[op2(async)]
+#[serde]
+async fn op_run_sql_batch(
+    #[serde] statements: Vec<String>,
+) -> Result<serde_json::Value, JsErrorBox> {
+    let conn = duckdb_connect()?;
 
-mod connector_type {
-    pub const LOCAL_FILE_CSV: &str = "LocalFileCSV";
-    pub const LOCAL_FILE_JSON: &str = "LocalFileJSON";
+    let Some((last, rest)) = statements.split_last() else {
+        return Ok(serde_json::Value::Array(Vec::new()));
+    };
+
+    for sql in rest {
+        conn.execute_batch(sql).map_err(|e| {
+            JsErrorBox::from_err(std::io::Error::other(format!(
+                "Failed to execute query: {}",
+                e
+            )))
+        })?;
+    }
+
+    let mut result = query_result_json(&conn, last)
+        .map_err(|e| JsErrorBox::from_err(std::io::Error::other(e.to_string())))?;
+
+    Ok(result["rows"].take())
 }
 
-fn resolve_connector_path(ty: &str) -> Result<PathBuf, String> {
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+// Note: Only pragmas that report state are allowed; each is run without arguments
+const ALLOWED_PRAGMAS: &[&str] = &[
+    "collations",
+    "database_list",
+    "database_size",
+    "platform",
+    "show_tables",
+    "user_agent",
+    "version",
+];
 
-    let connector_file = match ty {
-        connector_type::LOCAL_FILE_CSV => "LocalFileCSVConnector.js",
-        connector_type::LOCAL_FILE_JSON => "LocalFileJSONConnector.js",
-        _ => return Err("Unknown connector type".to_string()),
-    };
+#[op2(async)]
+#[serde]
+async fn op_run_duckdb_pragma(#[string] pragma: String) -> Result<serde_json::Value, JsErrorBox> {
+    if !ALLOWED_PRAGMAS.contains(&pragma.as_str()) {
+        return Err(JsErrorBox::type_error(format!(
+            "Pragma not allowed: {}",
+            pragma
+        )));
+    }
 
-    let connector_path = if current_dir.ends_with("src-tauri") {
-        current_dir.join(format!("src/{}", connector_file))
-    } else {
-        current_dir.join(format!("src-tauri/src/{}", connector_file))
-    };
+    let conn = duckdb_connect()?;
+    let mut result = query_result_json(&conn, &format!("PRAGMA {}", pragma))
+        .map_err(|e| JsErrorBox::from_err(std::io::Error::other(e.to_string())))?;
 
-    Ok(connector_path)
+    Ok(result["rows"].take())
 }
 
-fn duckdb_value_to_json(value: duckdb::types::ValueRef) -> serde_json::Value {
-    match value {
-        duckdb::types::ValueRef::Null => serde_json::Value::Null,
-        duckdb::types::ValueRef::Boolean(b) => serde_json::Value::Bool(b),
-        duckdb::types::ValueRef::TinyInt(i) => serde_json::Value::Number(i.into()),
-        duckdb::types::ValueRef::SmallInt(i) => serde_json::Value::Number(i.into()),
-        duckdb::types::ValueRef::Int(i) => serde_json::Value::Number(i.into()),
-        duckdb::types::ValueRef::BigInt(i) => serde_json::Value::Number(i.into()),
-        duckdb::types::ValueRef::HugeInt(i) => serde_json::Value::Number((i as i64).into()),
-        duckdb::types::ValueRef::Float(f) => serde_json::Number::from_f64(f as f64)
-            .map(serde_json::Value::Number)
-            .unwrap_or(serde_json::Value::Null),
-        duckdb::types::ValueRef::Double(d) => serde_json::Number::from_f64(d)
-            .map(serde_json::Value::Number)
-            .unwrap_or(serde_json::Value::Null),
-        duckdb::types::ValueRef::Text(s) => {
-            serde_json::Value::String(String::from_utf8_lossy(s).to_string())
-        }
-        duckdb::types::ValueRef::Date32(days) => {
-            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
-            let date = epoch + chrono::Duration::days(days as i64);
-            serde_json::Value::String(date.format("%Y-%m-%d").to_string())
-        }
-        _ => serde_json::Value::String(format!("{:?}", value)),
+const SQL_STREAM_BUFFER_SIZE: usize = 64;
+
+type SqlStreamItem = Result<serde_json::Value, String>;
+
+struct SqlStreamResource {
+    rx: AsyncRefCell<tokio::sync::mpsc::Receiver<SqlStreamItem>>,
+}
+
+impl Resource for SqlStreamResource {
+    fn name(&self) -> std::borrow::Cow<'_, str> {
+        "sqlStream".into()
     }
 }
 
-async fn load_runtime_js(
-    runtime: &mut deno_core::JsRuntime,
-    current_dir: &std::path::Path,
-) -> Result<(), String> {
-    let runtime_js_path = if current_dir.ends_with("src-tauri") {
-        current_dir.join("src/runtime.js")
-    } else {
-        current_dir.join("src-tauri/src/runtime.js")
-    };
+// Note: Runs on its own thread and stops as soon as the receiving resource is closed
+fn stream_sql_rows(sql: &str, tx: &tokio::sync::mpsc::Sender<SqlStreamItem>) -> Result<(), String> {
+    let conn = duckdb_connect().map_err(|e| e.to_string())?;
 
-    let runtime_js_url = deno_core::ModuleSpecifier::from_file_path(&runtime_js_path)
-        .map_err(|_| "Failed to convert runtime.js path to URL".to_string())?;
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to prepare SQL: {}", e))?;
+    let mut rows = stmt
+        .query([])
+        .map_err(|e| format!("Failed to execute query: {}", e))?;
+    let column_names = rows
+        .as_ref()
+        .map(|stmt| stmt.column_names())
+        .unwrap_or_default();
 
-    let runtime_id = runtime
-        .load_side_es_module(&runtime_js_url)
-        .await
-        .map_err(|e| format!("Failed to load runtime.js: {}", e))?;
-    let runtime_eval = runtime.mod_evaluate(runtime_id);
-    runtime
-        .run_event_loop(Default::default())
-        .await
-        .map_err(|e| format!("Failed to run event loop for runtime.js: {}", e))?;
-    runtime_eval
-        .await
-        .map_err(|e| format!("Failed to evaluate runtime.js: {}", e))?;
+    while let Some(row) = rows
+        .next()
+        .map_err(|e| format!("Failed to fetch row: {}", e))?
+    {
+        let mut map = serde_json::Map::new();
+        for (i, col_name) in column_names.iter().enumerate() {
+            let value = match row.get_ref(i) {
+                Ok(val) => duckdb_value_to_json(val),
+                Err(_) => serde_json::Value::Null,
+            };
+            map.insert(col_name.clone(), value);
+        }
+        if tx
+            .blocking_send(Ok(serde_json::Value::Object(map)))
+            .is_err()
+        {
+            break;
+        }
+    }
 
     Ok(())
 }
 
-async fn execute_deno_module(
-    runtime: &mut deno_core::JsRuntime,
-    module_path: &deno_core::ModuleSpecifier,
-) -> Result<(), String> {
-    let id = runtime
-        .load_main_es_module(module_path)
-        .await
-        .map_err(|e| format!("Failed to load module: {}", e))?;
-    let eval = runtime.mod_evaluate(id);
-    runtime
-        .run_event_loop(Default::default())
-        .await
-        .map_err(|e| format!("Failed to run event loop: {}", e))?;
-    eval.await
-        .map_err(|e| format!("Failed to evaluate module: {}", e))?;
+fn spawn_sql_stream(
+    sql: String,
+    buffer_size: usize,
+) -> (
+    tokio::sync::mpsc::Receiver<SqlStreamItem>,
+    std::thread::JoinHandle<()>,
+) {
+    let (tx, rx) = tokio::sync::mpsc::channel(buffer_size);
+    let handle = std::thread::spawn(move || {
+        if let Err(e) = stream_sql_rows(&sql, &tx) {
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+    (rx, handle)
+}
 
-    Ok(())
+#[op2(fast)]
+#[smi]
+fn op_run_sql_stream(state: &mut OpState, #[string] sql: String) -> ResourceId {
+    let (rx, _) = spawn_sql_stream(sql, SQL_STREAM_BUFFER_SIZE);
+    state.resource_table.add(SqlStreamResource {
+        rx: AsyncRefCell::new(rx),
+    })
 }
 
-#[tauri::command]
-async fn config(ty: String) -> Result<String, String> {
-    if ty != connector_type::LOCAL_FILE_CSV && ty != connector_type::LOCAL_FILE_JSON {
-        return Err("Unknown connector type".to_string());
+#[op2(async)]
+#[serde]
+async fn op_run_sql_stream_next(
+    state: std::rc::Rc<std::cell::RefCell<OpState>>,
+    #[smi] rid: ResourceId,
+) -> Result<serde_json::Value, JsErrorBox> {
+    let resource = state
+        .borrow()
+        .resource_table
+        .get::<SqlStreamResource>(rid)
+        .map_err(JsErrorBox::from_err)?;
+    let mut rx = RcRef::map(&resource, |r| &r.rx).borrow_mut().await;
+
+    match rx.recv().await {
+        Some(Ok(row)) => Ok(row),
+        Some(Err(e)) => Err(JsErrorBox::from_err(std::io::Error::other(e))),
+        None => Ok(serde_json::Value::Null),
     }
+}
 
-    tokio::task::spawn_blocking(move || {
-        use deno_core::{JsRuntime, RuntimeOptions};
-        use std::rc::Rc;
+#[op2(async)]
+#[serde]
+async fn op_infer_csv_schema(
+    #[string] path: String,
+    #[string] delimiter: String,
+    has_header: bool,
+    #[smi] sample_rows: u32,
+) -> Result<serde_json::Value, JsErrorBox> {
+    let mut chars = delimiter.chars();
+    let (Some(delimiter), None) = (chars.next(), chars.next()) else {
+        return Err(JsErrorBox::type_error(
+            "Delimiter must be a single character",
+        ));
+    };
 
-        let connector_path = resolve_connector_path(&ty)?;
+    schema_inference::infer_csv_schema(&path, delimiter, has_header, sample_rows as usize)
+        .map_err(|e| JsErrorBox::from_err(std::io::Error::other(e)))
+}
 
-        let result_file_path = std::env::temp_dir().join("streaksight_config_result.json");
+#[op2(async)]
+#[serde]
+async fn op_sample_column_widths(
+    #[string] path: String,
+    #[string] format: String,
+    #[smi] sample_rows: u32,
+) -> Result<serde_json::Value, JsErrorBox> {
+    schema_inference::sample_column_widths(&path, &format, sample_rows as usize)
+        .map_err(|e| JsErrorBox::from_err(std::io::Error::other(e)))
+}
 
-        let temp_js = format!(
-            r#"import {{ config }} from "{}";
-               const result = config();
-               const resultJson = JSON.stringify(result);
-               await streaksight.writeFile("{}", resultJson);"#,
-            connector_path.to_str().unwrap().replace("\\", "/"),
-            result_file_path.to_str().unwrap().replace("\\", "/")
-        );
+#[op2(async)]
+#[serde]
+async fn op_sample_n_rows(
+    #[string] path: String,
+    #[string] format: String,
+    #[smi] n: u32,
+) -> Result<serde_json::Value, JsErrorBox> {
+    schema_inference::sample_rows(&path, &format, n as usize)
+        .map_err(|e| JsErrorBox::from_err(std::io::Error::other(e)))
+}
 
-        let temp_js_path = std::env::temp_dir().join("streaksight_config_temp.js");
-        std::fs::write(&temp_js_path, temp_js)
-            .map_err(|e| format!("Failed to write temp JS file: {}", e))?;
+#[op2(async)]
+#[serde]
+async fn op_read_json_files(#[serde] paths: Vec<String>) -> Result<serde_json::Value, JsErrorBox> {
+    schema_inference::infer_json_files_schema(&paths)
+        .map_err(|e| JsErrorBox::from_err(std::io::Error::other(e)))
+}
 
-        let module_path = deno_core::ModuleSpecifier::from_file_path(&temp_js_path)
-            .map_err(|_| "Failed to convert temp path to URL".to_string())?;
+fn glob_files(dir: &str, pattern: &str) -> Result<Vec<String>, String> {
+    let pattern =
+        glob::Pattern::new(pattern).map_err(|e| format!("Invalid glob pattern: {}", e))?;
 
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .map_err(|e| format!("Failed to create runtime: {}", e))?;
+    let mut paths = Vec::new();
+    for entry in
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read directory {}: {}", dir, e))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+        if path.is_file() && pattern.matches(&entry.file_name().to_string_lossy()) {
+            paths.push(path.to_string_lossy().to_string());
+        }
+    }
+    paths.sort();
 
-        let local = tokio::task::LocalSet::new();
-        local.block_on(&rt, async move {
-            let mut runtime = JsRuntime::new(RuntimeOptions {
-                module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
-                extensions: vec![streaksight_ext::init()],
-                ..Default::default()
-            });
+    Ok(paths)
+}
 
-            execute_deno_module(&mut runtime, &module_path).await?;
+#[op2(async)]
+#[serde]
+async fn op_glob(
+    #[string] dir: String,
+    #[string] pattern: String,
+) -> Result<Vec<String>, JsErrorBox> {
+    glob_files(&dir, &pattern).map_err(|e| JsErrorBox::from_err(std::io::Error::other(e)))
+}
 
-            let json_str = std::fs::read_to_string(&result_file_path)
-                .map_err(|e| format!("Failed to read result file: {}", e))?;
+const FILE_CHANGED_EVENT: &str = "connector:file-changed";
 
-            let _ = std::fs::remove_file(&temp_js_path);
-            let _ = std::fs::remove_file(&result_file_path);
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct FileChanged {
+    path: String,
+}
 
-            Ok(json_str)
-        })
+trait FileChangeEmitter: Send + Sync + 'static {
+    fn emit_file_changed(&self, event: &FileChanged) -> Result<(), String>;
+}
+
+struct AppFileChangeEmitter {
+    app: tauri::AppHandle,
+}
+
+impl FileChangeEmitter for AppFileChangeEmitter {
+    fn emit_file_changed(&self, event: &FileChanged) -> Result<(), String> {
+        self.app
+            .emit(FILE_CHANGED_EVENT, event)
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn file_watchers() -> &'static Mutex<HashMap<String, notify::RecommendedWatcher>> {
+    FILE_WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Note: The parent directory is watched because editors often save by replacing the file
+fn spawn_file_watcher(
+    path: String,
+    debounce_ms: u64,
+    emitter: impl FileChangeEmitter,
+) -> Result<(), String> {
+    use notify::Watcher;
+
+    let file_path = std::fs::canonicalize(&path)
+        .map_err(|e| format!("Failed to resolve path {}: {}", path, e))?;
+    let dir = file_path
+        .parent()
+        .ok_or_else(|| format!("File has no parent directory: {}", path))?
+        .to_path_buf();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let watched_path = file_path.clone();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        if let Ok(event) = result {
+            let relevant = matches!(
+                event.kind,
+                notify::EventKind::Create(_) | notify::EventKind::Modify(_)
+            );
+            if relevant && event.paths.iter().any(|p| p == &watched_path) {
+                let _ = tx.send(());
+            }
+        }
     })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+    watcher
+        .watch(&dir, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", path, e))?;
+
+    // Note: The thread exits once the watcher, and with it the sender, is dropped
+    let event = FileChanged { path: path.clone() };
+    let debounce = std::time::Duration::from_millis(debounce_ms);
+    std::thread::spawn(move || {
+        while rx.recv().is_ok() {
+            loop {
+                match rx.recv_timeout(debounce) {
+                    Ok(()) => continue,
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            if let Err(e) = emitter.emit_file_changed(&event) {
+                eprintln!("Failed to emit change of {}: {}", event.path, e);
+            }
+        }
+    });
+
+    file_watchers()
+        .lock()
+        .map_err(|e| format!("Watcher registry poisoned: {}", e))?
+        .insert(path, watcher);
+
+    Ok(())
+}
+
+fn stop_file_watcher(path: &str) -> Result<(), String> {
+    file_watchers()
+        .lock()
+        .map_err(|e| format!("Watcher registry poisoned: {}", e))?
+        .remove(path);
+
+    Ok(())
+}
+
+#[op2(fast)]
+fn op_watch_file(#[string] path: String, #[number] debounce_ms: u64) -> Result<(), JsErrorBox> {
+    let app = APP_HANDLE
+        .get()
+        .ok_or_else(|| JsErrorBox::generic("App handle not initialized"))?;
+
+    spawn_file_watcher(path, debounce_ms, AppFileChangeEmitter { app: app.clone() })
+        .map_err(|e| JsErrorBox::from_err(std::io::Error::other(e)))
+}
+
+#[op2(fast)]
+fn op_unwatch_file(#[string] path: String) -> Result<(), JsErrorBox> {
+    stop_file_watcher(&path).map_err(|e| JsErrorBox::from_err(std::io::Error::other(e)))
+}
+
+const DELIMITER_SAMPLE_BYTES: u64 = 1024;
+
+#[op2(async)]
+#[string]
+async fn op_detect_delimiter(#[string] path: String) -> Result<String, JsErrorBox> {
+    use tokio::io::AsyncReadExt;
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(JsErrorBox::from_err)?;
+    let mut buf = Vec::new();
+    file.take(DELIMITER_SAMPLE_BYTES)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(JsErrorBox::from_err)?;
+
+    let sample = String::from_utf8_lossy(&buf);
+    Ok(schema_inference::detect_delimiter(&sample).to_string())
+}
+
+const ENCODING_SAMPLE_BYTES: u64 = 4096;
+
+#[op2(async)]
+#[string]
+async fn op_detect_encoding(#[string] path: String) -> Result<String, JsErrorBox> {
+    use tokio::io::AsyncReadExt;
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(JsErrorBox::from_err)?;
+    let mut buf = Vec::new();
+    file.take(ENCODING_SAMPLE_BYTES)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(JsErrorBox::from_err)?;
+
+    Ok(schema_inference::detect_encoding(&buf).to_string())
+}
+
+extension!(
+    streaksight_ext,
+    ops = [
+        op_read_file,
+        op_read_file_with_encoding,
+        op_read_file_chunked,
+        op_read_file_chunked_offset,
+        op_temp_dir,
+        op_get_system_info,
+        op_write_file,
+        op_remove_file,
+        op_run_sql,
+        op_run_sql_batch,
+        op_run_duckdb_pragma,
+        op_run_sql_stream,
+        op_run_sql_stream_next,
+        op_infer_csv_schema,
+        op_sample_column_widths,
+        op_sample_n_rows,
+        op_read_json_files,
+        op_detect_delimiter,
+        op_detect_encoding,
+        op_glob,
+        op_watch_file,
+        op_unwatch_file
+    ],
+    esm_entry_point = "ext:streaksight_ext/src/runtime.js",
+    esm = ["src/runtime.js"],
+);
+
+mod connector_type {
+    pub const LOCAL_FILE_CSV: &str = "LocalFileCSV";
+    pub const LOCAL_FILE_JSON: &str = "LocalFileJSON";
+}
+
+const BUILTIN_CONNECTORS: [(&str, &str, &str); 2] = [
+    (
+        connector_type::LOCAL_FILE_CSV,
+        "LocalFileCSVConnector.js",
+        "Local CSV/TSV File",
+    ),
+    (
+        connector_type::LOCAL_FILE_JSON,
+        "LocalFileJSONConnector.js",
+        "Local JSON File",
+    ),
+];
+
+fn user_connectors() -> Vec<connector_registry::ConnectorManifest> {
+    APP_DATA_PATH
+        .get()
+        .map(|path| connector_registry::scan_connectors(&path.join("connectors")))
+        .unwrap_or_default()
 }
 
+fn builtin_connector_path(connector_file: &str) -> Result<PathBuf, String> {
+    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+
+    let connector_path = if current_dir.ends_with("src-tauri") {
+        current_dir.join(format!("src/{}", connector_file))
+    } else {
+        current_dir.join(format!("src-tauri/src/{}", connector_file))
+    };
+
+    Ok(connector_path)
+}
+
+// Note: User connectors take precedence so that a built-in one can be overridden
+fn resolve_connector_path(ty: &str) -> Result<PathBuf, String> {
+    if let Some(manifest) = user_connectors().into_iter().find(|m| m.id == ty) {
+        return Ok(manifest.path);
+    }
+
+    match BUILTIN_CONNECTORS.iter().find(|(id, _, _)| *id == ty) {
+        Some((_, connector_file, _)) => builtin_connector_path(connector_file),
+        None => Err("Unknown connector type".to_string()),
+    }
+}
+
+fn validate_connector_type(ty: &str) -> Result<(), AppError> {
+    resolve_connector_path(ty)
+        .map(|_| ())
+        .map_err(|_| AppError::ValidationError("Unknown connector type".to_string()))
+}
+
+fn connector_manifests() -> Result<Vec<connector_registry::ConnectorManifest>, String> {
+    let user_connectors = user_connectors();
+
+    // Note: The built-in connectors read source files and write transcoded temp copies
+    let mut manifests = Vec::new();
+    for (id, connector_file, label) in BUILTIN_CONNECTORS {
+        if user_connectors.iter().all(|m| m.id != id) {
+            manifests.push(connector_registry::ConnectorManifest {
+                id: id.to_string(),
+                path: builtin_connector_path(connector_file)?,
+                label: label.to_string(),
+                icon: None,
+                version: None,
+                permissions: connector_registry::ConnectorPermissions {
+                    http: false,
+                    file_read: true,
+                    file_write: true,
+                    system_info: false,
+                },
+            });
+        }
+    }
+    manifests.extend(user_connectors);
+
+    Ok(manifests)
+}
+
+fn connector_permissions(ty: &str) -> connector_registry::ConnectorPermissions {
+    connector_manifests()
+        .ok()
+        .and_then(|manifests| manifests.into_iter().find(|m| m.id == ty))
+        .map(|manifest| manifest.permissions)
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+async fn list_connector_types() -> Result<String, AppError> {
+    let connector_types = connector_manifests().map_err(AppError::IoError)?;
+
+    let result = serde_json::json!({
+        "connector_types": connector_types
+    });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn describe_connector(ty: String) -> Result<String, AppError> {
+    let manifest = connector_manifests()
+        .map_err(AppError::IoError)?
+        .into_iter()
+        .find(|m| m.id == ty)
+        .ok_or_else(|| AppError::ValidationError("Unknown connector type".to_string()))?;
+
+    serde_json::to_string(&manifest).map_err(|e| AppError::ConnectorError(e.to_string()))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum NullRepr {
+    #[default]
+    JsonNull,
+    EmptyString,
+    NullString,
+}
+
+fn null_to_json(repr: NullRepr) -> serde_json::Value {
+    match repr {
+        NullRepr::JsonNull => serde_json::Value::Null,
+        NullRepr::EmptyString => serde_json::Value::String(String::new()),
+        NullRepr::NullString => serde_json::Value::String("NULL".to_string()),
+    }
+}
+
+#[tauri::command]
+async fn set_null_representation(repr: NullRepr) -> Result<(), AppError> {
+    NULL_REPRESENTATION
+        .set(repr)
+        .map_err(|_| AppError::ValidationError("Null representation already set".to_string()))
+}
+
+// Note: In strict mode node graphs may only name columns and tables, never SQL expressions
 #[tauri::command]
-async fn discovery(ty: String, config: String) -> Result<String, String> {
-    if ty != connector_type::LOCAL_FILE_CSV && ty != connector_type::LOCAL_FILE_JSON {
-        return Err("Unknown connector type".to_string());
+async fn set_strict_mode(enabled: bool) -> Result<(), AppError> {
+    STRICT_MODE
+        .set(enabled)
+        .map_err(|_| AppError::ValidationError("Strict mode already set".to_string()))
+}
+
+fn generate_query_sql(
+    graph: &query_builder::NodeGraph,
+    pagination: Option<(i64, i64)>,
+) -> Result<String, AppError> {
+    let options = query_builder::QueryOptions {
+        strict: STRICT_MODE.get().copied().unwrap_or(false),
+        ..Default::default()
+    };
+
+    query_builder::generate_sql_with_options(graph, pagination, options)
+        .map_err(AppError::ValidationError)
+}
+
+fn duckdb_value_to_json(value: duckdb::types::ValueRef) -> serde_json::Value {
+    match value {
+        duckdb::types::ValueRef::Null => {
+            null_to_json(NULL_REPRESENTATION.get().copied().unwrap_or_default())
+        }
+        duckdb::types::ValueRef::Boolean(b) => serde_json::Value::Bool(b),
+        duckdb::types::ValueRef::TinyInt(i) => serde_json::Value::Number(i.into()),
+        duckdb::types::ValueRef::SmallInt(i) => serde_json::Value::Number(i.into()),
+        duckdb::types::ValueRef::Int(i) => serde_json::Value::Number(i.into()),
+        duckdb::types::ValueRef::BigInt(i) => serde_json::Value::Number(i.into()),
+        duckdb::types::ValueRef::HugeInt(i) => serde_json::Value::Number((i as i64).into()),
+        duckdb::types::ValueRef::Float(f) => serde_json::Number::from_f64(f as f64)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        duckdb::types::ValueRef::Double(d) => serde_json::Number::from_f64(d)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        duckdb::types::ValueRef::Text(s) => {
+            serde_json::Value::String(String::from_utf8_lossy(s).to_string())
+        }
+        duckdb::types::ValueRef::Date32(days) => {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+            let date = epoch + chrono::Duration::days(days as i64);
+            serde_json::Value::String(date.format("%Y-%m-%d").to_string())
+        }
+        // Note: duckdb-rs also reports TIMESTAMPTZ as Timestamp, holding the UTC instant
+        duckdb::types::ValueRef::Timestamp(unit, t) => {
+            match chrono::DateTime::<chrono::Utc>::from_timestamp_micros(unit.to_micros(t)) {
+                Some(dt) => serde_json::Value::String(
+                    dt.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+                ),
+                None => serde_json::Value::String(format!("{:?}", value)),
+            }
+        }
+        _ => serde_json::Value::String(format!("{:?}", value)),
+    }
+}
+
+fn json_to_duckdb_value(value: &serde_json::Value) -> Result<duckdb::types::Value, String> {
+    match value {
+        serde_json::Value::Null => Ok(duckdb::types::Value::Null),
+        serde_json::Value::Bool(b) => Ok(duckdb::types::Value::Boolean(*b)),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(duckdb::types::Value::BigInt(i))
+            } else if let Some(f) = n.as_f64() {
+                Ok(duckdb::types::Value::Double(f))
+            } else {
+                Err(format!("Unsupported number: {}", n))
+            }
+        }
+        serde_json::Value::String(s) => Ok(duckdb::types::Value::Text(s.clone())),
+        _ => Err(format!("Unsupported value type: {:?}", value)),
     }
+}
+
+async fn load_runtime_js(
+    runtime: &mut deno_core::JsRuntime,
+    current_dir: &std::path::Path,
+) -> Result<(), String> {
+    let runtime_js_path = if current_dir.ends_with("src-tauri") {
+        current_dir.join("src/runtime.js")
+    } else {
+        current_dir.join("src-tauri/src/runtime.js")
+    };
+
+    let runtime_js_url =
+        deno_core::ModuleSpecifier::from_file_path(&runtime_js_path).map_err(|_| {
+            format!(
+                "Failed to convert runtime.js path to URL: {}",
+                runtime_js_path.display()
+            )
+        })?;
+
+    let runtime_id = runtime
+        .load_side_es_module(&runtime_js_url)
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to load runtime.js from {}: {}",
+                runtime_js_path.display(),
+                e
+            )
+        })?;
+    let runtime_eval = runtime.mod_evaluate(runtime_id);
+    runtime
+        .run_event_loop(Default::default())
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to run event loop for runtime.js ({}): {}",
+                runtime_js_path.display(),
+                e
+            )
+        })?;
+    runtime_eval.await.map_err(|e| {
+        format!(
+            "Failed to evaluate runtime.js ({}): {}",
+            runtime_js_path.display(),
+            e
+        )
+    })?;
+
+    Ok(())
+}
+
+async fn execute_deno_module(
+    runtime: &mut deno_core::JsRuntime,
+    module_path: &deno_core::ModuleSpecifier,
+) -> Result<(), String> {
+    let id = runtime
+        .load_main_es_module(module_path)
+        .await
+        .map_err(|e| format!("Failed to load module: {}", e))?;
+    let eval = runtime.mod_evaluate(id);
+    runtime
+        .run_event_loop(Default::default())
+        .await
+        .map_err(|e| format!("Failed to run event loop: {}", e))?;
+    eval.await
+        .map_err(|e| format!("Failed to evaluate module: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn config(ty: String) -> Result<String, AppError> {
+    validate_connector_type(&ty)?;
 
     tokio::task::spawn_blocking(move || {
         use deno_core::{JsRuntime, RuntimeOptions};
         use std::rc::Rc;
 
-        let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
         let connector_path = resolve_connector_path(&ty)?;
+        let permissions = connector_permissions(&ty);
 
-        if !connector_path.exists() {
-            return Err(format!("Connector file not found: {:?}", connector_path));
-        }
-
-        let result_file_path = std::env::temp_dir().join("streaksight_discovery_result.json");
-        let temp_js_path = std::env::temp_dir().join("streaksight_discovery_temp.js");
+        let result_file_path = temp_dir().join("streaksight_config_result.json");
 
         let temp_js = format!(
-            r#"import {{ discovery }} from "{}";
-               const configObj = JSON.parse(`{}`);
-               const result = await discovery(configObj);
+            r#"import {{ config }} from "{}";
+               const result = config();
                const resultJson = JSON.stringify(result);
                await streaksight.writeFile("{}", resultJson);"#,
-            connector_path.to_string_lossy().replace('\\', "/"),
-            config.replace('\\', "\\\\").replace('`', "\\`"),
-            result_file_path.to_string_lossy().replace('\\', "/")
+            connector_path.to_str().unwrap().replace("\\", "/"),
+            result_file_path.to_str().unwrap().replace("\\", "/")
         );
 
+        let temp_js_path = temp_dir().join("streaksight_config_temp.js");
         std::fs::write(&temp_js_path, temp_js)
-            .map_err(|e| format!("Failed to write temp JS: {}", e))?;
+            .map_err(|e| format!("Failed to write temp JS file: {}", e))?;
+
+        let module_path = deno_core::ModuleSpecifier::from_file_path(&temp_js_path)
+            .map_err(|_| "Failed to convert temp path to URL".to_string())?;
 
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
@@ -305,11 +1036,7 @@ async fn discovery(ty: String, config: String) -> Result<String, String> {
                 extensions: vec![streaksight_ext::init()],
                 ..Default::default()
             });
-
-            load_runtime_js(&mut runtime, &current_dir).await?;
-
-            let module_path = deno_core::ModuleSpecifier::from_file_path(&temp_js_path)
-                .map_err(|_| "Failed to convert temp path to URL".to_string())?;
+            runtime.op_state().borrow_mut().put(permissions);
 
             execute_deno_module(&mut runtime, &module_path).await?;
 
@@ -323,80 +1050,801 @@ async fn discovery(ty: String, config: String) -> Result<String, String> {
         })
     })
     .await
-    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| AppError::ConnectorError(format!("Task join error: {}", e)))?
+    .map_err(AppError::ConnectorError)
 }
 
 #[tauri::command]
-async fn sync(ty: String, name: String, config: String, schema: String) -> Result<String, String> {
-    if ty != connector_type::LOCAL_FILE_CSV && ty != connector_type::LOCAL_FILE_JSON {
-        return Err("Unknown connector type".to_string());
-    }
+async fn discovery(ty: String, config: String) -> Result<String, AppError> {
+    validate_connector_type(&ty)?;
 
-    tokio::task::spawn_blocking(move || {
-        use deno_core::{JsRuntime, RuntimeOptions};
-        use std::rc::Rc;
+    tokio::task::spawn_blocking(move || run_discovery(&ty, &config))
+        .await
+        .map_err(|e| AppError::ConnectorError(format!("Task join error: {}", e)))?
+        .map_err(AppError::ConnectorError)
+}
 
-        let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-        let connector_path = resolve_connector_path(&ty)?;
+fn run_discovery(ty: &str, config: &str) -> Result<String, String> {
+    use deno_core::{JsRuntime, RuntimeOptions};
+    use std::rc::Rc;
 
-        if !connector_path.exists() {
-            return Err(format!("Connector file not found: {:?}", connector_path));
-        }
+    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+    let connector_path = resolve_connector_path(ty)?;
 
-        let temp_js_path = std::env::temp_dir().join("streaksight_sync_temp.js");
+    if !connector_path.exists() {
+        return Err(format!("Connector file not found: {:?}", connector_path));
+    }
 
-        let temp_js = format!(
-            r#"import {{ sync }} from "{}";
-               const configObj = JSON.parse(`{}`);
-               const schemaObj = JSON.parse(`{}`);
-               await sync("{}", configObj, schemaObj);"#,
-            connector_path.to_string_lossy().replace('\\', "/"),
-            config.replace('\\', "\\\\").replace('`', "\\`"),
-            schema.replace('\\', "\\\\").replace('`', "\\`"),
-            name.replace('"', "\\\"")
-        );
+    let permissions = connector_permissions(ty);
+
+    // Note: Each discovery gets its own temp file so that bulk discovery can run them in parallel
+    let discovery_id = DISCOVERY_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let temp_js_path = temp_dir().join(format!("streaksight_discovery_temp_{}.js", discovery_id));
+    let result_file_path = temp_dir().join(format!(
+        "streaksight_discovery_result_{}.json",
+        discovery_id
+    ));
+
+    let temp_js = format!(
+        r#"import {{ discovery }} from "{}";
+           const configObj = JSON.parse(`{}`);
+           const result = await discovery(configObj);
+           const resultJson = JSON.stringify(result);
+           await streaksight.writeFile("{}", resultJson);"#,
+        connector_path.to_string_lossy().replace('\\', "/"),
+        config.replace('\\', "\\\\").replace('`', "\\`"),
+        result_file_path.to_string_lossy().replace('\\', "/")
+    );
+
+    std::fs::write(&temp_js_path, temp_js)
+        .map_err(|e| format!("Failed to write temp JS: {}", e))?;
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to create runtime: {}", e))?;
+
+    let local = tokio::task::LocalSet::new();
+    local.block_on(&rt, async move {
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        runtime.op_state().borrow_mut().put(permissions);
 
-        std::fs::write(&temp_js_path, temp_js)
-            .map_err(|e| format!("Failed to write temp JS: {}", e))?;
+        load_runtime_js(&mut runtime, &current_dir).await?;
 
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .map_err(|e| format!("Failed to create runtime: {}", e))?;
+        let module_path = deno_core::ModuleSpecifier::from_file_path(&temp_js_path)
+            .map_err(|_| "Failed to convert temp path to URL".to_string())?;
 
-        let local = tokio::task::LocalSet::new();
-        local.block_on(&rt, async move {
-            let mut runtime = JsRuntime::new(RuntimeOptions {
-                module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
-                extensions: vec![streaksight_ext::init()],
-                ..Default::default()
-            });
+        execute_deno_module(&mut runtime, &module_path).await?;
 
-            load_runtime_js(&mut runtime, &current_dir).await?;
+        let json_str = std::fs::read_to_string(&result_file_path)
+            .map_err(|e| format!("Failed to read result file: {}", e))?;
 
-            let module_path = deno_core::ModuleSpecifier::from_file_path(&temp_js_path)
-                .map_err(|_| "Failed to convert temp path to URL".to_string())?;
+        let _ = std::fs::remove_file(&temp_js_path);
+        let _ = std::fs::remove_file(&result_file_path);
 
-            execute_deno_module(&mut runtime, &module_path).await?;
+        Ok(json_str)
+    })
+}
 
-            let _ = std::fs::remove_file(&temp_js_path);
+const DEFAULT_DISCOVERY_PARALLELISM: usize = 4;
 
-            Ok("Sync completed successfully".to_string())
-        })
-    })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?
+#[derive(Debug, Deserialize)]
+struct DiscoveryRequest {
+    ty: String,
+    name: String,
+    config: String,
 }
 
 #[tauri::command]
-async fn tables() -> Result<String, String> {
-    let conn = duckdb_connect().map_err(|e| e.to_string())?;
+async fn bulk_discovery(
+    requests: Vec<DiscoveryRequest>,
+    parallelism: Option<usize>,
+) -> Result<String, AppError> {
+    let parallelism = parallelism.unwrap_or(DEFAULT_DISCOVERY_PARALLELISM).max(1);
+    let mut results: Vec<serde_json::Value> = vec![serde_json::Value::Null; requests.len()];
+    let mut names = Vec::with_capacity(requests.len());
+    let mut tasks: tokio::task::JoinSet<(usize, Result<String, AppError>)> =
+        tokio::task::JoinSet::new();
+
+    for (index, req) in requests.into_iter().enumerate() {
+        names.push(req.name);
+        if tasks.len() >= parallelism {
+            if let Some(joined) = tasks.join_next().await {
+                let (index, result) = joined
+                    .map_err(|e| AppError::ConnectorError(format!("Task join error: {}", e)))?;
+                results[index] = discovery_result(&names[index], result);
+            }
+        }
+        tasks.spawn_blocking(move || {
+            let result = validate_connector_type(&req.ty).and_then(|()| {
+                run_discovery(&req.ty, &req.config).map_err(AppError::ConnectorError)
+            });
+            (index, result)
+        });
+    }
 
-    let mut stmt = conn
-        .prepare("SELECT table_name FROM information_schema.tables WHERE table_schema = 'main'")
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    while let Some(joined) = tasks.join_next().await {
+        let (index, result) =
+            joined.map_err(|e| AppError::ConnectorError(format!("Task join error: {}", e)))?;
+        results[index] = discovery_result(&names[index], result);
+    }
 
-    let tables: Vec<serde_json::Value> = stmt
+    Ok(serde_json::Value::Array(results).to_string())
+}
+
+fn discovery_result(name: &str, result: Result<String, AppError>) -> serde_json::Value {
+    let parsed = result.and_then(|json_str| {
+        serde_json::from_str::<serde_json::Value>(&json_str).map_err(|e| {
+            AppError::ConnectorError(format!("Failed to parse discovery result: {}", e))
+        })
+    });
+
+    match parsed {
+        Ok(schema) => serde_json::json!({ "name": name, "schema": schema, "error": null }),
+        Err(error) => serde_json::json!({ "name": name, "schema": null, "error": error }),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ConflictResolution {
+    Replace,
+    Ignore,
+    #[default]
+    Fail,
+}
+
+impl ConflictResolution {
+    fn insert_statement(self) -> &'static str {
+        match self {
+            ConflictResolution::Replace => "INSERT OR REPLACE INTO",
+            ConflictResolution::Ignore => "INSERT OR IGNORE INTO",
+            ConflictResolution::Fail => "INSERT INTO",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SchemaEvolution {
+    #[default]
+    Strict,
+    AddNewColumns,
+    DropRemovedColumns,
+    FullSync,
+}
+
+impl SchemaEvolution {
+    fn adds_columns(self) -> bool {
+        matches!(
+            self,
+            SchemaEvolution::AddNewColumns | SchemaEvolution::FullSync
+        )
+    }
+
+    fn drops_columns(self) -> bool {
+        matches!(
+            self,
+            SchemaEvolution::DropRemovedColumns | SchemaEvolution::FullSync
+        )
+    }
+}
+
+// Note: Removed columns that are kept are filled with NULL; new columns that are not added
+// cannot be merged, so they always fail the sync
+fn evolve_table_schema(
+    tx: &Connection,
+    table_name: &str,
+    source_columns: &[(String, String)],
+    schema_evolution: SchemaEvolution,
+) -> Result<(), AppError> {
+    let table = quote_identifier(table_name);
+    let target_columns = describe_table(tx, &table)?;
+
+    let new_columns: Vec<&(String, String)> = source_columns
+        .iter()
+        .filter(|(name, _)| !target_columns.iter().any(|(target, _)| target == name))
+        .collect();
+    let removed_columns: Vec<&str> = target_columns
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .filter(|name| {
+            *name != ROW_HASH_COLUMN && !source_columns.iter().any(|(source, _)| source == name)
+        })
+        .collect();
+
+    let mut rejected = Vec::new();
+    if !new_columns.is_empty() && !schema_evolution.adds_columns() {
+        let names: Vec<&str> = new_columns.iter().map(|(name, _)| name.as_str()).collect();
+        rejected.push(format!("new columns: {}", names.join(", ")));
+    }
+    if !removed_columns.is_empty() && matches!(schema_evolution, SchemaEvolution::Strict) {
+        rejected.push(format!("removed columns: {}", removed_columns.join(", ")));
+    }
+    if !rejected.is_empty() {
+        return Err(AppError::ValidationError(format!(
+            "Source schema of {} changed ({})",
+            table_name,
+            rejected.join("; ")
+        )));
+    }
+
+    if schema_evolution.adds_columns() {
+        for (name, ty) in new_columns {
+            tx.execute(
+                &format!(
+                    "ALTER TABLE {} ADD COLUMN {} {}",
+                    table,
+                    quote_identifier(name),
+                    ty
+                ),
+                [],
+            )
+            .map_err(|e| AppError::DbError(format!("Failed to add column {}: {}", name, e)))?;
+        }
+    }
+    if schema_evolution.drops_columns() {
+        for name in removed_columns {
+            tx.execute(
+                &format!(
+                    "ALTER TABLE {} DROP COLUMN {}",
+                    table,
+                    quote_identifier(name)
+                ),
+                [],
+            )
+            .map_err(|e| AppError::DbError(format!("Failed to drop column {}: {}", name, e)))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_primary_keys(schema: &str, primary_keys: &[String]) -> Result<(), AppError> {
+    let schema: serde_json::Value = serde_json::from_str(schema)
+        .map_err(|e| AppError::ValidationError(format!("Invalid schema: {}", e)))?;
+    let columns: Vec<&str> = schema["columns"]
+        .as_array()
+        .map(|columns| {
+            columns
+                .iter()
+                .filter(|c| !c["skip"].as_bool().unwrap_or(false))
+                .filter_map(|c| c["name"].as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    match primary_keys
+        .iter()
+        .find(|pk| !columns.contains(&pk.as_str()))
+    {
+        Some(pk) => Err(AppError::ValidationError(format!(
+            "Primary key column not found in schema: {}",
+            pk
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn merge_staging_table(
+    conn: &mut Connection,
+    table_name: &str,
+    staging_name: &str,
+    primary_keys: &[String],
+    on_conflict: ConflictResolution,
+    schema_evolution: SchemaEvolution,
+) -> Result<(), AppError> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::DbError(format!("Failed to begin transaction: {}", e)))?;
+
+    merge_staging_rows(
+        &tx,
+        table_name,
+        staging_name,
+        primary_keys,
+        on_conflict,
+        schema_evolution,
+    )?;
+
+    tx.commit()
+        .map_err(|e| AppError::DbError(format!("Failed to commit transaction: {}", e)))
+}
+
+fn merge_staging_rows(
+    tx: &Connection,
+    table_name: &str,
+    staging_name: &str,
+    primary_keys: &[String],
+    on_conflict: ConflictResolution,
+    schema_evolution: SchemaEvolution,
+) -> Result<(), AppError> {
+    let columns: Vec<(String, String)> = tx
+        .prepare(&format!("DESCRIBE {}", quote_identifier(staging_name)))
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| AppError::DbError(format!("Failed to describe staging table: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+
+    let table_exists: bool = tx
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM information_schema.tables \
+             WHERE table_schema = 'main' AND table_name = ?",
+            [table_name],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to check table: {}", e)))?;
+
+    if !table_exists {
+        let column_defs: Vec<String> = columns
+            .iter()
+            .map(|(name, ty)| format!("{} {}", quote_identifier(name), ty))
+            .collect();
+        let key_list: Vec<String> = primary_keys.iter().map(|pk| quote_identifier(pk)).collect();
+        tx.execute(
+            &format!(
+                "CREATE TABLE {} ({}, PRIMARY KEY ({}))",
+                quote_identifier(table_name),
+                column_defs.join(", "),
+                key_list.join(", ")
+            ),
+            [],
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to create table: {}", e)))?;
+    } else {
+        evolve_table_schema(tx, table_name, &columns, schema_evolution)?;
+    }
+
+    let column_list = columns
+        .iter()
+        .map(|(name, _)| quote_identifier(name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    tx.execute(
+        &format!(
+            "{} {} ({}) SELECT {} FROM {}",
+            on_conflict.insert_statement(),
+            quote_identifier(table_name),
+            column_list,
+            column_list,
+            quote_identifier(staging_name)
+        ),
+        [],
+    )
+    .map_err(|e| AppError::DbError(format!("Failed to merge synced rows: {}", e)))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn sync(
+    ty: String,
+    name: String,
+    config: String,
+    schema: String,
+    on_error: Option<ErrorHandling>,
+    primary_keys: Option<Vec<String>>,
+    on_conflict: Option<ConflictResolution>,
+    include_row_hash: Option<bool>,
+    schema_evolution: Option<SchemaEvolution>,
+) -> Result<String, AppError> {
+    validate_connector_type(&ty)?;
+
+    let primary_keys = primary_keys.unwrap_or_default();
+    if !primary_keys.is_empty() {
+        validate_primary_keys(&schema, &primary_keys)?;
+    }
+
+    // Note: With primary keys the connector loads into a staging table that is merged afterwards
+    let table_name = name.clone();
+    let staging_name = format!("_streaksight_staging_{}", name);
+    let target_name = if primary_keys.is_empty() {
+        name
+    } else {
+        let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+        conn.execute(
+            &format!("DROP TABLE IF EXISTS {}", quote_identifier(&staging_name)),
+            [],
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to drop staging table: {}", e)))?;
+        staging_name.clone()
+    };
+
+    let rows_failed = tokio::task::spawn_blocking(move || {
+        run_sync(
+            ty,
+            target_name,
+            config,
+            schema,
+            on_error.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| AppError::ConnectorError(format!("Task join error: {}", e)))?
+    .map_err(AppError::ConnectorError)?;
+
+    if !primary_keys.is_empty() {
+        let mut conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+        let merged = merge_staging_table(
+            &mut conn,
+            &table_name,
+            &staging_name,
+            &primary_keys,
+            on_conflict.unwrap_or_default(),
+            schema_evolution.unwrap_or_default(),
+        );
+        let _ = conn.execute(
+            &format!("DROP TABLE IF EXISTS {}", quote_identifier(&staging_name)),
+            [],
+        );
+        merged?;
+    }
+    if include_row_hash.unwrap_or(false) {
+        let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+        add_row_hash(&conn, &table_name)?;
+    }
+    invalidate_query_cache();
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let rows_synced: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM {}", quote_identifier(&table_name)),
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to count synced rows: {}", e)))?;
+
+    let result = serde_json::json!({
+        "rows_synced": rows_synced,
+        "rows_failed": rows_failed
+    });
+
+    Ok(result.to_string())
+}
+
+const ROW_HASH_COLUMN: &str = "_row_hash";
+
+// Note: The hash covers every other column and is recomputed for all rows on each sync
+fn add_row_hash(conn: &Connection, table_name: &str) -> Result<(), AppError> {
+    let table = quote_identifier(table_name);
+    conn.execute(
+        &format!(
+            "ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} VARCHAR",
+            table,
+            quote_identifier(ROW_HASH_COLUMN)
+        ),
+        [],
+    )
+    .map_err(|e| AppError::DbError(format!("Failed to add row hash column: {}", e)))?;
+
+    let columns: Vec<String> = describe_table(conn, &table)?
+        .into_iter()
+        .filter(|(name, _)| name != ROW_HASH_COLUMN)
+        .map(|(name, _)| quote_identifier(&name))
+        .collect();
+    conn.execute(
+        &format!(
+            "UPDATE {} SET {} = md5(CAST(ROW({}) AS VARCHAR))",
+            table,
+            quote_identifier(ROW_HASH_COLUMN),
+            columns.join(", ")
+        ),
+        [],
+    )
+    .map_err(|e| AppError::DbError(format!("Failed to compute row hashes: {}", e)))?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ErrorHandling {
+    #[default]
+    Abort,
+    Skip,
+    CollectAndReport,
+}
+
+fn run_sync(
+    ty: String,
+    name: String,
+    config: String,
+    schema: String,
+    on_error: ErrorHandling,
+) -> Result<Option<i64>, String> {
+    use deno_core::{JsRuntime, RuntimeOptions};
+    use std::rc::Rc;
+
+    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+    let connector_path = resolve_connector_path(&ty)?;
+    let permissions = connector_permissions(&ty);
+
+    if !connector_path.exists() {
+        return Err(format!("Connector file not found: {:?}", connector_path));
+    }
+
+    // Note: Each sync gets its own temp file so that parallel syncs do not overwrite each other
+    let sync_id = SYNC_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let temp_js_path = temp_dir().join(format!("streaksight_sync_temp_{}.js", sync_id));
+    let result_file_path = temp_dir().join(format!("streaksight_sync_result_{}.json", sync_id));
+    let on_error = serde_json::to_string(&on_error).map_err(|e| e.to_string())?;
+
+    let temp_js = format!(
+        r#"import {{ sync }} from "{}";
+           const configObj = JSON.parse(`{}`);
+           const schemaObj = JSON.parse(`{}`);
+           const result = await sync("{}", configObj, schemaObj, {{ onError: {} }});
+           await streaksight.writeFile("{}", JSON.stringify(result ?? {{}}));"#,
+        connector_path.to_string_lossy().replace('\\', "/"),
+        config.replace('\\', "\\\\").replace('`', "\\`"),
+        schema.replace('\\', "\\\\").replace('`', "\\`"),
+        name.replace('"', "\\\""),
+        on_error,
+        result_file_path.to_string_lossy().replace('\\', "/")
+    );
+
+    std::fs::write(&temp_js_path, temp_js)
+        .map_err(|e| format!("Failed to write temp JS: {}", e))?;
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to create runtime: {}", e))?;
+
+    let local = tokio::task::LocalSet::new();
+    local.block_on(&rt, async move {
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        runtime.op_state().borrow_mut().put(permissions);
+
+        load_runtime_js(&mut runtime, &current_dir).await?;
+
+        let module_path = deno_core::ModuleSpecifier::from_file_path(&temp_js_path)
+            .map_err(|_| "Failed to convert temp path to URL".to_string())?;
+
+        execute_deno_module(&mut runtime, &module_path).await?;
+
+        let json_str = std::fs::read_to_string(&result_file_path)
+            .map_err(|e| format!("Failed to read result file: {}", e))?;
+
+        let _ = std::fs::remove_file(&temp_js_path);
+        let _ = std::fs::remove_file(&result_file_path);
+
+        let result: serde_json::Value = serde_json::from_str(&json_str)
+            .map_err(|e| format!("Failed to parse sync result: {}", e))?;
+
+        Ok(result["rowsFailed"].as_i64())
+    })
+}
+
+const DEFAULT_SYNC_PARALLELISM: usize = 4;
+
+#[derive(Debug, Deserialize)]
+struct SyncRequest {
+    ty: String,
+    name: String,
+    config: String,
+    schema: String,
+    #[serde(default)]
+    on_error: Option<ErrorHandling>,
+    #[serde(default)]
+    primary_keys: Option<Vec<String>>,
+    #[serde(default)]
+    on_conflict: Option<ConflictResolution>,
+    #[serde(default)]
+    include_row_hash: Option<bool>,
+    #[serde(default)]
+    schema_evolution: Option<SchemaEvolution>,
+}
+
+#[tauri::command]
+async fn sync_all(syncs: Vec<SyncRequest>, parallelism: Option<usize>) -> Result<String, AppError> {
+    let parallelism = parallelism.unwrap_or(DEFAULT_SYNC_PARALLELISM).max(1);
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(parallelism));
+
+    let handles: Vec<_> = syncs
+        .into_iter()
+        .map(|req| {
+            let name = req.name.clone();
+            let semaphore = semaphore.clone();
+            let handle = tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await.map_err(|e| {
+                    AppError::ConnectorError(format!("Failed to acquire sync slot: {}", e))
+                })?;
+                sync(
+                    req.ty,
+                    req.name,
+                    req.config,
+                    req.schema,
+                    req.on_error,
+                    req.primary_keys,
+                    req.on_conflict,
+                    req.include_row_hash,
+                    req.schema_evolution,
+                )
+                .await
+            });
+            (name, handle)
+        })
+        .collect();
+
+    let mut successes = Vec::new();
+    let mut failures = Vec::new();
+
+    for (name, handle) in handles {
+        let result = handle
+            .await
+            .map_err(|e| AppError::ConnectorError(format!("Task join error: {}", e)))
+            .and_then(|r| r);
+
+        match result {
+            Ok(_) => successes.push(name),
+            Err(error) => failures.push(serde_json::json!({
+                "name": name,
+                "error": error
+            })),
+        }
+    }
+
+    let result = serde_json::json!({
+        "successes": successes,
+        "failures": failures
+    });
+
+    Ok(result.to_string())
+}
+
+fn drop_staging_tables(conn: &Connection, staging_names: &[String]) {
+    for staging_name in staging_names {
+        let _ = conn.execute(
+            &format!("DROP TABLE IF EXISTS {}", quote_identifier(staging_name)),
+            [],
+        );
+    }
+}
+
+fn swap_staging_tables(
+    conn: &mut Connection,
+    syncs: &[SyncRequest],
+    staging_names: &[String],
+    rows_failed: Vec<Option<i64>>,
+) -> Result<Vec<serde_json::Value>, AppError> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::DbError(format!("Failed to begin transaction: {}", e)))?;
+
+    let mut synced = Vec::with_capacity(syncs.len());
+    for ((req, staging_name), rows_failed) in syncs.iter().zip(staging_names).zip(rows_failed) {
+        match req.primary_keys.as_deref() {
+            Some(primary_keys) if !primary_keys.is_empty() => merge_staging_rows(
+                &tx,
+                &req.name,
+                staging_name,
+                primary_keys,
+                req.on_conflict.unwrap_or_default(),
+                req.schema_evolution.unwrap_or_default(),
+            )?,
+            _ => {
+                tx.execute(
+                    &format!("DROP TABLE IF EXISTS {}", quote_identifier(&req.name)),
+                    [],
+                )
+                .map_err(|e| AppError::DbError(format!("Failed to drop table: {}", e)))?;
+                tx.execute(
+                    &format!(
+                        "ALTER TABLE {} RENAME TO {}",
+                        quote_identifier(staging_name),
+                        quote_identifier(&req.name)
+                    ),
+                    [],
+                )
+                .map_err(|e| AppError::DbError(format!("Failed to rename staging table: {}", e)))?;
+            }
+        }
+
+        if req.include_row_hash.unwrap_or(false) {
+            add_row_hash(&tx, &req.name)?;
+        }
+
+        let rows_synced: i64 = tx
+            .query_row(
+                &format!("SELECT COUNT(*) FROM {}", quote_identifier(&req.name)),
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| AppError::DbError(format!("Failed to count synced rows: {}", e)))?;
+
+        synced.push(serde_json::json!({
+            "name": req.name,
+            "rows_synced": rows_synced,
+            "rows_failed": rows_failed
+        }));
+    }
+
+    tx.commit()
+        .map_err(|e| AppError::DbError(format!("Failed to commit transaction: {}", e)))?;
+    Ok(synced)
+}
+
+// Note: Connectors write through their own connections, so every sync loads into a
+// staging table first and only the final swap into the target tables is transactional
+#[tauri::command]
+async fn multi_table_sync(syncs: Vec<SyncRequest>) -> Result<String, AppError> {
+    let mut names = std::collections::HashSet::new();
+    for req in &syncs {
+        validate_connector_type(&req.ty)?;
+        if !names.insert(req.name.as_str()) {
+            return Err(AppError::ValidationError(format!(
+                "Duplicate sync target: {}",
+                req.name
+            )));
+        }
+        if let Some(primary_keys) = req.primary_keys.as_ref().filter(|pks| !pks.is_empty()) {
+            validate_primary_keys(&req.schema, primary_keys)?;
+        }
+    }
+
+    let staging_names: Vec<String> = syncs
+        .iter()
+        .map(|req| format!("_streaksight_staging_{}", req.name))
+        .collect();
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    drop_staging_tables(&conn, &staging_names);
+    drop(conn);
+
+    let mut rows_failed = Vec::with_capacity(syncs.len());
+    for (req, staging_name) in syncs.iter().zip(&staging_names) {
+        let (ty, config, schema) = (req.ty.clone(), req.config.clone(), req.schema.clone());
+        let target_name = staging_name.clone();
+        let on_error = req.on_error.unwrap_or_default();
+        let result = tokio::task::spawn_blocking(move || {
+            run_sync(ty, target_name, config, schema, on_error)
+        })
+        .await
+        .map_err(|e| AppError::ConnectorError(format!("Task join error: {}", e)))
+        .and_then(|r| {
+            r.map_err(|e| AppError::ConnectorError(format!("Sync failed for {}: {}", req.name, e)))
+        });
+
+        match result {
+            Ok(failed) => rows_failed.push(failed),
+            Err(error) => {
+                let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+                drop_staging_tables(&conn, &staging_names);
+                return Err(error);
+            }
+        }
+    }
+
+    let mut conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let swapped = swap_staging_tables(&mut conn, &syncs, &staging_names, rows_failed);
+    drop_staging_tables(&conn, &staging_names);
+    invalidate_query_cache();
+
+    let result = serde_json::json!({ "synced": swapped? });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn tables() -> Result<String, AppError> {
+    let conn = duckdb_connect_readonly().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let mut stmt = conn
+        .prepare("SELECT table_name FROM information_schema.tables WHERE table_schema = 'main'")
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
+
+    let tables: Vec<serde_json::Value> = stmt
         .query_map([], |row| {
             let name: String = row.get(0)?;
             Ok(serde_json::json!({
@@ -404,9 +1852,9 @@ async fn tables() -> Result<String, String> {
                 "row_count": 0
             }))
         })
-        .map_err(|e| format!("Failed to query tables: {}", e))?
+        .map_err(|e| AppError::DbError(format!("Failed to query tables: {}", e)))?
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect results: {}", e))?;
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
 
     let result = serde_json::json!({
         "tables": tables
@@ -415,41 +1863,83 @@ async fn tables() -> Result<String, String> {
     Ok(result.to_string())
 }
 
+fn map_column_type(column_type: &str) -> &'static str {
+    match column_type.to_uppercase().as_str() {
+        t if t.contains("INT")
+            || t.contains("DOUBLE")
+            || t.contains("FLOAT")
+            || t.contains("DECIMAL") =>
+        {
+            "number"
+        }
+        t if t.contains("BOOL") => "boolean",
+        t if t.contains("DATE") || t.contains("TIME") => "date",
+        _ => "string",
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TypeMapping {
+    contains: String,
+    maps_to: String,
+}
+
+fn apply_type_mapping(column_type: &str, mappings: &[TypeMapping]) -> String {
+    let type_upper = column_type.to_uppercase();
+    mappings
+        .iter()
+        .find(|m| type_upper.contains(&m.contains.to_uppercase()))
+        .map(|m| m.maps_to.clone())
+        .unwrap_or_else(|| map_column_type(column_type).to_string())
+}
+
+fn type_mappings() -> &'static [TypeMapping] {
+    TYPE_MAPPINGS.get().map(Vec::as_slice).unwrap_or(&[])
+}
+
 #[tauri::command]
-async fn table_schema(table_name: String) -> Result<String, String> {
-    let conn = duckdb_connect().map_err(|e| e.to_string())?;
+async fn set_type_mappings(mappings: Vec<TypeMapping>) -> Result<(), AppError> {
+    for mapping in &mappings {
+        if mapping.contains.is_empty() {
+            return Err(AppError::ValidationError(
+                "Type mapping pattern must not be empty".to_string(),
+            ));
+        }
+        if !["number", "boolean", "date", "string"].contains(&mapping.maps_to.as_str()) {
+            return Err(AppError::ValidationError(format!(
+                "Invalid type mapping target: {}",
+                mapping.maps_to
+            )));
+        }
+    }
+
+    TYPE_MAPPINGS
+        .set(mappings)
+        .map_err(|_| AppError::ValidationError("Type mappings already set".to_string()))
+}
+
+#[tauri::command]
+async fn table_schema(table_name: String) -> Result<String, AppError> {
+    let conn = duckdb_connect_readonly().map_err(|e| AppError::DbError(e.to_string()))?;
 
     let query = format!("DESCRIBE {}", table_name);
     let mut stmt = conn
         .prepare(&query)
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
 
     let columns: Vec<serde_json::Value> = stmt
         .query_map([], |row| {
             let name: String = row.get(0)?;
             let column_type: String = row.get(1)?;
 
-            let mapped_type = match column_type.to_uppercase().as_str() {
-                t if t.contains("INT")
-                    || t.contains("DOUBLE")
-                    || t.contains("FLOAT")
-                    || t.contains("DECIMAL") =>
-                {
-                    "number"
-                }
-                t if t.contains("BOOL") => "boolean",
-                t if t.contains("DATE") || t.contains("TIME") => "date",
-                _ => "string",
-            };
-
             Ok(serde_json::json!({
                 "name": name,
-                "type": mapped_type
+                "type": apply_type_mapping(&column_type, type_mappings())
             }))
         })
-        .map_err(|e| format!("Failed to query schema: {}", e))?
+        .map_err(|e| AppError::DbError(format!("Failed to query schema: {}", e)))?
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect results: {}", e))?;
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
 
     let result = serde_json::json!({
         "table_name": table_name,
@@ -459,268 +1949,8599 @@ async fn table_schema(table_name: String) -> Result<String, String> {
     Ok(result.to_string())
 }
 
-#[tauri::command]
-async fn drop_table(table_name: String) -> Result<String, String> {
-    let conn = duckdb_connect().map_err(|e| e.to_string())?;
+fn describe_table(conn: &Connection, table_name: &str) -> Result<Vec<(String, String)>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!("DESCRIBE {}", table_name))
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
+
+    stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| AppError::DbError(format!("Failed to query schema: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))
+}
 
-    if !table_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
-        return Err("Invalid table name".to_string());
+struct TableColumn {
+    name: String,
+    data_type: String,
+    nullable: bool,
+    default: Option<String>,
+}
+
+fn build_create_table_sql(table_name: &str, columns: &[TableColumn]) -> Result<String, String> {
+    use sqlparser::ast::helpers::stmt_create_table::CreateTableBuilder;
+    use sqlparser::ast::{ColumnDef, ColumnOption, ColumnOptionDef, DataType, Ident, ObjectName};
+    use sqlparser::dialect::DuckDbDialect;
+    use sqlparser::parser::Parser;
+    use sqlparser::tokenizer::Token;
+
+    let dialect = DuckDbDialect {};
+    let ident = |name: &str| {
+        if is_valid_identifier(name) {
+            Ident::new(name)
+        } else {
+            Ident::with_quote('"', name)
+        }
+    };
+
+    let column_defs = columns
+        .iter()
+        .map(|column| {
+            // Note: Types sqlparser does not know are emitted verbatim
+            let data_type = Parser::new(&dialect)
+                .try_with_sql(&column.data_type)
+                .and_then(|mut parser| {
+                    let data_type = parser.parse_data_type()?;
+                    parser.expect_token(&Token::EOF)?;
+                    Ok(data_type)
+                })
+                .unwrap_or_else(|_| {
+                    DataType::Custom(
+                        ObjectName::from(vec![Ident::new(&column.data_type)]),
+                        vec![],
+                    )
+                });
+
+            let mut options = Vec::new();
+            if !column.nullable {
+                options.push(ColumnOptionDef {
+                    name: None,
+                    option: ColumnOption::NotNull,
+                });
+            }
+            if let Some(default) = &column.default {
+                let expr = Parser::new(&dialect)
+                    .try_with_sql(default)
+                    .and_then(|mut parser| parser.parse_expr())
+                    .map_err(|e| format!("Failed to parse default of {}: {}", column.name, e))?;
+                options.push(ColumnOptionDef {
+                    name: None,
+                    option: ColumnOption::Default(expr),
+                });
+            }
+
+            Ok(ColumnDef {
+                name: ident(&column.name),
+                data_type,
+                options,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(
+        CreateTableBuilder::new(ObjectName::from(vec![ident(table_name)]))
+            .columns(column_defs)
+            .build()
+            .to_string(),
+    )
+}
+
+#[tauri::command]
+async fn generate_create_table_sql(table_name: String) -> Result<String, AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
     }
 
-    let query = format!("DROP TABLE IF EXISTS {}", table_name);
-    conn.execute(&query, [])
-        .map_err(|e| format!("Failed to drop table: {}", e))?;
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
 
-    Ok(format!("Table {} dropped successfully", table_name))
+    let mut stmt = conn
+        .prepare(
+            "SELECT column_name, data_type, is_nullable = 'YES', column_default \
+             FROM information_schema.columns \
+             WHERE table_name = ? AND table_schema = current_schema() \
+             ORDER BY ordinal_position",
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
+
+    let columns: Vec<TableColumn> = stmt
+        .query_map([&table_name], |row| {
+            Ok(TableColumn {
+                name: row.get(0)?,
+                data_type: row.get(1)?,
+                nullable: row.get(2)?,
+                default: row.get(3)?,
+            })
+        })
+        .map_err(|e| AppError::DbError(format!("Failed to query columns: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+
+    if columns.is_empty() {
+        return Err(AppError::DbError(format!(
+            "Table not found: {}",
+            table_name
+        )));
+    }
+
+    build_create_table_sql(&table_name, &columns).map_err(AppError::DbError)
 }
 
+// Note: hash(*) does not bind in DuckDB, so each row is hashed as a struct through the alias
 #[tauri::command]
-async fn run_query(
-    node_graph: String,
-    page: Option<i32>,
-    page_size: Option<i32>,
-) -> Result<String, String> {
-    let graph: query_builder::NodeGraph = serde_json::from_str(&node_graph)
-        .map_err(|e| format!("Failed to parse node graph: {}", e))?;
+async fn get_table_checksum(table_name: String) -> Result<String, AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
 
-    let page = page.unwrap_or(1);
-    let page_size = page_size.unwrap_or(100);
-    let limit = page_size as i64;
-    let offset = ((page - 1) * page_size) as i64;
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    conn.query_row(
+        &format!(
+            "SELECT md5(COALESCE(string_agg(CAST(row_hash AS VARCHAR), ',' ORDER BY row_hash), '')) \
+             FROM (SELECT hash(t) AS row_hash FROM {} AS t)",
+            table_name
+        ),
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| AppError::DbError(format!("Failed to compute checksum: {}", e)))
+}
 
-    let sql = query_builder::generate_sql(&graph, Some((limit, offset)))?;
+#[tauri::command]
+async fn schema_diff(table_a: String, table_b: String) -> Result<String, AppError> {
+    if !is_valid_identifier(&table_a) || !is_valid_identifier(&table_b) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
 
-    let conn = duckdb_connect().map_err(|e| e.to_string())?;
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let columns_a = describe_table(&conn, &table_a)?;
+    let columns_b = describe_table(&conn, &table_b)?;
 
-    let column_names = {
-        let mut info_stmt = conn
-            .prepare(&sql)
-            .map_err(|e| format!("Failed to prepare SQL: {}", e))?;
-        info_stmt
-            .execute([])
-            .map_err(|e| format!("Failed to execute query: {}", e))?;
-        info_stmt.column_names()
+    let find_type = |columns: &[(String, String)], name: &str| {
+        columns
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, ty)| ty.clone())
     };
 
-    let mut stmt = conn
-        .prepare(&sql)
-        .map_err(|e| format!("Failed to prepare SQL: {}", e))?;
+    let added: Vec<serde_json::Value> = columns_b
+        .iter()
+        .filter(|(name, _)| find_type(&columns_a, name).is_none())
+        .map(|(name, ty)| serde_json::json!({ "name": name, "type": ty }))
+        .collect();
 
-    let mut rows_data = Vec::new();
-    let mut rows = stmt
-        .query([])
-        .map_err(|e| format!("Failed to execute query: {}", e))?;
+    let removed: Vec<serde_json::Value> = columns_a
+        .iter()
+        .filter(|(name, _)| find_type(&columns_b, name).is_none())
+        .map(|(name, ty)| serde_json::json!({ "name": name, "type": ty }))
+        .collect();
 
-    while let Some(row) = rows
-        .next()
-        .map_err(|e| format!("Failed to fetch row: {}", e))?
-    {
-        let mut row_obj = serde_json::Map::new();
-        for (i, col_name) in column_names.iter().enumerate() {
-            let value = match row.get_ref(i) {
-                Ok(val) => duckdb_value_to_json(val),
-                Err(_) => serde_json::Value::Null,
-            };
-            row_obj.insert(col_name.clone(), value);
+    let changed: Vec<serde_json::Value> = columns_a
+        .iter()
+        .filter_map(|(name, from)| match find_type(&columns_b, name) {
+            Some(to) if &to != from => {
+                Some(serde_json::json!({ "name": name, "from": from, "to": to }))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let result = serde_json::json!({
+        "added": added,
+        "removed": removed,
+        "changed": changed
+    });
+
+    Ok(result.to_string())
+}
+
+// Note: COUNT(DISTINCT) skips NULLs, so a column with any NULL can never match the row count
+#[tauri::command]
+async fn detect_primary_key_candidates(table_name: String) -> Result<String, AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let columns = describe_table(&conn, &table_name)?;
+
+    let counts_sql = format!(
+        "SELECT COUNT(*){} FROM {}",
+        columns
+            .iter()
+            .map(|(name, _)| format!(", COUNT(DISTINCT {})", quote_identifier(name)))
+            .collect::<String>(),
+        table_name
+    );
+    let counts: Vec<i64> = conn
+        .query_row(&counts_sql, [], |row| {
+            (0..=columns.len()).map(|i| row.get(i)).collect()
+        })
+        .map_err(|e| AppError::DbError(format!("Failed to count distinct values: {}", e)))?;
+    let total_count = counts[0];
+
+    let column_counts: Vec<serde_json::Value> = columns
+        .iter()
+        .zip(&counts[1..])
+        .map(|((name, _), distinct_count)| {
+            serde_json::json!({ "name": name, "distinct_count": distinct_count })
+        })
+        .collect();
+
+    let candidates: Vec<&str> = columns
+        .iter()
+        .zip(&counts[1..])
+        .filter(|(_, &distinct_count)| total_count > 0 && distinct_count == total_count)
+        .map(|((name, _), _)| name.as_str())
+        .collect();
+
+    let result = serde_json::json!({
+        "total_count": total_count,
+        "columns": column_counts,
+        "candidates": candidates
+    });
+
+    Ok(result.to_string())
+}
+
+const PIVOT_MAX_DISTINCT: i64 = 20;
+
+fn pivot_sql(table_name: &str, column: &str, group_by: Option<&str>) -> String {
+    match group_by {
+        Some(group_by) => format!(
+            "PIVOT {} ON {} USING COUNT(*) GROUP BY {}",
+            table_name,
+            quote_identifier(column),
+            quote_identifier(group_by)
+        ),
+        None => format!(
+            "PIVOT (SELECT {} FROM {}) ON {} USING COUNT(*)",
+            quote_identifier(column),
+            table_name,
+            quote_identifier(column)
+        ),
+    }
+}
+
+#[tauri::command]
+async fn suggest_pivot(table_name: String) -> Result<String, AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let columns: Vec<String> = describe_table(&conn, &table_name)?
+        .into_iter()
+        .filter(|(_, column_type)| column_type == "VARCHAR")
+        .map(|(name, _)| name)
+        .collect();
+
+    let mut low_cardinality = Vec::new();
+    if !columns.is_empty() {
+        let counts_sql = format!(
+            "SELECT {} FROM {}",
+            columns
+                .iter()
+                .map(|name| format!("COUNT(DISTINCT {})", quote_identifier(name)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            table_name
+        );
+        let counts: Vec<i64> = conn
+            .query_row(&counts_sql, [], |row| {
+                (0..columns.len()).map(|i| row.get(i)).collect()
+            })
+            .map_err(|e| AppError::DbError(format!("Failed to count distinct values: {}", e)))?;
+        low_cardinality = columns
+            .iter()
+            .zip(counts)
+            .filter(|(_, distinct_count)| (1..=PIVOT_MAX_DISTINCT).contains(distinct_count))
+            .collect();
+    }
+
+    // Note: Another low-cardinality column makes a natural row key for the pivot
+    let candidates: Vec<serde_json::Value> = low_cardinality
+        .iter()
+        .map(|(name, distinct_count)| {
+            let group_by = low_cardinality
+                .iter()
+                .map(|(other, _)| other.as_str())
+                .find(|other| other != name);
+            serde_json::json!({
+                "column": name,
+                "distinct_count": distinct_count,
+                "sql": pivot_sql(&table_name, name, group_by)
+            })
+        })
+        .collect();
+
+    let result = serde_json::json!({ "candidates": candidates });
+
+    Ok(result.to_string())
+}
+
+// Note: Dates are spread over the past year; the arithmetic avoids functions that need the ICU extension
+const RANDOM_PAST_MICROS: &str =
+    "epoch_us(get_current_timestamp()) - CAST(floor(random() * 31536000000000) AS BIGINT)";
+
+fn random_value_expr(column_type: &str) -> String {
+    let upper = column_type.to_uppercase();
+    match upper.as_str() {
+        "BOOLEAN" => "random() < 0.5".to_string(),
+        "VARCHAR" => "concat('val_', random()::TEXT)".to_string(),
+        "UUID" => "gen_random_uuid()".to_string(),
+        "DATE" => format!("CAST(make_timestamp({}) AS DATE)", RANDOM_PAST_MICROS),
+        "TIMESTAMP WITH TIME ZONE" => format!("to_timestamp(({}) / 1000000)", RANDOM_PAST_MICROS),
+        t if t.starts_with("TIMESTAMP") => {
+            format!(
+                "CAST(make_timestamp({}) AS {})",
+                RANDOM_PAST_MICROS, column_type
+            )
+        }
+        "FLOAT" | "DOUBLE" => format!("CAST(random() * 100 AS {})", column_type),
+        t if t.starts_with("DECIMAL") => format!("CAST(random() * 100 AS {})", column_type),
+        t if t.ends_with("INT") || t == "INTEGER" || t == "UINTEGER" => {
+            format!("CAST(floor(random() * 100) AS {})", column_type)
+        }
+        _ => "NULL".to_string(),
+    }
+}
+
+fn insert_random_rows(
+    conn: &Connection,
+    table_name: &str,
+    row_count: usize,
+) -> Result<usize, AppError> {
+    let columns = describe_table(conn, table_name)?;
+
+    let values: Vec<String> = columns
+        .iter()
+        .map(|(_, column_type)| random_value_expr(column_type))
+        .collect();
+    conn.execute(
+        &format!(
+            "INSERT INTO {} SELECT {} FROM range({})",
+            table_name,
+            values.join(", "),
+            row_count
+        ),
+        [],
+    )
+    .map_err(|e| AppError::DbError(format!("Failed to insert test data: {}", e)))
+}
+
+#[tauri::command]
+async fn generate_test_data(table_name: String, row_count: usize) -> Result<String, AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let rows_inserted = insert_random_rows(&conn, &table_name, row_count)?;
+    invalidate_query_cache();
+
+    let result = serde_json::json!({
+        "table_name": table_name,
+        "rows_inserted": rows_inserted
+    });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn generate_fake_data_for_schema(
+    table_name: String,
+    row_count: usize,
+    dest_table: String,
+) -> Result<String, AppError> {
+    for name in [&table_name, &dest_table] {
+        if !is_valid_identifier(name) {
+            return Err(AppError::ValidationError(format!("Invalid name: {}", name)));
+        }
+    }
+
+    let mut conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    if main_table_exists(&conn, &dest_table)? {
+        return Err(AppError::ValidationError(format!(
+            "Table already exists: {}",
+            dest_table
+        )));
+    }
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::DbError(e.to_string()))?;
+    tx.execute_batch(&format!(
+        "CREATE TABLE {} AS SELECT * FROM {} LIMIT 0",
+        dest_table, table_name
+    ))
+    .map_err(|e| AppError::DbError(format!("Failed to create {}: {}", dest_table, e)))?;
+    let rows_inserted = insert_random_rows(&tx, &dest_table, row_count)?;
+    tx.commit().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    invalidate_query_cache();
+
+    let result = serde_json::json!({
+        "table_name": dest_table,
+        "rows_inserted": rows_inserted
+    });
+
+    Ok(result.to_string())
+}
+
+// Note: Fixed-width types use their in-memory size; strings and nested types use the 16-byte inline header
+fn estimated_column_width(column_type: &str) -> u64 {
+    match column_type.to_uppercase().as_str() {
+        "BOOLEAN" | "TINYINT" | "UTINYINT" => 1,
+        "SMALLINT" | "USMALLINT" => 2,
+        "INTEGER" | "UINTEGER" | "FLOAT" | "DATE" => 4,
+        "BIGINT" | "UBIGINT" | "DOUBLE" | "TIME" => 8,
+        t if t.starts_with("TIMESTAMP") => 8,
+        t if t.starts_with("DECIMAL") => 8,
+        _ => 16,
+    }
+}
+
+// Note: duckdb_tables() estimates the row count, so the size is rows times the estimated row width
+#[tauri::command]
+async fn estimate_table_size_bytes(table_name: String) -> Result<u64, AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
+
+    let conn = duckdb_connect_readonly().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let mut stmt = conn
+        .prepare("SELECT type FROM pragma_table_info(?)")
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
+    let column_types: Vec<String> = stmt
+        .query_map([&table_name], |row| row.get(0))
+        .map_err(|_| AppError::DbError(format!("Table not found: {}", table_name)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+    let row_width: u64 = column_types
+        .iter()
+        .map(|column_type| estimated_column_width(column_type))
+        .sum();
+
+    let estimated_rows = conn
+        .query_row(
+            "SELECT estimated_size FROM duckdb_tables() \
+             WHERE schema_name = 'main' AND table_name = ?",
+            [&table_name],
+            |row| row.get::<_, i64>(0),
+        )
+        .or_else(|_| {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table_name), [], |row| {
+                row.get::<_, i64>(0)
+            })
+        })
+        .map_err(|e| AppError::DbError(format!("Failed to estimate row count: {}", e)))?;
+
+    Ok(estimated_rows.max(0) as u64 * row_width)
+}
+
+const INGEST_URL_SCHEMES: &[&str] = &["https://", "s3://"];
+
+fn ingest_url(
+    conn: &Connection,
+    url: &str,
+    table_name: &str,
+    format: &str,
+) -> Result<i64, AppError> {
+    if !INGEST_URL_SCHEMES
+        .iter()
+        .any(|scheme| url.to_lowercase().starts_with(scheme))
+    {
+        return Err(AppError::ValidationError(format!(
+            "Unsupported URL scheme: {} (expected https:// or s3://)",
+            url
+        )));
+    }
+    let reader = schema_inference::file_reader(format).map_err(AppError::ValidationError)?;
+
+    conn.execute_batch("INSTALL httpfs; LOAD httpfs;")
+        .map_err(|e| AppError::DbError(format!("Failed to load httpfs extension: {}", e)))?;
+
+    conn.execute(
+        &format!(
+            "CREATE TABLE {} AS SELECT * FROM {}('{}')",
+            table_name,
+            reader,
+            url.replace('\'', "''")
+        ),
+        [],
+    )
+    .map_err(|e| AppError::ConnectorError(format!("Failed to ingest {}: {}", url, e)))?;
+
+    conn.query_row(&format!("SELECT COUNT(*) FROM {}", table_name), [], |row| {
+        row.get(0)
+    })
+    .map_err(|e| AppError::DbError(format!("Failed to count rows: {}", e)))
+}
+
+#[tauri::command]
+async fn ingest_from_url(
+    url: String,
+    table_name: String,
+    format: String,
+) -> Result<String, AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let row_count = ingest_url(&conn, &url, &table_name, &format)?;
+    invalidate_query_cache();
+
+    let result = serde_json::json!({
+        "table_name": table_name,
+        "row_count": row_count
+    });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn drop_table(table_name: String) -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
+
+    let query = format!("DROP TABLE IF EXISTS {}", table_name);
+    conn.execute(&query, [])
+        .map_err(|e| AppError::DbError(format!("Failed to drop table: {}", e)))?;
+    invalidate_query_cache();
+
+    Ok(format!("Table {} dropped successfully", table_name))
+}
+
+#[tauri::command]
+async fn merge_table(
+    source_table: String,
+    target_table: String,
+    match_keys: Vec<String>,
+    update_columns: Vec<String>,
+) -> Result<String, AppError> {
+    for name in [&source_table, &target_table]
+        .into_iter()
+        .chain(&match_keys)
+        .chain(&update_columns)
+    {
+        if !is_valid_identifier(name) {
+            return Err(AppError::ValidationError(format!(
+                "Invalid identifier: {}",
+                name
+            )));
+        }
+    }
+    if match_keys.is_empty() {
+        return Err(AppError::ValidationError(
+            "At least one match key is required".to_string(),
+        ));
+    }
+    if let Some(column) = update_columns.iter().find(|c| match_keys.contains(c)) {
+        return Err(AppError::ValidationError(format!(
+            "Match key cannot be updated: {}",
+            column
+        )));
+    }
+
+    let on = match_keys
+        .iter()
+        .map(|key| format!("target.{} = source.{}", key, key))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    // Note: Without update columns, matched rows are left untouched
+    let when_matched = if update_columns.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " WHEN MATCHED THEN UPDATE SET {}",
+            update_columns
+                .iter()
+                .map(|column| format!("{} = source.{}", column, column))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+
+    let sql = format!(
+        "MERGE INTO {} AS target USING {} AS source ON {}{} \
+         WHEN NOT MATCHED THEN INSERT BY NAME RETURNING merge_action",
+        target_table, source_table, on, when_matched
+    );
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let actions: Vec<String> = conn
+        .prepare(&sql)
+        .map_err(|e| AppError::DbError(format!("Failed to prepare merge: {}", e)))?
+        .query_map([], |row| row.get(0))
+        .map_err(|e| AppError::DbError(format!("Failed to merge tables: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+    invalidate_query_cache();
+
+    let result = serde_json::json!({
+        "rows_updated": actions.iter().filter(|a| *a == "UPDATE").count(),
+        "rows_inserted": actions.iter().filter(|a| *a == "INSERT").count()
+    });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn export_table(
+    table_name: String,
+    path: String,
+    format: String,
+    compress: bool,
+) -> Result<String, AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
+
+    let format = match format.to_lowercase().as_str() {
+        "csv" => "CSV",
+        "parquet" => "PARQUET",
+        _ => {
+            return Err(AppError::ValidationError(format!(
+                "Unsupported export format: {}",
+                format
+            )))
+        }
+    };
+
+    // Note: Parquet compresses its column chunks internally, so only CSV output is a gzip file
+    let path = if compress && format == "CSV" && !path.ends_with(".gz") {
+        format!("{}.gz", path)
+    } else {
+        path
+    };
+
+    let mut options = vec![format!("FORMAT {}", format)];
+    if compress {
+        options.push("COMPRESSION GZIP".to_string());
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let query = format!(
+        "COPY {} TO '{}' ({})",
+        table_name,
+        path.replace('\'', "''"),
+        options.join(", ")
+    );
+    conn.execute(&query, [])
+        .map_err(|e| AppError::DbError(format!("Failed to export table: {}", e)))?;
+
+    Ok(path)
+}
+
+#[tauri::command]
+async fn column_correlation(
+    table_name: String,
+    col_a: String,
+    col_b: String,
+) -> Result<f64, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    for column in [&col_a, &col_b] {
+        let column_type: Option<String> = conn
+            .query_row(
+                "SELECT data_type FROM information_schema.columns \
+                 WHERE table_schema = 'main' AND table_name = ? AND column_name = ?",
+                [&table_name, column],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| AppError::DbError(format!("Failed to query column type: {}", e)))?;
+
+        match column_type {
+            None => {
+                return Err(AppError::ValidationError(format!(
+                    "Column not found: {}.{}",
+                    table_name, column
+                )))
+            }
+            Some(t) if map_column_type(&t) != "number" => {
+                return Err(AppError::ValidationError(format!(
+                    "Column {} is not numeric (type {})",
+                    column, t
+                )))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let query = format!(
+        "SELECT corr({}, {}) FROM {}",
+        quote_identifier(&col_a),
+        quote_identifier(&col_b),
+        quote_identifier(&table_name)
+    );
+    let correlation: Option<f64> = conn
+        .query_row(&query, [], |row| row.get(0))
+        .map_err(|e| AppError::DbError(format!("Failed to compute correlation: {}", e)))?;
+
+    correlation.ok_or_else(|| {
+        AppError::ValidationError(format!(
+            "Correlation between {} and {} is undefined",
+            col_a, col_b
+        ))
+    })
+}
+
+fn resolve_duckdb_type(ty: &str) -> Result<String, AppError> {
+    let resolved = match ty {
+        "number" => "DOUBLE",
+        "string" => "VARCHAR",
+        "boolean" => "BOOLEAN",
+        "date" => "DATE",
+        other => other,
+    };
+
+    let is_valid = !resolved.is_empty()
+        && resolved
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | ' ' | '(' | ')' | ',' | '[' | ']'));
+    if !is_valid {
+        return Err(AppError::ValidationError(format!(
+            "Invalid column type: {}",
+            ty
+        )));
+    }
+
+    Ok(resolved.to_uppercase())
+}
+
+#[tauri::command]
+async fn apply_schema_to_table(
+    table_name: String,
+    schema: serde_json::Value,
+) -> Result<String, AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
+
+    let columns = schema["columns"].as_array().ok_or_else(|| {
+        AppError::ValidationError("Schema must contain a columns array".to_string())
+    })?;
+
+    let mut targets = Vec::new();
+    for column in columns {
+        let name = column["name"]
+            .as_str()
+            .ok_or_else(|| AppError::ValidationError("Column name is missing".to_string()))?;
+        let ty = column["type"].as_str().ok_or_else(|| {
+            AppError::ValidationError(format!("Column type is missing: {}", name))
+        })?;
+        targets.push((name.to_string(), resolve_duckdb_type(ty)?));
+    }
+
+    let mut conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let mut current_types = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare(&format!("DESCRIBE {}", table_name))
+            .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| AppError::DbError(format!("Failed to query schema: {}", e)))?;
+        for row in rows {
+            let (name, column_type) =
+                row.map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+            current_types.insert(name, column_type);
+        }
+    }
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::DbError(format!("Failed to begin transaction: {}", e)))?;
+
+    let mut cast_columns = Vec::new();
+    for (name, new_type) in &targets {
+        let current_type = current_types.get(name).ok_or_else(|| {
+            AppError::ValidationError(format!("Column not found: {}.{}", table_name, name))
+        })?;
+        if current_type.eq_ignore_ascii_case(new_type) {
+            continue;
+        }
+
+        let column = quote_identifier(name);
+        let query = format!(
+            "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING CAST({} AS {})",
+            table_name, column, new_type, column, new_type
+        );
+        tx.execute(&query, []).map_err(|e| {
+            AppError::DbError(format!(
+                "Failed to cast column {} to {}: {}",
+                name, new_type, e
+            ))
+        })?;
+        cast_columns.push(name.clone());
+    }
+
+    tx.commit()
+        .map_err(|e| AppError::DbError(format!("Failed to commit transaction: {}", e)))?;
+    invalidate_query_cache();
+
+    Ok(serde_json::json!(cast_columns).to_string())
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// Note: Keys are quoted inside the JSON path so that keys with dots or spaces are read as a single key
+fn json_key_path(key: &str) -> String {
+    let path = format!("$.\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""));
+    format!("'{}'", path.replace('\'', "''"))
+}
+
+#[tauri::command]
+async fn flatten_json_column(
+    table_name: String,
+    column_name: String,
+    new_table_name: String,
+) -> Result<String, AppError> {
+    for name in [&table_name, &column_name, &new_table_name] {
+        if !is_valid_identifier(name) {
+            return Err(AppError::ValidationError(format!("Invalid name: {}", name)));
+        }
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let existing: Vec<String> = describe_table(&conn, &table_name)?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    if !existing.contains(&column_name) {
+        return Err(AppError::ValidationError(format!(
+            "Column not found: {}",
+            column_name
+        )));
+    }
+
+    let keys: Vec<String> = conn
+        .prepare(&format!(
+            "SELECT DISTINCT unnest(json_keys({})) AS key FROM {} \
+             WHERE json_type({}) = 'OBJECT' ORDER BY key",
+            column_name, table_name, column_name
+        ))
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()
+        })
+        .map_err(|e| AppError::DbError(format!("Failed to read JSON keys: {}", e)))?;
+
+    if keys.is_empty() {
+        return Err(AppError::ValidationError(format!(
+            "Column {} has no JSON object keys",
+            column_name
+        )));
+    }
+    if let Some(key) = keys
+        .iter()
+        .find(|key| existing.iter().any(|c| c.eq_ignore_ascii_case(key)))
+    {
+        return Err(AppError::ValidationError(format!(
+            "JSON key {} conflicts with an existing column",
+            key
+        )));
+    }
+
+    let extracted: Vec<String> = keys
+        .iter()
+        .map(|key| {
+            format!(
+                "json_extract({}, {}) AS {}",
+                column_name,
+                json_key_path(key),
+                quote_identifier(key)
+            )
+        })
+        .collect();
+    conn.execute_batch(&format!(
+        "CREATE TABLE {} AS SELECT *, {} FROM {}",
+        new_table_name,
+        extracted.join(", "),
+        table_name
+    ))
+    .map_err(|e| AppError::DbError(format!("Failed to create {}: {}", new_table_name, e)))?;
+
+    let result = serde_json::json!({
+        "table_name": new_table_name,
+        "columns": keys
+    });
+
+    Ok(result.to_string())
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum HashAlgorithm {
+    Md5,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    fn function_name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha256 => "sha256",
+        }
+    }
+}
+
+#[tauri::command]
+async fn hash_column(
+    table_name: String,
+    column_name: String,
+    algorithm: HashAlgorithm,
+    salt: Option<String>,
+) -> Result<(), AppError> {
+    for name in [&table_name, &column_name] {
+        if !is_valid_identifier(name) {
+            return Err(AppError::ValidationError(format!("Invalid name: {}", name)));
+        }
+    }
+
+    let mut conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let column_type = describe_table(&conn, &table_name)?
+        .into_iter()
+        .find(|(name, _)| *name == column_name)
+        .map(|(_, data_type)| data_type)
+        .ok_or_else(|| AppError::ValidationError(format!("Column not found: {}", column_name)))?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::DbError(e.to_string()))?;
+    // Note: Digests are hex strings, so non-text columns are converted first.
+    if column_type != "VARCHAR" {
+        tx.execute_batch(&format!(
+            "ALTER TABLE {} ALTER COLUMN {} TYPE VARCHAR",
+            table_name, column_name
+        ))
+        .map_err(|e| AppError::DbError(format!("Failed to convert {}: {}", column_name, e)))?;
+    }
+    tx.execute(
+        &format!(
+            "UPDATE {} SET {} = {}(? || CAST({} AS VARCHAR))",
+            table_name,
+            column_name,
+            algorithm.function_name(),
+            column_name
+        ),
+        duckdb::params![salt.unwrap_or_default()],
+    )
+    .map_err(|e| AppError::DbError(format!("Failed to hash {}: {}", column_name, e)))?;
+    tx.commit().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    invalidate_query_cache();
+    Ok(())
+}
+
+#[tauri::command]
+async fn create_macro(name: String, params: Vec<String>, body: String) -> Result<(), AppError> {
+    if !is_valid_identifier(&name) {
+        return Err(AppError::ValidationError("Invalid macro name".to_string()));
+    }
+
+    if let Some(param) = params.iter().find(|p| !is_valid_identifier(p)) {
+        return Err(AppError::ValidationError(format!(
+            "Invalid macro parameter name: {}",
+            param
+        )));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let query = format!(
+        "CREATE OR REPLACE MACRO {}({}) AS ({})",
+        name,
+        params.join(", "),
+        body
+    );
+    conn.execute(&query, [])
+        .map_err(|e| AppError::DbError(format!("Failed to create macro: {}", e)))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn drop_macro(name: String) -> Result<String, AppError> {
+    if !is_valid_identifier(&name) {
+        return Err(AppError::ValidationError("Invalid macro name".to_string()));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let query = format!("DROP MACRO IF EXISTS {}", name);
+    conn.execute(&query, [])
+        .map_err(|e| AppError::DbError(format!("Failed to drop macro: {}", e)))?;
+
+    Ok(format!("Macro {} dropped successfully", name))
+}
+
+#[tauri::command]
+async fn call_macro(name: String, args: Vec<serde_json::Value>) -> Result<String, AppError> {
+    if !is_valid_identifier(&name) {
+        return Err(AppError::ValidationError("Invalid macro name".to_string()));
+    }
+
+    let params = args
+        .iter()
+        .map(json_to_duckdb_value)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(AppError::ValidationError)?;
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let placeholders = vec!["?"; params.len()].join(", ");
+    let query = format!("SELECT {}({}) AS result", name, placeholders);
+    let mut stmt = conn
+        .prepare(&query)
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
+
+    let mut rows = stmt
+        .query(duckdb::params_from_iter(params))
+        .map_err(|e| AppError::DbError(format!("Failed to call macro: {}", e)))?;
+
+    let value = match rows
+        .next()
+        .map_err(|e| AppError::DbError(format!("Failed to fetch row: {}", e)))?
+    {
+        Some(row) => match row.get_ref(0) {
+            Ok(val) => duckdb_value_to_json(val),
+            Err(_) => serde_json::Value::Null,
+        },
+        None => serde_json::Value::Null,
+    };
+
+    Ok(value.to_string())
+}
+
+#[tauri::command]
+async fn list_macros() -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT function_name, parameters, macro_definition FROM duckdb_functions() \
+             WHERE function_type = 'macro' AND NOT internal ORDER BY function_name",
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
+
+    let macros: Vec<serde_json::Value> = stmt
+        .query_map([], |row| {
+            let name: String = row.get(0)?;
+            let parameters = match row.get::<_, duckdb::types::Value>(1)? {
+                duckdb::types::Value::List(values) => values
+                    .into_iter()
+                    .filter_map(|v| match v {
+                        duckdb::types::Value::Text(s) => Some(s),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let definition: Option<String> = row.get(2)?;
+
+            Ok(serde_json::json!({
+                "name": name,
+                "parameters": parameters,
+                "definition": definition
+            }))
+        })
+        .map_err(|e| AppError::DbError(format!("Failed to query macros: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+
+    let result = serde_json::json!({
+        "macros": macros
+    });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn create_sequence(name: String, start: i64, increment: i64) -> Result<(), AppError> {
+    if !is_valid_identifier(&name) {
+        return Err(AppError::ValidationError(
+            "Invalid sequence name".to_string(),
+        ));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let query = format!(
+        "CREATE SEQUENCE {} START {} INCREMENT BY {}",
+        name, start, increment
+    );
+    conn.execute(&query, [])
+        .map_err(|e| AppError::DbError(format!("Failed to create sequence: {}", e)))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn next_value(name: String) -> Result<i64, AppError> {
+    if !is_valid_identifier(&name) {
+        return Err(AppError::ValidationError(
+            "Invalid sequence name".to_string(),
+        ));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let query = format!("SELECT nextval('{}')", name);
+    let value: i64 = conn
+        .query_row(&query, [], |row| row.get(0))
+        .map_err(|e| AppError::DbError(format!("Failed to get next sequence value: {}", e)))?;
+
+    Ok(value)
+}
+
+#[tauri::command]
+async fn create_schema(name: String) -> Result<(), AppError> {
+    if !is_valid_identifier(&name) {
+        return Err(AppError::ValidationError("Invalid schema name".to_string()));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let query = format!("CREATE SCHEMA IF NOT EXISTS {}", name);
+    conn.execute(&query, [])
+        .map_err(|e| AppError::DbError(format!("Failed to create schema: {}", e)))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn drop_schema(name: String, cascade: bool) -> Result<(), AppError> {
+    if !is_valid_identifier(&name) {
+        return Err(AppError::ValidationError("Invalid schema name".to_string()));
+    }
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let query = if cascade {
+        format!("DROP SCHEMA {} CASCADE", name)
+    } else {
+        format!("DROP SCHEMA {}", name)
+    };
+    conn.execute(&query, [])
+        .map_err(|e| AppError::DbError(format!("Failed to drop schema: {}", e)))?;
+    invalidate_query_cache();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_schemas() -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT schema_name FROM information_schema.schemata \
+             WHERE catalog_name = current_database() ORDER BY schema_name",
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
+
+    let schemas: Vec<String> = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| AppError::DbError(format!("Failed to query schemas: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+
+    let result = serde_json::json!({
+        "schemas": schemas
+    });
+
+    Ok(result.to_string())
+}
+
+const MIGRATIONS_TABLE: &str = "_streaksight_migrations";
+
+#[derive(Debug, Deserialize)]
+struct Migration {
+    version: String,
+    sql: String,
+}
+
+fn ensure_migrations_table(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} \
+         (version TEXT PRIMARY KEY, applied_at TIMESTAMPTZ, checksum TEXT)",
+        MIGRATIONS_TABLE
+    ))
+    .map_err(|e| AppError::DbError(format!("Failed to create migrations table: {}", e)))
+}
+
+// Note: A version that was already applied is skipped, unless its SQL changed since then
+#[tauri::command]
+async fn apply_migrations(migrations: Vec<Migration>) -> Result<String, AppError> {
+    let mut conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    ensure_migrations_table(&conn)?;
+
+    let mut applied = Vec::new();
+    let mut skipped = Vec::new();
+    for migration in &migrations {
+        let checksum = sha256_hex(&migration.sql);
+        let applied_checksum: Option<String> = conn
+            .query_row(
+                &format!(
+                    "SELECT checksum FROM {} WHERE version = ?",
+                    MIGRATIONS_TABLE
+                ),
+                [&migration.version],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| AppError::DbError(format!("Failed to read migrations: {}", e)))?;
+
+        match applied_checksum {
+            Some(applied_checksum) if applied_checksum == checksum => {
+                skipped.push(migration.version.as_str());
+                continue;
+            }
+            Some(_) => {
+                return Err(AppError::ValidationError(format!(
+                    "Migration {} was modified after it was applied",
+                    migration.version
+                )))
+            }
+            None => {}
+        }
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::DbError(format!("Failed to begin transaction: {}", e)))?;
+        tx.execute_batch(&migration.sql).map_err(|e| {
+            AppError::DbError(format!("Migration {} failed: {}", migration.version, e))
+        })?;
+        tx.execute(
+            &format!("INSERT INTO {} VALUES (?, now(), ?)", MIGRATIONS_TABLE),
+            [&migration.version, &checksum],
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to record migration: {}", e)))?;
+        tx.commit()
+            .map_err(|e| AppError::DbError(format!("Failed to commit migration: {}", e)))?;
+        applied.push(migration.version.as_str());
+    }
+    invalidate_query_cache();
+
+    let result = serde_json::json!({
+        "applied": applied,
+        "skipped": skipped
+    });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn get_migration_history() -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    ensure_migrations_table(&conn)?;
+
+    let result = query_result_json(
+        &conn,
+        &format!(
+            "SELECT m.version, CAST(m.applied_at AS VARCHAR) AS applied_at, m.checksum \
+             FROM {} m ORDER BY m.applied_at, m.version",
+            MIGRATIONS_TABLE
+        ),
+    )?;
+
+    let history = serde_json::json!({
+        "migrations": result["rows"]
+    });
+
+    Ok(history.to_string())
+}
+
+const TEMPLATES_TABLE: &str = "_streaksight_query_templates";
+
+fn ensure_templates_table(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} \
+         (name TEXT PRIMARY KEY, description TEXT, node_graph_template TEXT)",
+        TEMPLATES_TABLE
+    ))
+    .map_err(|e| AppError::DbError(format!("Failed to create templates table: {}", e)))
+}
+
+// Note: Placeholders such as {{table_name}} are strings in the template, so it must already be a valid node graph
+#[tauri::command]
+async fn create_template(
+    name: String,
+    description: String,
+    node_graph_template: String,
+) -> Result<String, AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Template name must not be empty".to_string(),
+        ));
+    }
+    serde_json::from_str::<query_builder::NodeGraph>(&node_graph_template)
+        .map_err(|e| AppError::ValidationError(format!("Invalid node graph template: {}", e)))?;
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    ensure_templates_table(&conn)?;
+
+    let exists: bool = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) > 0 FROM {} WHERE name = ?",
+                TEMPLATES_TABLE
+            ),
+            [&name],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to read templates: {}", e)))?;
+    if exists {
+        return Err(AppError::ValidationError(format!(
+            "Template already exists: {}",
+            name
+        )));
+    }
+
+    conn.execute(
+        &format!("INSERT INTO {} VALUES (?, ?, ?)", TEMPLATES_TABLE),
+        [&name, &description, &node_graph_template],
+    )
+    .map_err(|e| AppError::DbError(format!("Failed to save template: {}", e)))?;
+
+    let result = serde_json::json!({ "name": name });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn apply_template(
+    name: String,
+    bindings: HashMap<String, String>,
+) -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    ensure_templates_table(&conn)?;
+
+    let template: String = conn
+        .query_row(
+            &format!(
+                "SELECT node_graph_template FROM {} WHERE name = ?",
+                TEMPLATES_TABLE
+            ),
+            [&name],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| AppError::DbError(format!("Failed to read templates: {}", e)))?
+        .ok_or_else(|| AppError::ValidationError(format!("Template not found: {}", name)))?;
+
+    let variables: HashMap<String, serde_json::Value> = bindings
+        .into_iter()
+        .map(|(key, value)| (key, serde_json::Value::String(value)))
+        .collect();
+    let graph =
+        apply_template_variables(&template, &variables).map_err(AppError::ValidationError)?;
+
+    serde_json::to_string(&graph)
+        .map_err(|e| AppError::ValidationError(format!("Failed to serialize node graph: {}", e)))
+}
+
+#[tauri::command]
+async fn list_templates() -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    ensure_templates_table(&conn)?;
+
+    let result = query_result_json(
+        &conn,
+        &format!(
+            "SELECT name, description FROM {} ORDER BY name",
+            TEMPLATES_TABLE
+        ),
+    )?;
+
+    let templates = serde_json::json!({
+        "templates": result["rows"]
+    });
+
+    Ok(templates.to_string())
+}
+
+#[tauri::command]
+async fn get_duckdb_version() -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let duckdb_version: String = conn
+        .query_row("SELECT version()", [], |row| row.get(0))
+        .map_err(|e| AppError::DbError(format!("Failed to get DuckDB version: {}", e)))?;
+
+    let result = serde_json::json!({
+        "duckdb_version": duckdb_version,
+        "crate_version": env!("DUCKDB_CRATE_VERSION")
+    });
+
+    Ok(result.to_string())
+}
+
+// Note: A statement cannot outlive its connection and every command opens its own, so the
+// cache is duckdb's per-connection LRU; DuckDB rebinds cached statements after schema changes
+fn query_result_json(conn: &Connection, sql: &str) -> Result<serde_json::Value, AppError> {
+    let column_names = {
+        let mut info_stmt = conn
+            .prepare_cached(sql)
+            .map_err(|e| AppError::DbError(format!("Failed to prepare SQL: {}", e)))?;
+        info_stmt
+            .execute([])
+            .map_err(|e| AppError::DbError(format!("Failed to execute query: {}", e)))?;
+        info_stmt.column_names()
+    };
+
+    let mut stmt = conn
+        .prepare_cached(sql)
+        .map_err(|e| AppError::DbError(format!("Failed to prepare SQL: {}", e)))?;
+
+    let mut rows_data = Vec::new();
+    let mut rows = stmt
+        .query([])
+        .map_err(|e| AppError::DbError(format!("Failed to execute query: {}", e)))?;
+
+    while let Some(row) = rows
+        .next()
+        .map_err(|e| AppError::DbError(format!("Failed to fetch row: {}", e)))?
+    {
+        let mut row_obj = serde_json::Map::new();
+        for (i, col_name) in column_names.iter().enumerate() {
+            let value = match row.get_ref(i) {
+                Ok(val) => duckdb_value_to_json(val),
+                Err(_) => serde_json::Value::Null,
+            };
+            row_obj.insert(col_name.clone(), value);
+        }
+        rows_data.push(serde_json::Value::Object(row_obj));
+    }
+
+    let columns_info: Vec<serde_json::Value> = column_names
+        .iter()
+        .map(|name| {
+            serde_json::json!({
+                "name": name
+            })
+        })
+        .collect();
+
+    let result = serde_json::json!({
+        "columns": columns_info,
+        "rows": rows_data,
+        "row_count": rows_data.len()
+    });
+
+    Ok(result)
+}
+
+// Note: A data string that is only a placeholder takes the variable's JSON value, so numbers and IN lists keep their type
+fn substitute_template(
+    text: &str,
+    field: &str,
+    variables: &HashMap<String, serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    if !text.contains("{{") {
+        return Ok(serde_json::Value::String(text.to_string()));
+    }
+
+    let lookup = |name: &str| {
+        variables
+            .get(name.trim())
+            .ok_or_else(|| format!("Unknown template variable: {}", name.trim()))
+    };
+    let lone_placeholder = text
+        .strip_prefix("{{")
+        .and_then(|rest| rest.strip_suffix("}}"))
+        .filter(|name| !name.contains("{{") && !name.contains("}}"));
+
+    let value = match lone_placeholder {
+        Some(name) => lookup(name)?.clone(),
+        None => {
+            let mut substituted = String::new();
+            let mut rest = text;
+            while let Some((start, end)) = rest
+                .find("{{")
+                .and_then(|start| Some((start, start + rest[start..].find("}}")?)))
+            {
+                let name = &rest[start + 2..end];
+                substituted.push_str(&rest[..start]);
+                match lookup(name)? {
+                    serde_json::Value::String(s) => substituted.push_str(s),
+                    v @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_)) => {
+                        substituted.push_str(&v.to_string())
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Template variable {} must be a string, number or boolean",
+                            name.trim()
+                        ))
+                    }
+                }
+                rest = &rest[end + 2..];
+            }
+            substituted.push_str(rest);
+            serde_json::Value::String(substituted)
+        }
+    };
+
+    // Note: Filter values become SQL literals; anything else is a name and must be a plain identifier
+    let is_safe = |v: &serde_json::Value| match v {
+        serde_json::Value::String(s) => field == "value" || is_valid_identifier(s),
+        serde_json::Value::Number(_) | serde_json::Value::Bool(_) | serde_json::Value::Null => {
+            field == "value"
+        }
+        _ => false,
+    };
+    let safe = match &value {
+        serde_json::Value::Array(items) => items.iter().all(is_safe),
+        other => is_safe(other),
+    };
+    if !safe {
+        return Err(format!(
+            "Invalid value for template placeholder in {}: {}",
+            field, value
+        ));
+    }
+
+    Ok(value)
+}
+
+fn substitute_templates(
+    value: &mut serde_json::Value,
+    field: &str,
+    variables: &HashMap<String, serde_json::Value>,
+) -> Result<(), String> {
+    match value {
+        serde_json::Value::String(text) => *value = substitute_template(text, field, variables)?,
+        serde_json::Value::Array(items) => {
+            for item in items {
+                substitute_templates(item, field, variables)?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                substitute_templates(item, key, variables)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn parse_node_graph(node_graph: &str) -> Result<query_builder::NodeGraph, AppError> {
+    query_builder::parse_node_graph(node_graph).map_err(AppError::ValidationError)
+}
+
+fn apply_template_variables(
+    node_graph: &str,
+    variables: &HashMap<String, serde_json::Value>,
+) -> Result<query_builder::NodeGraph, String> {
+    let mut graph: serde_json::Value = serde_json::from_str(node_graph)
+        .map_err(|e| format!("Failed to parse node graph: {}", e))?;
+
+    if let Some(nodes) = graph["nodes"].as_array_mut() {
+        for node in nodes {
+            if let Some(data) = node.get_mut("data") {
+                substitute_templates(data, "data", variables)?;
+            }
+        }
+    }
+
+    query_builder::node_graph_from_value(graph)
+}
+
+fn query_page(
+    mut graph: query_builder::NodeGraph,
+    limit: i64,
+    offset: i64,
+    include_row_numbers: bool,
+) -> Result<(String, serde_json::Value), AppError> {
+    let conn = duckdb_connect_readonly().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    coerce_filter_values(&mut graph, &conn).map_err(AppError::DbError)?;
+    check_joined_tables(&graph, &conn)?;
+    let sql = generate_query_sql(&graph, Some((limit, offset)))?;
+    let result = page_result(&conn, &sql, offset, include_row_numbers)?;
+
+    Ok((sql, result))
+}
+
+fn check_joined_tables(
+    graph: &query_builder::NodeGraph,
+    conn: &Connection,
+) -> Result<(), AppError> {
+    for (schema, table) in query_builder::joined_tables(graph).map_err(AppError::ValidationError)? {
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM information_schema.tables \
+                 WHERE table_schema = ? AND table_name = ?",
+                [schema.as_deref().unwrap_or("main"), table.as_str()],
+                |row| row.get(0),
+            )
+            .map_err(|e| AppError::DbError(format!("Failed to check table: {}", e)))?;
+        if !exists {
+            return Err(AppError::ValidationError(format!(
+                "Table not found: {}",
+                table
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn page_result(
+    conn: &Connection,
+    sql: &str,
+    offset: i64,
+    include_row_numbers: bool,
+) -> Result<serde_json::Value, AppError> {
+    let mut result = query_result_json(conn, sql)?;
+    let column_types = describe_table(conn, sql)?;
+    add_column_metadata(&column_types, &mut result);
+    result["schema_version"] = schema_version(&column_types).into();
+    if include_row_numbers {
+        add_row_numbers(&mut result, offset);
+    }
+
+    Ok(result)
+}
+
+// Note: Row numbers restart at 1 on every cursor page because the offset is unknown
+fn query_cursor_page(
+    mut graph: query_builder::NodeGraph,
+    cursor: &query_builder::CursorPagination,
+    include_row_numbers: bool,
+) -> Result<String, AppError> {
+    let conn = duckdb_connect_readonly().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    coerce_filter_values(&mut graph, &conn).map_err(AppError::DbError)?;
+    check_joined_tables(&graph, &conn)?;
+    let sql = query_builder::cursor_paginate(&generate_query_sql(&graph, None)?, cursor)
+        .map_err(AppError::ValidationError)?;
+    let mut result = page_result(&conn, &sql, 0, include_row_numbers)?;
+
+    let rows = result["rows"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    result["next_cursor"] = match rows.last() {
+        Some(last) if rows.len() == cursor.page_size => last[&cursor.order_column].clone(),
+        _ => serde_json::Value::Null,
+    };
+
+    Ok(result.to_string())
+}
+
+// Note: Each selected node is queried on its own so that pipeline stages can be compared side by side;
+// these results are not cached
+fn run_query_stages(
+    graph: query_builder::NodeGraph,
+    limit: i64,
+    offset: i64,
+    include_row_numbers: bool,
+) -> Result<String, AppError> {
+    let stages = graph
+        .selected_node_ids
+        .iter()
+        .map(|node_id| {
+            let mut stage = graph.clone();
+            stage.selected_node_ids = vec![node_id.clone()];
+            let (sql, mut result) = query_page(stage, limit, offset, include_row_numbers)?;
+            result["node_id"] = node_id.as_str().into();
+            result["sql"] = sql.into();
+            Ok(result)
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    Ok(serde_json::Value::Array(stages).to_string())
+}
+
+#[tauri::command]
+async fn run_query(
+    node_graph: String,
+    page: Option<i32>,
+    page_size: Option<i32>,
+    include_row_numbers: Option<bool>,
+    last_schema_version: Option<String>,
+    variables: Option<HashMap<String, serde_json::Value>>,
+    cursor_pagination: Option<query_builder::CursorPagination>,
+) -> Result<String, AppError> {
+    let graph = apply_template_variables(&node_graph, &variables.unwrap_or_default())
+        .map_err(AppError::ValidationError)?;
+
+    let page = page.unwrap_or(1);
+    let page_size = page_size.unwrap_or(100);
+    let include_row_numbers = include_row_numbers.unwrap_or(false);
+
+    if graph.selected_node_ids.len() > 1 {
+        if cursor_pagination.is_some() {
+            return Err(AppError::ValidationError(
+                "Cursor pagination requires a single selected node".to_string(),
+            ));
+        }
+        let offset = ((page - 1) * page_size) as i64;
+        return run_query_stages(graph, page_size as i64, offset, include_row_numbers);
+    }
+
+    // Note: Cursor pages are not cached since each cursor value is typically fetched once
+    let result = match cursor_pagination {
+        Some(cursor) => query_cursor_page(graph, &cursor, include_row_numbers)?,
+        None => cached_query_page(query_cache(), graph, page, page_size, include_row_numbers)?,
+    };
+
+    match last_schema_version {
+        Some(last_schema_version) => flag_schema_change(&result, &last_schema_version),
+        None => Ok(result),
+    }
+}
+
+fn cached_query_page(
+    cache: &Mutex<lru::LruCache<String, String>>,
+    graph: query_builder::NodeGraph,
+    page: i32,
+    page_size: i32,
+    include_row_numbers: bool,
+) -> Result<String, AppError> {
+    let limit = page_size as i64;
+    let offset = ((page - 1) * page_size) as i64;
+    let cache_key = query_cache_key(&graph, page, page_size, include_row_numbers)?;
+    let cached = cache.lock().unwrap().get(&cache_key).cloned();
+    let result = match cached {
+        Some(cached) => cached,
+        None => {
+            let (_, result) = query_page(graph, limit, offset, include_row_numbers)?;
+            let result = result.to_string();
+            cache.lock().unwrap().put(cache_key, result.clone());
+            result
+        }
+    };
+
+    Ok(result)
+}
+
+fn infer_filter_type(
+    column_name: &str,
+    value: &serde_json::Value,
+    conn: &Connection,
+    table_name: &str,
+) -> Result<sqlparser::ast::Expr, String> {
+    let column_type: Option<String> = conn
+        .query_row(
+            "SELECT data_type FROM information_schema.columns \
+             WHERE table_name = ? AND column_name = ? LIMIT 1",
+            [table_name, column_name],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to look up column type: {}", e))?;
+
+    query_builder::typed_value_expr(value, column_type.as_deref().unwrap_or("VARCHAR"))
+}
+
+// Note: Values that cannot be converted are left as they are, so DuckDB reports the mismatch
+fn coerce_filter_values(
+    graph: &mut query_builder::NodeGraph,
+    conn: &Connection,
+) -> Result<(), String> {
+    let Some(table_name) = query_builder::source_table(graph)? else {
+        return Ok(());
+    };
+
+    for node in graph.nodes.iter_mut().filter(|n| n.node_type == "filter") {
+        let Some(conditions) = node.data["conditions"].as_array_mut() else {
+            continue;
+        };
+        for condition in conditions {
+            let Some(column) = condition["column"].as_str().map(str::to_string) else {
+                continue;
+            };
+            let values = match &mut condition["value"] {
+                serde_json::Value::Array(values) => values.iter_mut().collect(),
+                value => vec![value],
+            };
+            for value in values.into_iter().filter(|v| v.is_string()) {
+                let Ok(expr) = infer_filter_type(&column, value, conn, &table_name) else {
+                    continue;
+                };
+                if let Ok(typed) = query_builder::expr_to_value(&expr) {
+                    *value = typed;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn sha256_hex(data: impl AsRef<[u8]>) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn schema_version(column_types: &[(String, String)]) -> String {
+    let columns: String = column_types
+        .iter()
+        .map(|(name, column_type)| format!("{}\t{}\n", name, column_type))
+        .collect();
+
+    sha256_hex(columns)
+}
+
+// Note: Cached results are shared between callers, so the flag is added to a copy
+fn flag_schema_change(result: &str, last_schema_version: &str) -> Result<String, AppError> {
+    let mut result: serde_json::Value = serde_json::from_str(result)
+        .map_err(|e| AppError::DbError(format!("Failed to parse query result: {}", e)))?;
+    result["schema_changed"] = (result["schema_version"] != last_schema_version).into();
+
+    Ok(result.to_string())
+}
+
+const MAX_LENGTH_SCAN_ROWS: usize = 1000;
+
+// Note: max_length counts characters, not bytes, and is only reported for text columns
+fn add_column_metadata(column_types: &[(String, String)], result: &mut serde_json::Value) {
+    let rows = result["rows"].as_array().cloned().unwrap_or_default();
+    let scanned = &rows[..rows.len().min(MAX_LENGTH_SCAN_ROWS)];
+
+    if let Some(columns) = result["columns"].as_array_mut() {
+        for column in columns.iter_mut() {
+            let Some(name) = column["name"].as_str().map(str::to_string) else {
+                continue;
+            };
+            let Some((_, column_type)) = column_types.iter().find(|(n, _)| *n == name) else {
+                continue;
+            };
+            let column_type = map_column_type(column_type);
+            column["type"] = column_type.into();
+
+            if column_type == "string" {
+                let max_length = scanned
+                    .iter()
+                    .filter_map(|row| row[&name].as_str())
+                    .map(|value| value.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                column["max_length"] = max_length.into();
+            }
+        }
+    }
+}
+
+const ROW_NUMBER_COLUMN: &str = "_rn";
+
+fn add_row_numbers(result: &mut serde_json::Value, offset: i64) {
+    if let Some(columns) = result["columns"].as_array_mut() {
+        columns.insert(0, serde_json::json!({ "name": ROW_NUMBER_COLUMN }));
+    }
+    if let Some(rows) = result["rows"].as_array_mut() {
+        for (i, row) in rows.iter_mut().enumerate() {
+            if let Some(row) = row.as_object_mut() {
+                row.insert(
+                    ROW_NUMBER_COLUMN.to_string(),
+                    (offset + i as i64 + 1).into(),
+                );
+            }
+        }
+    }
+}
+
+const QUERY_CACHE_CAPACITY: usize = 10;
+
+fn query_cache() -> &'static Mutex<lru::LruCache<String, String>> {
+    QUERY_CACHE.get_or_init(|| {
+        Mutex::new(lru::LruCache::new(
+            std::num::NonZeroUsize::new(QUERY_CACHE_CAPACITY).unwrap(),
+        ))
+    })
+}
+
+// Note: The graph is re-serialized so that formatting and unknown fields do not affect the key
+fn query_cache_key(
+    graph: &query_builder::NodeGraph,
+    page: i32,
+    page_size: i32,
+    include_row_numbers: bool,
+) -> Result<String, AppError> {
+    let graph = serde_json::to_string(graph)
+        .map_err(|e| AppError::ValidationError(format!("Failed to serialize node graph: {}", e)))?;
+
+    Ok(sha256_hex(format!(
+        "{}\n{}\n{}\n{}",
+        graph, page, page_size, include_row_numbers
+    )))
+}
+
+// Note: Cached results are keyed on the query alone, so anything that changes table data clears them
+fn invalidate_query_cache() {
+    query_cache().lock().unwrap().clear();
+}
+
+#[tauri::command]
+async fn get_cached_result(
+    node_graph: String,
+    page: Option<i32>,
+    page_size: Option<i32>,
+    include_row_numbers: Option<bool>,
+) -> Result<Option<String>, AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    let cache_key = query_cache_key(
+        &graph,
+        page.unwrap_or(1),
+        page_size.unwrap_or(100),
+        include_row_numbers.unwrap_or(false),
+    )?;
+
+    Ok(query_cache().lock().unwrap().get(&cache_key).cloned())
+}
+
+// Note: COPY writes straight from DuckDB, so large results never pass through JSON in memory
+#[tauri::command]
+async fn run_query_to_file(
+    node_graph: String,
+    path: String,
+    format: String,
+) -> Result<String, AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    let options = match format.to_lowercase().as_str() {
+        "csv" => "FORMAT CSV, HEADER",
+        "json" => "FORMAT JSON, ARRAY true",
+        _ => {
+            return Err(AppError::ValidationError(format!(
+                "Unsupported export format: {}",
+                format
+            )))
+        }
+    };
+
+    let sql = generate_query_sql(&graph, None)?;
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let rows_written = conn
+        .execute(
+            &format!(
+                "COPY ({}) TO '{}' ({})",
+                sql,
+                path.replace('\'', "''"),
+                options
+            ),
+            [],
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to write query result: {}", e)))?;
+
+    let result = serde_json::json!({
+        "rows_written": rows_written,
+        "path": path
+    });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn get_query_schema(node_graph: String) -> Result<String, AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    let sql = generate_query_sql(&graph, None)?;
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let mut stmt = conn
+        .prepare(&format!("DESCRIBE {}", sql))
+        .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?;
+
+    let columns: Vec<serde_json::Value> = stmt
+        .query_map([], |row| {
+            let name: String = row.get(0)?;
+            let column_type: String = row.get(1)?;
+            let nullable = row.get::<_, String>(2)? != "NO";
+
+            Ok(serde_json::json!({
+                "name": name,
+                "type": column_type,
+                "nullable": nullable
+            }))
+        })
+        .map_err(|e| AppError::DbError(format!("Failed to describe query: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+
+    Ok(serde_json::Value::Array(columns).to_string())
+}
+
+fn json_schema_for_type(column_type: &str) -> serde_json::Value {
+    if let Some(element_type) = column_type.strip_suffix("[]") {
+        return serde_json::json!({
+            "type": "array",
+            "items": json_schema_for_type(element_type)
+        });
+    }
+
+    let upper = column_type.to_uppercase();
+    match map_column_type(column_type) {
+        _ if upper == "INTERVAL" => serde_json::json!({ "type": "string" }),
+        "number" if upper.contains("INT") => serde_json::json!({ "type": "integer" }),
+        "number" => serde_json::json!({ "type": "number" }),
+        "boolean" => serde_json::json!({ "type": "boolean" }),
+        "date" => {
+            let format = if upper == "DATE" {
+                "date"
+            } else if upper.starts_with("TIMESTAMP") {
+                "date-time"
+            } else {
+                "time"
+            };
+            serde_json::json!({ "type": "string", "format": format })
+        }
+        _ => serde_json::json!({ "type": "string" }),
+    }
+}
+
+#[tauri::command]
+async fn node_graph_to_json_schema(node_graph: String) -> Result<String, AppError> {
+    let columns: Vec<serde_json::Value> =
+        serde_json::from_str(&get_query_schema(node_graph).await?)
+            .map_err(|e| AppError::DbError(format!("Failed to parse query schema: {}", e)))?;
+
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::with_capacity(columns.len());
+    for column in &columns {
+        let name = column["name"].as_str().unwrap_or_default();
+        let column_type = column["type"].as_str().unwrap_or_default();
+        let mut schema = json_schema_for_type(column_type);
+        // Note: Columns are required keys either way; a nullable one may hold null
+        if column["nullable"].as_bool().unwrap_or(true) {
+            schema["type"] = serde_json::json!([schema["type"], "null"]);
+        }
+        properties.insert(name.to_string(), schema);
+        required.push(name);
+    }
+
+    let result = serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "properties": properties,
+        "required": required
+    });
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn export_node_graph_as_sql(node_graph: String, path: String) -> Result<(), AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    let sql = generate_query_sql(&graph, None)?;
+    let formatted = sqlformat::format(
+        &sql,
+        &sqlformat::QueryParams::None,
+        &sqlformat::FormatOptions::default(),
+    );
+
+    std::fs::write(&path, format!("{}\n", formatted))
+        .map_err(|e| AppError::IoError(format!("Failed to write SQL file {}: {}", path, e)))
+}
+
+trait SqlClipboard {
+    fn set_text(&mut self, text: String) -> Result<(), String>;
+}
+
+impl SqlClipboard for arboard::Clipboard {
+    fn set_text(&mut self, text: String) -> Result<(), String> {
+        arboard::Clipboard::set_text(self, text).map_err(|e| e.to_string())
+    }
+}
+
+fn copy_sql_to_clipboard(
+    node_graph: &str,
+    clipboard: &mut impl SqlClipboard,
+) -> Result<(), AppError> {
+    let graph = parse_node_graph(node_graph)?;
+
+    let sql = generate_query_sql(&graph, None)?;
+
+    clipboard
+        .set_text(sql)
+        .map_err(|e| AppError::IoError(format!("Failed to copy SQL to clipboard: {}", e)))
+}
+
+#[tauri::command]
+async fn copy_query_sql_to_clipboard(node_graph: String) -> Result<(), AppError> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| AppError::IoError(format!("Failed to access clipboard: {}", e)))?;
+
+    copy_sql_to_clipboard(&node_graph, &mut clipboard)
+}
+
+#[tauri::command]
+async fn explain_node_graph(node_graph: String) -> Result<String, AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    query_builder::explain_node_graph(&graph).map_err(AppError::ValidationError)
+}
+
+#[tauri::command]
+async fn node_graph_to_mermaid(node_graph: String) -> Result<String, AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    query_builder::node_graph_to_mermaid(&graph).map_err(AppError::ValidationError)
+}
+
+#[tauri::command]
+async fn node_graph_to_dot(node_graph: String) -> Result<String, AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    query_builder::node_graph_to_dot(&graph).map_err(AppError::ValidationError)
+}
+
+#[tauri::command]
+async fn get_graph_complexity(node_graph: String) -> Result<u32, AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    Ok(query_builder::complexity_score(&graph))
+}
+
+fn expand_star(
+    node_graph: &query_builder::NodeGraph,
+    conn: &Connection,
+) -> Result<query_builder::NodeGraph, String> {
+    let mut expanded = node_graph.clone();
+
+    for table in node_graph.nodes.iter().filter(|n| n.node_type == "table") {
+        let table_name = table.data["table_name"]
+            .as_str()
+            .ok_or_else(|| format!("Table node {} has no table_name", table.id))?;
+        let schema = table.data["schema"].as_str();
+
+        let targets: Vec<&str> = node_graph
+            .edges
+            .iter()
+            .filter(|e| e.source == table.id)
+            .map(|e| e.target.as_str())
+            .collect();
+        let selects: Vec<&mut query_builder::Node> = expanded
+            .nodes
+            .iter_mut()
+            .filter(|n| targets.contains(&n.id.as_str()) && n.node_type == "select")
+            .filter(|n| {
+                n.data["columns"]
+                    .as_array()
+                    .is_none_or(|columns| columns.is_empty())
+            })
+            .collect();
+        if selects.is_empty() {
+            continue;
+        }
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT column_name FROM information_schema.columns \
+                 WHERE table_name = ? AND table_schema = COALESCE(?, current_schema()) \
+                 ORDER BY ordinal_position",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+        let columns: Vec<String> = stmt
+            .query_map(duckdb::params![table_name, schema], |row| row.get(0))
+            .map_err(|e| format!("Failed to query columns: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect results: {}", e))?;
+        if columns.is_empty() {
+            return Err(format!("Table not found: {}", table_name));
+        }
+
+        for select in selects {
+            select.data["columns"] = serde_json::json!(columns);
+        }
+    }
+
+    Ok(expanded)
+}
+
+#[tauri::command]
+async fn expand_star_in_select(node_graph: String) -> Result<String, AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let expanded = expand_star(&graph, &conn).map_err(AppError::DbError)?;
+
+    serde_json::to_string(&expanded)
+        .map_err(|e| AppError::ValidationError(format!("Failed to serialize node graph: {}", e)))
+}
+
+#[tauri::command]
+async fn import_sql_as_node_graph(path: String) -> Result<String, AppError> {
+    let sql = std::fs::read_to_string(&path)
+        .map_err(|e| AppError::IoError(format!("Failed to read SQL file {}: {}", path, e)))?;
+
+    let graph = query_builder::sql_to_node_graph(&sql).map_err(AppError::ValidationError)?;
+
+    serde_json::to_string(&graph)
+        .map_err(|e| AppError::ValidationError(format!("Failed to serialize node graph: {}", e)))
+}
+
+#[tauri::command]
+async fn join_preview(
+    left_table: String,
+    right_table: String,
+    left_key: String,
+    right_key: String,
+    join_type: String,
+    n: usize,
+) -> Result<String, AppError> {
+    for name in [&left_table, &right_table, &left_key, &right_key] {
+        if !is_valid_identifier(name) {
+            return Err(AppError::ValidationError(format!(
+                "Invalid identifier: {}",
+                name
+            )));
+        }
+    }
+
+    let join = match join_type.to_lowercase().as_str() {
+        "inner" => "INNER JOIN",
+        "left" => "LEFT JOIN",
+        "right" => "RIGHT JOIN",
+        "full" => "FULL OUTER JOIN",
+        _ => {
+            return Err(AppError::ValidationError(format!(
+                "Unsupported join type: {}",
+                join_type
+            )))
+        }
+    };
+
+    let sql = format!(
+        "SELECT * FROM {left} {join} {right} ON {left}.{left_key} = {right}.{right_key} LIMIT {n}",
+        left = left_table,
+        right = right_table,
+        join = join,
+        left_key = left_key,
+        right_key = right_key,
+        n = n
+    );
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let result = query_result_json(&conn, &sql)?;
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn get_query_row_count(node_graph: String) -> Result<i64, AppError> {
+    let mut graph = parse_node_graph(&node_graph)?;
+
+    let conn = duckdb_connect_readonly().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    coerce_filter_values(&mut graph, &conn).map_err(AppError::DbError)?;
+    let sql = generate_query_sql(&graph, None)?;
+
+    let count_sql = format!("SELECT COUNT(*) FROM ({}) AS subquery", sql);
+
+    let count: i64 = conn
+        .query_row(&count_sql, [], |row| row.get(0))
+        .map_err(|e| AppError::DbError(format!("Failed to get row count: {}", e)))?;
+
+    Ok(count)
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AssertionType {
+    NoNulls,
+    RowCountGte,
+    AllUnique,
+}
+
+#[derive(Debug, Deserialize)]
+struct Assertion {
+    #[serde(rename = "type")]
+    assertion_type: AssertionType,
+    #[serde(default)]
+    column: Option<String>,
+    #[serde(default)]
+    value: Option<i64>,
+}
+
+impl Assertion {
+    fn describe(&self) -> String {
+        match self.assertion_type {
+            AssertionType::NoNulls => format!("no_nulls({})", self.column.as_deref().unwrap_or("")),
+            AssertionType::RowCountGte => format!("row_count_gte({})", self.value.unwrap_or(0)),
+            AssertionType::AllUnique => {
+                format!("all_unique({})", self.column.as_deref().unwrap_or(""))
+            }
+        }
+    }
+
+    // Note: The query counts the rows or values that violate the assertion
+    fn check_sql(&self, sql: &str) -> Result<String, AppError> {
+        let column = || {
+            self.column.as_deref().map(quote_identifier).ok_or_else(|| {
+                AppError::ValidationError(format!("{} requires a column", self.describe()))
+            })
+        };
+
+        Ok(match self.assertion_type {
+            AssertionType::NoNulls => format!(
+                "SELECT COUNT(*) FROM ({}) AS subquery WHERE {} IS NULL",
+                sql,
+                column()?
+            ),
+            AssertionType::RowCountGte => {
+                if self.value.is_none() {
+                    return Err(AppError::ValidationError(format!(
+                        "{} requires a value",
+                        self.describe()
+                    )));
+                }
+                format!("SELECT COUNT(*) FROM ({}) AS subquery", sql)
+            }
+            AssertionType::AllUnique => {
+                let column = column()?;
+                format!(
+                    "SELECT COUNT({0}) - COUNT(DISTINCT {0}) FROM ({1}) AS subquery",
+                    column, sql
+                )
+            }
+        })
+    }
+
+    fn failure(&self, count: i64) -> Option<String> {
+        match self.assertion_type {
+            AssertionType::NoNulls => {
+                (count > 0).then(|| format!("{}: found {} NULL values", self.describe(), count))
+            }
+            AssertionType::RowCountGte => (count < self.value.unwrap_or(0))
+                .then(|| format!("{}: found {} rows", self.describe(), count)),
+            AssertionType::AllUnique => (count > 0)
+                .then(|| format!("{}: found {} duplicate values", self.describe(), count)),
+        }
+    }
+}
+
+#[tauri::command]
+async fn assert_query_result(
+    node_graph: String,
+    assertions: Vec<Assertion>,
+) -> Result<String, AppError> {
+    let graph = parse_node_graph(&node_graph)?;
+
+    let sql = generate_query_sql(&graph, None)?;
+    let checks = assertions
+        .iter()
+        .map(|assertion| assertion.check_sql(&sql))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let mut passed = Vec::new();
+    let mut failed = Vec::new();
+    for (assertion, check_sql) in assertions.iter().zip(checks) {
+        let count: i64 = conn
+            .query_row(&check_sql, [], |row| row.get(0))
+            .map_err(|e| {
+                AppError::DbError(format!(
+                    "Failed to evaluate {}: {}",
+                    assertion.describe(),
+                    e
+                ))
+            })?;
+
+        match assertion.failure(count) {
+            Some(message) => failed.push(message),
+            None => passed.push(assertion.describe()),
+        }
+    }
+
+    let result = serde_json::json!({
+        "passed": passed,
+        "failed": failed
+    });
+
+    Ok(result.to_string())
+}
+
+const AUDITED_QUERY_TABLES: [&str; 2] =
+    ["_streaksight_query_history", "_streaksight_saved_queries"];
+
+// Note: Works on tokens rather than raw text so that quoted strings and identifiers are not flagged
+fn sql_audit_reasons(sql: &str) -> Vec<String> {
+    use sqlparser::dialect::DuckDbDialect;
+    use sqlparser::keywords::Keyword;
+    use sqlparser::tokenizer::{Token, Tokenizer, Whitespace};
+
+    let tokens = match Tokenizer::new(&DuckDbDialect {}, sql).tokenize() {
+        Ok(tokens) => tokens,
+        Err(e) => return vec![format!("Cannot be tokenized: {}", e.message)],
+    };
+    let code: Vec<&Token> = tokens
+        .iter()
+        .filter(|t| !matches!(t, Token::Whitespace(_)))
+        .collect();
+    let is_keyword = |token: &Token, keyword: Keyword| matches!(token, Token::Word(w) if w.keyword == keyword && w.quote_style.is_none());
+
+    let mut reasons = Vec::new();
+    if tokens.iter().any(|t| {
+        matches!(
+            t,
+            Token::Whitespace(Whitespace::SingleLineComment { .. })
+                | Token::Whitespace(Whitespace::MultiLineComment(_))
+        )
+    }) {
+        reasons.push("Contains a SQL comment".to_string());
+    }
+    if code
+        .iter()
+        .rposition(|t| !matches!(t, Token::SemiColon))
+        .is_some_and(|last| code[..last].iter().any(|t| matches!(t, Token::SemiColon)))
+    {
+        reasons.push("Contains multiple statements".to_string());
+    }
+    if code.windows(2).any(|w| {
+        is_keyword(w[0], Keyword::UNION)
+            && (is_keyword(w[1], Keyword::SELECT) || is_keyword(w[1], Keyword::ALL))
+    }) {
+        reasons.push("Contains UNION SELECT".to_string());
+    }
+    if code.windows(4).any(|w| {
+        is_keyword(w[0], Keyword::OR)
+            && matches!(w[2], Token::Eq)
+            && matches!(w[1], Token::Number(..) | Token::SingleQuotedString(_))
+            && w[1] == w[3]
+    }) {
+        reasons.push("Contains an always-true OR condition".to_string());
+    }
+
+    reasons
+}
+
+fn main_table_exists(conn: &Connection, table_name: &str) -> Result<bool, AppError> {
+    conn.query_row(
+        "SELECT COUNT(*) > 0 FROM information_schema.tables \
+         WHERE table_schema = 'main' AND table_name = ?",
+        [table_name],
+        |row| row.get(0),
+    )
+    .map_err(|e| AppError::DbError(format!("Failed to check table: {}", e)))
+}
+
+// Note: Nothing in the app writes these tables yet, so missing ones are skipped
+#[tauri::command]
+async fn sql_injection_audit() -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let mut flagged = Vec::new();
+    for table_name in AUDITED_QUERY_TABLES {
+        if !main_table_exists(&conn, table_name)? {
+            continue;
+        }
+
+        let entries: Vec<(i64, Option<String>)> = conn
+            .prepare(&format!(
+                "SELECT rowid, sql FROM {} ORDER BY rowid",
+                table_name
+            ))
+            .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| AppError::DbError(format!("Failed to read {}: {}", table_name, e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+
+        for (rowid, sql) in entries {
+            let Some(sql) = sql else { continue };
+            let reasons = sql_audit_reasons(&sql);
+            if !reasons.is_empty() {
+                flagged.push(serde_json::json!({
+                    "table": table_name,
+                    "rowid": rowid,
+                    "sql": sql,
+                    "reasons": reasons
+                }));
+            }
+        }
+    }
+
+    Ok(serde_json::Value::Array(flagged).to_string())
+}
+
+const SAVED_QUERIES_TABLE: &str = "_streaksight_saved_queries";
+
+// Note: A saved query counts when it reads the table and one of its select, filter or sort
+// nodes names the column; graphs that cannot be read are reported separately
+#[tauri::command]
+async fn audit_column_access(table_name: String, column_name: String) -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let mut queries = Vec::new();
+    let mut unreadable = Vec::new();
+    if main_table_exists(&conn, SAVED_QUERIES_TABLE)? {
+        let entries: Vec<(String, Option<String>)> = conn
+            .prepare(&format!(
+                "SELECT name, node_graph FROM {} ORDER BY rowid",
+                SAVED_QUERIES_TABLE
+            ))
+            .map_err(|e| AppError::DbError(format!("Failed to prepare statement: {}", e)))?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| {
+                AppError::DbError(format!("Failed to read {}: {}", SAVED_QUERIES_TABLE, e))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::DbError(format!("Failed to collect results: {}", e)))?;
+
+        for (name, node_graph) in entries {
+            let Some(node_graph) = node_graph else {
+                continue;
+            };
+            match graph_reads_column(&node_graph, &table_name, &column_name) {
+                Ok(true) => queries.push(name),
+                Ok(false) => {}
+                Err(_) => unreadable.push(name),
+            }
+        }
+    }
+
+    let result = serde_json::json!({
+        "queries": queries,
+        "unreadable": unreadable
+    });
+
+    Ok(result.to_string())
+}
+
+fn graph_reads_column(
+    node_graph: &str,
+    table_name: &str,
+    column_name: &str,
+) -> Result<bool, String> {
+    let graph: query_builder::NodeGraph = serde_json::from_str(node_graph)
+        .map_err(|e| format!("Failed to parse node graph: {}", e))?;
+
+    let reads_table = graph.nodes.iter().any(|node| {
+        node.node_type == "table"
+            && node.data["table_name"]
+                .as_str()
+                .is_some_and(|name| name.eq_ignore_ascii_case(table_name))
+    });
+    if !reads_table {
+        return Ok(false);
+    }
+
+    for node in &graph.nodes {
+        let columns = query_builder::referenced_columns(node)?;
+        if columns.iter().any(|c| c.eq_ignore_ascii_case(column_name)) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+const WORKSPACE_DATABASE_DIR: &str = "database";
+const WORKSPACE_QUERIES_DIR: &str = "queries";
+const WORKSPACE_CONNECTORS_DIR: &str = "connectors";
+
+// Note: Removes the staging directory on drop, so early returns do not leave extracted data behind
+struct StagingDir(PathBuf);
+
+impl StagingDir {
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn workspace_staging_dir(kind: &str) -> Result<StagingDir, AppError> {
+    let id = WORKSPACE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let dir = temp_dir().join(format!(
+        "streaksight_workspace_{}_{}_{}",
+        kind,
+        std::process::id(),
+        id
+    ));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| AppError::IoError(format!("Failed to clear {}: {}", dir.display(), e)))?;
+    }
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| AppError::IoError(format!("Failed to create {}: {}", dir.display(), e)))?;
+    Ok(StagingDir(dir))
+}
+
+// Note: Contents are streamed into the entry, so exported tables are never held in memory whole
+fn add_to_archive<W: std::io::Write + std::io::Seek>(
+    archive: &mut zip::ZipWriter<W>,
+    name: &str,
+    contents: &mut impl std::io::Read,
+) -> Result<(), AppError> {
+    archive
+        .start_file(name, zip::write::SimpleFileOptions::default())
+        .and_then(|_| std::io::copy(contents, archive).map_err(Into::into))
+        .map(|_| ())
+        .map_err(|e| AppError::IoError(format!("Failed to write {} to archive: {}", name, e)))
+}
+
+fn open_file(path: &Path) -> Result<std::fs::File, AppError> {
+    std::fs::File::open(path)
+        .map_err(|e| AppError::IoError(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+// Note: Connectors that live in their own directory are archived together with their connector.json
+fn connector_archive_files(connectors_dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut files = Vec::new();
+    for manifest in connector_registry::scan_connectors(connectors_dir) {
+        let Ok(relative) = manifest.path.strip_prefix(connectors_dir) else {
+            continue;
+        };
+        let manifest_file = relative.with_file_name("connector.json");
+        if relative.parent().is_some_and(|p| !p.as_os_str().is_empty())
+            && connectors_dir.join(&manifest_file).is_file()
+        {
+            files.push(manifest_file);
+        }
+        files.push(relative.to_path_buf());
+    }
+
+    files
+        .into_iter()
+        .map(|relative| {
+            let name = format!(
+                "{}/{}",
+                WORKSPACE_CONNECTORS_DIR,
+                relative.to_string_lossy().replace('\\', "/")
+            );
+            (name, connectors_dir.join(relative))
+        })
+        .collect()
+}
+
+// Note: The saved queries table travels with the database export; the JSON copies can be read outside the app
+fn write_workspace_archive(
+    conn: &Connection,
+    connectors_dir: &Path,
+    dest_path: &Path,
+) -> Result<serde_json::Value, AppError> {
+    let staging = workspace_staging_dir("export")?;
+    let database_dir = staging.path().join(WORKSPACE_DATABASE_DIR);
+    conn.execute_batch(&format!(
+        "EXPORT DATABASE '{}'",
+        database_dir.to_string_lossy().replace('\'', "''")
+    ))
+    .map_err(|e| AppError::DbError(format!("Failed to export database: {}", e)))?;
+
+    let file = std::fs::File::create(dest_path).map_err(|e| {
+        AppError::IoError(format!("Failed to create {}: {}", dest_path.display(), e))
+    })?;
+    let mut archive = zip::ZipWriter::new(file);
+
+    let mut database_files: Vec<PathBuf> = std::fs::read_dir(&database_dir)
+        .map_err(|e| AppError::IoError(format!("Failed to read database export: {}", e)))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    database_files.sort();
+    for path in &database_files {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        add_to_archive(
+            &mut archive,
+            &format!("{}/{}", WORKSPACE_DATABASE_DIR, name),
+            &mut open_file(path)?,
+        )?;
+    }
+
+    let mut query_count = 0;
+    if main_table_exists(conn, SAVED_QUERIES_TABLE)? {
+        let saved = query_result_json(
+            conn,
+            &format!("SELECT * FROM {} ORDER BY rowid", SAVED_QUERIES_TABLE),
+        )?;
+        for (i, query) in saved["rows"].as_array().into_iter().flatten().enumerate() {
+            let json = serde_json::to_vec_pretty(query)
+                .map_err(|e| AppError::IoError(format!("Failed to serialize query: {}", e)))?;
+            add_to_archive(
+                &mut archive,
+                &format!("{}/{}.json", WORKSPACE_QUERIES_DIR, i + 1),
+                &mut json.as_slice(),
+            )?;
+            query_count += 1;
+        }
+    }
+
+    let connector_files = connector_archive_files(connectors_dir);
+    for (name, path) in &connector_files {
+        add_to_archive(&mut archive, name, &mut open_file(path)?)?;
+    }
+
+    archive
+        .finish()
+        .map_err(|e| AppError::IoError(format!("Failed to finish archive: {}", e)))?;
+
+    Ok(serde_json::json!({
+        "path": dest_path.to_string_lossy(),
+        "queries": query_count,
+        "connectors": connector_files.len()
+    }))
+}
+
+// Note: EXPORT DATABASE writes these as plain CREATE statements, so each one has to be gone before an import
+const WORKSPACE_OBJECTS_SQL: &str = "\
+    SELECT 'table' AS kind, table_name AS name FROM duckdb_tables() \
+    WHERE database_name = current_database() AND schema_name = 'main' AND NOT internal \
+    UNION ALL SELECT 'view', view_name FROM duckdb_views() \
+    WHERE database_name = current_database() AND schema_name = 'main' AND NOT internal \
+    UNION ALL SELECT 'sequence', sequence_name FROM duckdb_sequences() \
+    WHERE database_name = current_database() AND schema_name = 'main' \
+    UNION ALL SELECT DISTINCT function_type, function_name FROM duckdb_functions() \
+    WHERE database_name = current_database() AND schema_name = 'main' AND NOT internal \
+    AND function_type IN ('macro', 'table_macro') \
+    ORDER BY name, kind";
+
+// Note: Returns (kind, name) pairs, where kind is table, view, sequence, macro or table_macro
+fn workspace_objects(conn: &Connection) -> Result<Vec<(String, String)>, AppError> {
+    conn.prepare(WORKSPACE_OBJECTS_SQL)
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<(String, String)>, _>>()
+        })
+        .map_err(|e| AppError::DbError(format!("Failed to list database objects: {}", e)))
+}
+
+fn archived_objects(database_dir: &Path) -> Result<Vec<(String, String)>, AppError> {
+    let conn = Connection::open_in_memory().map_err(|e| AppError::DbError(e.to_string()))?;
+    conn.execute_batch(&format!(
+        "IMPORT DATABASE '{}'",
+        database_dir.to_string_lossy().replace('\'', "''")
+    ))
+    .map_err(|e| AppError::ValidationError(format!("Invalid workspace archive: {}", e)))?;
+
+    workspace_objects(&conn)
+}
+
+// Note: Views and macros go before the tables they read, and sequences after the tables that use them
+fn drop_workspace_object_sql(kind: &str, name: &str) -> (u8, String) {
+    let (order, statement) = match kind {
+        "view" => (0, "DROP VIEW"),
+        "macro" => (1, "DROP MACRO"),
+        "table_macro" => (1, "DROP MACRO TABLE"),
+        "sequence" => (3, "DROP SEQUENCE"),
+        _ => (2, "DROP TABLE"),
+    };
+    (
+        order,
+        format!("{} IF EXISTS {}", statement, quote_identifier(name)),
+    )
+}
+
+fn archived_connector_files(connectors_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = std::fs::read_dir(connectors_dir) else {
+        return files;
+    };
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.is_dir() {
+            if let Ok(nested) = std::fs::read_dir(&path) {
+                files.extend(
+                    nested
+                        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                        .filter(|path| path.is_file()),
+                );
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+// Note: Nothing is written until every conflict has been checked, and the tables are replaced in one transaction
+fn read_workspace_archive(
+    conn: &mut Connection,
+    connectors_dir: &Path,
+    src_path: &Path,
+    overwrite: bool,
+) -> Result<serde_json::Value, AppError> {
+    let file = std::fs::File::open(src_path)
+        .map_err(|e| AppError::IoError(format!("Failed to open {}: {}", src_path.display(), e)))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| AppError::ValidationError(format!("Invalid workspace archive: {}", e)))?;
+
+    let staging = workspace_staging_dir("import")?;
+    let staging_dir = staging.path();
+    archive
+        .extract(staging_dir)
+        .map_err(|e| AppError::ValidationError(format!("Invalid workspace archive: {}", e)))?;
+
+    let database_dir = staging_dir.join(WORKSPACE_DATABASE_DIR);
+    if !database_dir.join("schema.sql").is_file() {
+        return Err(AppError::ValidationError(
+            "Invalid workspace archive: missing database export".to_string(),
+        ));
+    }
+    let archived = archived_objects(&database_dir)?;
+    let tables: Vec<&String> = archived
+        .iter()
+        .filter(|(kind, _)| kind == "table")
+        .map(|(_, name)| name)
+        .collect();
+    // Note: Tables, views and sequences share a namespace, so a clash is any existing object with an archived name
+    let existing: Vec<(String, String)> = workspace_objects(conn)?
+        .into_iter()
+        .filter(|(_, name)| archived.iter().any(|(_, archived)| archived == name))
+        .collect();
+
+    let archived_connectors_dir = staging_dir.join(WORKSPACE_CONNECTORS_DIR);
+    let connector_files: Vec<(PathBuf, PathBuf)> =
+        archived_connector_files(&archived_connectors_dir)
+            .into_iter()
+            .filter_map(|path| {
+                let relative = path.strip_prefix(&archived_connectors_dir).ok()?;
+                Some((connectors_dir.join(relative), path.clone()))
+            })
+            .collect();
+
+    if !overwrite {
+        let mut conflicts: Vec<String> = existing.iter().map(|(_, name)| name.clone()).collect();
+        conflicts.dedup();
+        conflicts.extend(
+            connector_files
+                .iter()
+                .filter(|(dest, _)| dest.exists())
+                .map(|(dest, _)| dest.to_string_lossy().into_owned()),
+        );
+        if !conflicts.is_empty() {
+            return Err(AppError::ValidationError(format!(
+                "Workspace import would overwrite: {}",
+                conflicts.join(", ")
+            )));
+        }
+    }
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::DbError(format!("Failed to begin transaction: {}", e)))?;
+    let mut drops: Vec<(u8, String)> = existing
+        .iter()
+        .map(|(kind, name)| drop_workspace_object_sql(kind, name))
+        .collect();
+    drops.sort();
+    for (_, statement) in &drops {
+        tx.execute_batch(statement)
+            .map_err(|e| AppError::DbError(format!("Failed to run {}: {}", statement, e)))?;
+    }
+    tx.execute_batch(&format!(
+        "IMPORT DATABASE '{}'",
+        database_dir.to_string_lossy().replace('\'', "''")
+    ))
+    .map_err(|e| AppError::DbError(format!("Failed to import database: {}", e)))?;
+    tx.commit()
+        .map_err(|e| AppError::DbError(format!("Failed to commit transaction: {}", e)))?;
+
+    for (dest, path) in &connector_files {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                AppError::IoError(format!("Failed to create {}: {}", parent.display(), e))
+            })?;
+        }
+        std::fs::copy(path, dest)
+            .map_err(|e| AppError::IoError(format!("Failed to copy connector: {}", e)))?;
+    }
+
+    Ok(serde_json::json!({
+        "tables": tables,
+        "connectors": connector_files.len()
+    }))
+}
+
+fn workspace_connectors_dir() -> Result<PathBuf, AppError> {
+    APP_DATA_PATH
+        .get()
+        .map(|path| path.join("connectors"))
+        .ok_or_else(|| AppError::IoError("App data path is not set".to_string()))
+}
+
+#[tauri::command]
+async fn export_workspace(dest_path: String) -> Result<String, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let result =
+        write_workspace_archive(&conn, &workspace_connectors_dir()?, Path::new(&dest_path))?;
+
+    Ok(result.to_string())
+}
+
+#[tauri::command]
+async fn import_workspace(src_path: String, overwrite: bool) -> Result<String, AppError> {
+    let mut conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    let result = read_workspace_archive(
+        &mut conn,
+        &workspace_connectors_dir()?,
+        Path::new(&src_path),
+        overwrite,
+    )?;
+    invalidate_query_cache();
+
+    Ok(result.to_string())
+}
+
+// Note: Opens a read-write connection and writes a temp table, so a locked or read-only database fails here
+fn check_database() -> Result<PathBuf, AppError> {
+    let path = duckdb_path().map_err(|e| AppError::DbError(e.to_string()))?;
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    conn.execute_batch("CREATE TEMP TABLE _ping AS SELECT 1")
+        .map_err(|e| AppError::DbError(format!("Failed to write to the database: {}", e)))?;
+    conn.execute_batch("DROP TABLE _ping")
+        .map_err(|e| AppError::DbError(format!("Failed to drop ping table: {}", e)))?;
+
+    Ok(path)
+}
+
+#[tauri::command]
+async fn test_connection() -> Result<String, AppError> {
+    let path = check_database()?;
+
+    let result = serde_json::json!({
+        "status": "ok",
+        "path": path.to_string_lossy()
+    });
+
+    Ok(result.to_string())
+}
+
+const TABLE_SIZE_UPDATE_EVENT: &str = "table:size-update";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct TableSizeUpdate {
+    row_count: i64,
+    size_bytes: i64,
+}
+
+trait TableSizeEmitter: Send + Sync + 'static {
+    fn emit_size(&self, update: &TableSizeUpdate) -> Result<(), String>;
+}
+
+struct WindowSizeEmitter {
+    app: tauri::AppHandle,
+    window_label: String,
+}
+
+impl TableSizeEmitter for WindowSizeEmitter {
+    fn emit_size(&self, update: &TableSizeUpdate) -> Result<(), String> {
+        self.app
+            .emit_to(self.window_label.as_str(), TABLE_SIZE_UPDATE_EVENT, update)
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn table_monitors() -> &'static Mutex<HashMap<String, tokio::task::JoinHandle<()>>> {
+    TABLE_MONITORS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn query_table_size(table_name: &str) -> Result<TableSizeUpdate, AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+
+    let row_count: i64 = conn
+        .query_row(&format!("SELECT COUNT(*) FROM {}", table_name), [], |row| {
+            row.get(0)
+        })
+        .map_err(|e| AppError::DbError(format!("Failed to count rows: {}", e)))?;
+
+    let size_bytes: i64 = conn
+        .query_row(
+            "SELECT COUNT(DISTINCT block_id) * (SELECT block_size FROM pragma_database_size()) \
+             FROM pragma_storage_info(?) WHERE block_id >= 0",
+            [table_name],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::DbError(format!("Failed to get table size: {}", e)))?;
+
+    Ok(TableSizeUpdate {
+        row_count,
+        size_bytes,
+    })
+}
+
+fn spawn_table_size_monitor(
+    table_name: String,
+    interval_ms: u64,
+    emitter: impl TableSizeEmitter,
+) -> Result<(), AppError> {
+    if !is_valid_identifier(&table_name) {
+        return Err(AppError::ValidationError("Invalid table name".to_string()));
+    }
+
+    if interval_ms == 0 {
+        return Err(AppError::ValidationError(
+            "Interval must be greater than zero".to_string(),
+        ));
+    }
+
+    let monitored_table = table_name.clone();
+    let handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_millis(interval_ms));
+        loop {
+            interval.tick().await;
+            match query_table_size(&monitored_table) {
+                Ok(update) => {
+                    if let Err(e) = emitter.emit_size(&update) {
+                        eprintln!("Failed to emit size of {}: {}", monitored_table, e);
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("Failed to query size of {}: {}", monitored_table, e),
+            }
+        }
+    });
+
+    let mut monitors = table_monitors()
+        .lock()
+        .map_err(|e| AppError::ValidationError(format!("Monitor registry poisoned: {}", e)))?;
+    if let Some(previous) = monitors.insert(table_name, handle) {
+        previous.abort();
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn monitor_table_size(
+    app: tauri::AppHandle,
+    table_name: String,
+    interval_ms: u64,
+    window_label: String,
+) -> Result<(), AppError> {
+    spawn_table_size_monitor(
+        table_name,
+        interval_ms,
+        WindowSizeEmitter { app, window_label },
+    )
+}
+
+#[tauri::command]
+async fn stop_monitor(table_name: String) -> Result<(), AppError> {
+    let mut monitors = table_monitors()
+        .lock()
+        .map_err(|e| AppError::ValidationError(format!("Monitor registry poisoned: {}", e)))?;
+
+    if let Some(handle) = monitors.remove(&table_name) {
+        handle.abort();
+    }
+
+    Ok(())
+}
+
+fn aggregation_refreshes() -> &'static Mutex<HashMap<String, tokio::task::JoinHandle<()>>> {
+    AGGREGATION_REFRESHES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn refresh_aggregation(dest_table: &str, sql: &str) -> Result<(), AppError> {
+    let conn = duckdb_connect().map_err(|e| AppError::DbError(e.to_string()))?;
+    conn.execute_batch(&format!(
+        "CREATE OR REPLACE TABLE {} AS {}",
+        dest_table, sql
+    ))
+    .map_err(|e| AppError::DbError(format!("Failed to materialize aggregation: {}", e)))?;
+    invalidate_query_cache();
+    Ok(())
+}
+
+// Note: DuckDB has neither materialized views nor triggers, so the destination table is
+// rebuilt from the aggregation on every tick of the interval
+#[tauri::command]
+async fn create_incremental_aggregation(
+    source_table: String,
+    agg_node_graph: String,
+    dest_table: String,
+    interval_ms: u64,
+) -> Result<(), AppError> {
+    for table_name in [&source_table, &dest_table] {
+        if !is_valid_identifier(table_name) {
+            return Err(AppError::ValidationError("Invalid table name".to_string()));
+        }
+    }
+    if interval_ms == 0 {
+        return Err(AppError::ValidationError(
+            "Interval must be greater than zero".to_string(),
+        ));
+    }
+
+    let graph = parse_node_graph(&agg_node_graph)?;
+    if !graph
+        .nodes
+        .iter()
+        .any(|node| node.node_type == "aggregation")
+    {
+        return Err(AppError::ValidationError(
+            "Node graph has no aggregation node".to_string(),
+        ));
+    }
+    let reads_source = query_builder::source_table(&graph)
+        .map_err(AppError::ValidationError)?
+        .is_some_and(|table_name| table_name == source_table);
+    if !reads_source {
+        return Err(AppError::ValidationError(format!(
+            "Node graph does not read from {}",
+            source_table
+        )));
+    }
+
+    let sql = generate_query_sql(&graph, None)?;
+    refresh_aggregation(&dest_table, &sql)?;
+
+    let refreshed_table = dest_table.clone();
+    let handle = tokio::spawn(async move {
+        let period = std::time::Duration::from_millis(interval_ms);
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = refresh_aggregation(&refreshed_table, &sql) {
+                eprintln!("Failed to refresh {}: {}", refreshed_table, e);
+            }
+        }
+    });
+
+    let mut refreshes = aggregation_refreshes()
+        .lock()
+        .map_err(|e| AppError::ValidationError(format!("Aggregation registry poisoned: {}", e)))?;
+    if let Some(previous) = refreshes.insert(dest_table, handle) {
+        previous.abort();
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn stop_incremental_aggregation(dest_table: String) -> Result<(), AppError> {
+    let mut refreshes = aggregation_refreshes()
+        .lock()
+        .map_err(|e| AppError::ValidationError(format!("Aggregation registry poisoned: {}", e)))?;
+
+    if let Some(handle) = refreshes.remove(&dest_table) {
+        handle.abort();
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_temp_dir(path: String) -> Result<(), AppError> {
+    let path = PathBuf::from(path);
+    std::fs::create_dir_all(&path).map_err(|e| {
+        AppError::IoError(format!("Failed to create temp directory {:?}: {}", path, e))
+    })?;
+
+    TEMP_DIR_PATH
+        .set(path)
+        .map_err(|_| AppError::ValidationError("Temp directory already set".to_string()))
+}
+
+use duckdb::{Connection, OptionalExt};
+use query_builder::is_valid_identifier;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{path::BaseDirectory, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+static APP_DATA_PATH: OnceLock<PathBuf> = OnceLock::new();
+static TEMP_DIR_PATH: OnceLock<PathBuf> = OnceLock::new();
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+static TYPE_MAPPINGS: OnceLock<Vec<TypeMapping>> = OnceLock::new();
+static NULL_REPRESENTATION: OnceLock<NullRepr> = OnceLock::new();
+static STRICT_MODE: OnceLock<bool> = OnceLock::new();
+static SYNC_COUNTER: AtomicU64 = AtomicU64::new(0);
+static WORKSPACE_COUNTER: AtomicU64 = AtomicU64::new(0);
+static DISCOVERY_COUNTER: AtomicU64 = AtomicU64::new(0);
+static FILE_WATCHERS: OnceLock<Mutex<HashMap<String, notify::RecommendedWatcher>>> =
+    OnceLock::new();
+static TABLE_MONITORS: OnceLock<Mutex<HashMap<String, tokio::task::JoinHandle<()>>>> =
+    OnceLock::new();
+static AGGREGATION_REFRESHES: OnceLock<Mutex<HashMap<String, tokio::task::JoinHandle<()>>>> =
+    OnceLock::new();
+static QUERY_CACHE: OnceLock<Mutex<lru::LruCache<String, String>>> = OnceLock::new();
+
+pub fn set_app_data_path(path: PathBuf) {
+    APP_DATA_PATH.set(path).ok();
+}
+
+fn temp_dir() -> PathBuf {
+    TEMP_DIR_PATH
+        .get()
+        .cloned()
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .setup(|app| {
+            let app_data_path = app.path().resolve("data", BaseDirectory::AppData)?;
+            std::fs::create_dir_all(&app_data_path)?;
+            set_app_data_path(app_data_path);
+            let _ = APP_HANDLE.set(app.handle().clone());
+            if let Err(e) = check_database() {
+                app.dialog()
+                    .message(format!("The database cannot be used: {}", e))
+                    .title("Database error")
+                    .kind(MessageDialogKind::Error)
+                    .show(|_| {});
+            }
+            Ok(())
+        })
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_opener::init())
+        .invoke_handler(tauri::generate_handler![
+            test_connection,
+            list_connector_types,
+            describe_connector,
+            config,
+            discovery,
+            bulk_discovery,
+            sync,
+            sync_all,
+            multi_table_sync,
+            tables,
+            table_schema,
+            set_type_mappings,
+            set_null_representation,
+            set_strict_mode,
+            schema_diff,
+            detect_primary_key_candidates,
+            suggest_pivot,
+            estimate_table_size_bytes,
+            ingest_from_url,
+            generate_test_data,
+            get_table_checksum,
+            generate_create_table_sql,
+            run_query,
+            get_cached_result,
+            run_query_to_file,
+            get_query_row_count,
+            assert_query_result,
+            sql_injection_audit,
+            audit_column_access,
+            export_workspace,
+            flatten_json_column,
+            hash_column,
+            generate_fake_data_for_schema,
+            import_workspace,
+            join_preview,
+            get_query_schema,
+            node_graph_to_json_schema,
+            export_node_graph_as_sql,
+            copy_query_sql_to_clipboard,
+            import_sql_as_node_graph,
+            explain_node_graph,
+            node_graph_to_mermaid,
+            node_graph_to_dot,
+            get_graph_complexity,
+            expand_star_in_select,
+            drop_table,
+            export_table,
+            merge_table,
+            column_correlation,
+            apply_schema_to_table,
+            create_macro,
+            drop_macro,
+            call_macro,
+            list_macros,
+            create_sequence,
+            next_value,
+            create_schema,
+            drop_schema,
+            list_schemas,
+            apply_migrations,
+            get_migration_history,
+            create_template,
+            apply_template,
+            list_templates,
+            get_duckdb_version,
+            monitor_table_size,
+            stop_monitor,
+            create_incremental_aggregation,
+            stop_incremental_aggregation,
+            set_temp_dir
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deno_core::{error::AnyError, JsRuntime, RuntimeOptions};
+    use std::rc::Rc;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn setup_test_env() {
+        INIT.call_once(|| {
+            let temp_dir = std::env::temp_dir().join("streaksight_test");
+            let _ = std::fs::remove_file(temp_dir.join("database.duckdb"));
+            let _ = std::fs::remove_file(temp_dir.join("database.duckdb.wal"));
+            std::fs::create_dir_all(&temp_dir).unwrap();
+            set_app_data_path(temp_dir);
+        });
+    }
+
+    async fn run_plugin_test(test_csv_path: &str) -> Result<(), AnyError> {
+        let current_dir = std::env::current_dir()?;
+        let connector_path = current_dir.join("src/LocalFileCSVConnector.js");
+
+        let test_js = format!(
+            r#"import {{ discovery, sync }} from "{}";
+               export async function main() {{
+                   const config = {{
+                       filePath: "{}",
+                       delimiter: ",",
+                       hasHeader: true
+                   }};
+                   const schema = await discovery(config);
+                   console.log("Schema:", JSON.stringify(schema));
+                   await sync("test_csv_table", config, schema);
+                   console.log("Sync completed");
+               }}"#,
+            connector_path.to_str().unwrap().replace("\\", "/"),
+            test_csv_path
+        );
+
+        let test_js_path = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("test_plugin.js");
+        std::fs::write(&test_js_path, test_js)?;
+
+        let plugin_module =
+            deno_core::resolve_path(test_js_path.to_str().unwrap(), &std::env::current_dir()?)?;
+
+        let mut rt = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+
+        let id = rt.load_main_es_module(&plugin_module).await?;
+        let eval = rt.mod_evaluate(id);
+        rt.run_event_loop(Default::default()).await?;
+        eval.await?;
+
+        let script = format!(
+            r#"import("{}").then(m => m.main())"#,
+            plugin_module.as_str()
+        );
+        rt.execute_script("<call_main>", script)?;
+        rt.run_event_loop(Default::default()).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_csv_connector_sync() {
+        setup_test_env();
+
+        // Create test CSV file
+        let test_csv = r#"id,name,active,score
+1,Alice,true,95.5
+2,Bob,false,87.3
+3,Charlie,true,92.1"#;
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let csv_path = temp_dir.join("test_sync.csv");
+        std::fs::write(&csv_path, test_csv).unwrap();
+
+        run_plugin_test(csv_path.to_str().unwrap()).await.unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT id, name, active, score FROM test_csv_table ORDER BY id")
+            .unwrap();
+        let mut rows = stmt.query([]).unwrap();
+
+        let row = rows.next().unwrap().unwrap();
+        let id: f64 = row.get(0).unwrap();
+        let name: String = row.get(1).unwrap();
+        let active: bool = row.get(2).unwrap();
+        let score: f64 = row.get(3).unwrap();
+        assert_eq!(id, 1.0);
+        assert_eq!(name, "Alice");
+        assert_eq!(active, true);
+        assert_eq!(score, 95.5);
+
+        let row = rows.next().unwrap().unwrap();
+        let id: f64 = row.get(0).unwrap();
+        let name: String = row.get(1).unwrap();
+        let active: bool = row.get(2).unwrap();
+        let score: f64 = row.get(3).unwrap();
+        assert_eq!(id, 2.0);
+        assert_eq!(name, "Bob");
+        assert_eq!(active, false);
+        assert_eq!(score, 87.3);
+
+        let row = rows.next().unwrap().unwrap();
+        let id: f64 = row.get(0).unwrap();
+        let name: String = row.get(1).unwrap();
+        let active: bool = row.get(2).unwrap();
+        let score: f64 = row.get(3).unwrap();
+        assert_eq!(id, 3.0);
+        assert_eq!(name, "Charlie");
+        assert_eq!(active, true);
+        assert_eq!(score, 92.1);
+
+        assert!(rows.next().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sync_all() {
+        setup_test_env();
+
+        let test_csv = "id,name\n1,Alice\n2,Bob";
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let csv_path = temp_dir.join("test_sync_all.csv");
+        std::fs::write(&csv_path, test_csv).unwrap();
+
+        let config = serde_json::json!({ "filePath": csv_path.to_string_lossy() }).to_string();
+        let syncs = vec![
+            SyncRequest {
+                ty: connector_type::LOCAL_FILE_CSV.to_string(),
+                name: "test_sync_all_a".to_string(),
+                config: config.clone(),
+                schema: "{}".to_string(),
+                on_error: None,
+                primary_keys: None,
+                on_conflict: None,
+                include_row_hash: None,
+                schema_evolution: None,
+            },
+            SyncRequest {
+                ty: connector_type::LOCAL_FILE_CSV.to_string(),
+                name: "test_sync_all_b".to_string(),
+                config,
+                schema: "{}".to_string(),
+                on_error: None,
+                primary_keys: None,
+                on_conflict: None,
+                include_row_hash: None,
+                schema_evolution: None,
+            },
+            SyncRequest {
+                ty: "UnknownType".to_string(),
+                name: "test_sync_all_unknown".to_string(),
+                config: "{}".to_string(),
+                schema: "{}".to_string(),
+                on_error: None,
+                primary_keys: None,
+                on_conflict: None,
+                include_row_hash: None,
+                schema_evolution: None,
+            },
+        ];
+
+        let result = sync_all(syncs, Some(2)).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result["successes"],
+            serde_json::json!(["test_sync_all_a", "test_sync_all_b"])
+        );
+        assert_eq!(
+            result["failures"],
+            serde_json::json!([{
+                "name": "test_sync_all_unknown",
+                "error": {
+                    "code": "VALIDATION_ERROR",
+                    "message": "Unknown connector type"
+                }
+            }])
+        );
+
+        let conn = duckdb_connect().unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM test_sync_all_b", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_discovery() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let users_path = temp_dir.join("test_bulk_discovery_users.csv");
+        let orders_path = temp_dir.join("test_bulk_discovery_orders.csv");
+        std::fs::write(&users_path, "id,name\n1,Alice\n2,Bob").unwrap();
+        std::fs::write(&orders_path, "order_id,amount,user_id\n10,9.5,1").unwrap();
+
+        let request = |ty: &str, name: &str, path: &std::path::Path| DiscoveryRequest {
+            ty: ty.to_string(),
+            name: name.to_string(),
+            config: serde_json::json!({ "filePath": path.to_string_lossy() }).to_string(),
+        };
+        let requests = vec![
+            request(connector_type::LOCAL_FILE_CSV, "users", &users_path),
+            request("UnknownType", "unknown", &users_path),
+            request(connector_type::LOCAL_FILE_CSV, "orders", &orders_path),
+        ];
+
+        let result = bulk_discovery(requests, Some(2)).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        let column_names = |entry: &serde_json::Value| -> Vec<String> {
+            entry["schema"]["columns"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|column| column["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(result[0]["name"], "users");
+        assert_eq!(result[0]["error"], serde_json::Value::Null);
+        assert_eq!(column_names(&result[0]), vec!["id", "name"]);
+        assert_eq!(
+            result[1],
+            serde_json::json!({
+                "name": "unknown",
+                "schema": null,
+                "error": {
+                    "code": "VALIDATION_ERROR",
+                    "message": "Unknown connector type"
+                }
+            })
+        );
+        assert_eq!(result[2]["name"], "orders");
+        assert_eq!(
+            column_names(&result[2]),
+            vec!["order_id", "amount", "user_id"]
+        );
+
+        let result = bulk_discovery(Vec::new(), None).await.unwrap();
+        assert_eq!(result, "[]");
+    }
+
+    fn transcoded_copies_left(prefix: &str) -> usize {
+        std::fs::read_dir(super::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn test_bulk_discovery_with_encoding() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let customers_path = temp_dir.join("test_bulk_discovery_customers_latin1.csv");
+        let products_path = temp_dir.join("test_bulk_discovery_products_latin1.csv");
+        std::fs::write(&customers_path, b"id,stra\xdfe\n1,Hauptstra\xdfe\n").unwrap();
+        std::fs::write(&products_path, b"sku,pr\xe9cis,caf\xe9\n1,oui,noir\n").unwrap();
+
+        let request = |name: &str, path: &std::path::Path| DiscoveryRequest {
+            ty: connector_type::LOCAL_FILE_CSV.to_string(),
+            name: name.to_string(),
+            config: serde_json::json!({ "filePath": path.to_string_lossy(), "encoding": "latin1" })
+                .to_string(),
+        };
+        let requests = vec![
+            request("customers", &customers_path),
+            request("products", &products_path),
+        ];
+
+        let result = bulk_discovery(requests, Some(2)).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        let column_names = |entry: &serde_json::Value| -> Vec<String> {
+            entry["schema"]["columns"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|column| column["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(result[0]["error"], serde_json::Value::Null);
+        assert_eq!(column_names(&result[0]), vec!["id", "straße"]);
+        assert_eq!(result[1]["error"], serde_json::Value::Null);
+        assert_eq!(column_names(&result[1]), vec!["sku", "précis", "café"]);
+        assert_eq!(transcoded_copies_left("streaksight_utf8_discovery_"), 0);
+    }
+
+    fn csv_sync_request(name: &str, csv_path: &std::path::Path) -> SyncRequest {
+        SyncRequest {
+            ty: connector_type::LOCAL_FILE_CSV.to_string(),
+            name: name.to_string(),
+            config: serde_json::json!({ "filePath": csv_path.to_string_lossy() }).to_string(),
+            schema: "{}".to_string(),
+            on_error: None,
+            primary_keys: None,
+            on_conflict: None,
+            include_row_hash: None,
+            schema_evolution: None,
+        }
+    }
+
+    fn staging_tables_left(prefix: &str) -> i64 {
+        let conn = duckdb_connect().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE starts_with(table_name, ?)",
+            [format!("_streaksight_staging_{}", prefix)],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_multi_table_sync() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let csv_path = temp_dir.join("test_multi_table_sync.csv");
+        std::fs::write(&csv_path, "id,name\n1,Alice\n2,Bob").unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_multi_sync_a (old INTEGER);
+             INSERT INTO test_multi_sync_a VALUES (1), (2), (3);
+             DROP TABLE IF EXISTS test_multi_sync_b;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let syncs = vec![
+            csv_sync_request("test_multi_sync_a", &csv_path),
+            csv_sync_request("test_multi_sync_b", &csv_path),
+        ];
+
+        let result = multi_table_sync(syncs).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result["synced"],
+            serde_json::json!([
+                { "name": "test_multi_sync_a", "rows_synced": 2, "rows_failed": 0 },
+                { "name": "test_multi_sync_b", "rows_synced": 2, "rows_failed": 0 }
+            ])
+        );
+
+        let conn = duckdb_connect().unwrap();
+        let name: String = conn
+            .query_row(
+                "SELECT name FROM test_multi_sync_a ORDER BY id LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(name, "Alice");
+        assert_eq!(staging_tables_left("test_multi_sync_"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_multi_table_sync_rolls_back_on_failure() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let csv_path = temp_dir.join("test_multi_table_sync_rollback.csv");
+        std::fs::write(&csv_path, "id,name\n1,Alice\n2,Bob").unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_multi_rollback_a (old INTEGER);
+             INSERT INTO test_multi_rollback_a VALUES (1), (2), (3);
+             DROP TABLE IF EXISTS test_multi_rollback_b;
+             DROP TABLE IF EXISTS test_multi_rollback_c;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let syncs = vec![
+            csv_sync_request("test_multi_rollback_a", &csv_path),
+            csv_sync_request("test_multi_rollback_b", &csv_path),
+            csv_sync_request(
+                "test_multi_rollback_c",
+                &temp_dir.join("test_multi_table_sync_missing.csv"),
+            ),
+        ];
+
+        let result = multi_table_sync(syncs).await;
+        assert!(matches!(result, Err(AppError::ConnectorError(_))));
+
+        let conn = duckdb_connect().unwrap();
+        let old_rows: i64 = conn
+            .query_row("SELECT SUM(old) FROM test_multi_rollback_a", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(old_rows, 6);
+
+        let created: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM information_schema.tables \
+                 WHERE table_name IN ('test_multi_rollback_b', 'test_multi_rollback_c')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(created, 0);
+        assert_eq!(staging_tables_left("test_multi_rollback_"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_multi_table_sync_duplicate_target() {
+        let csv_path = std::path::Path::new("unused.csv");
+        let syncs = vec![
+            csv_sync_request("test_multi_dup", csv_path),
+            csv_sync_request("test_multi_dup", csv_path),
+        ];
+
+        let result = multi_table_sync(syncs).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Duplicate sync target: test_multi_dup".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_error_handling() {
+        setup_test_env();
+
+        let test_csv = "id,value\n1,10\n2,20\n3,30,extra\n4,40";
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let csv_path = temp_dir.join("test_sync_errors.csv");
+        std::fs::write(&csv_path, test_csv).unwrap();
+        let config = serde_json::json!({ "filePath": csv_path.to_string_lossy() }).to_string();
+
+        let result = sync(
+            connector_type::LOCAL_FILE_CSV.to_string(),
+            "test_sync_errors_abort".to_string(),
+            config.clone(),
+            "{}".to_string(),
+            Some(ErrorHandling::Abort),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::ConnectorError(_))));
+
+        let result = sync(
+            connector_type::LOCAL_FILE_CSV.to_string(),
+            "test_sync_errors_skip".to_string(),
+            config.clone(),
+            "{}".to_string(),
+            Some(ErrorHandling::Skip),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["rows_synced"], 3);
+        assert_eq!(result["rows_failed"], 1);
+
+        let result = sync(
+            connector_type::LOCAL_FILE_CSV.to_string(),
+            "test_sync_errors_collect".to_string(),
+            config,
+            "{}".to_string(),
+            Some(ErrorHandling::CollectAndReport),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["rows_synced"], 3);
+        assert_eq!(result["rows_failed"], 1);
+
+        let conn = duckdb_connect().unwrap();
+        let (line, error_message): (i64, String) = conn
+            .query_row(
+                "SELECT line, error_message FROM _streaksight_sync_errors_test_sync_errors_collect",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(line, 4);
+        assert!(!error_message.is_empty());
+    }
+
+    fn read_id_values(table_name: &str) -> Vec<(i64, i64)> {
+        let conn = duckdb_connect().unwrap();
+        conn.prepare(&format!("SELECT id, value FROM {} ORDER BY id", table_name))
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sync_on_conflict() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let initial_path = temp_dir.join("test_sync_conflict_initial.csv");
+        let update_path = temp_dir.join("test_sync_conflict_update.csv");
+        std::fs::write(&initial_path, "id,value\n1,10\n2,20\n").unwrap();
+        std::fs::write(&update_path, "id,value\n2,200\n3,30\n").unwrap();
+        let initial_config =
+            serde_json::json!({ "filePath": initial_path.to_string_lossy() }).to_string();
+        let update_config =
+            serde_json::json!({ "filePath": update_path.to_string_lossy() }).to_string();
+        let schema = serde_json::json!({
+            "columns": [
+                {"name": "id", "type": "number"},
+                {"name": "value", "type": "number"}
+            ]
+        })
+        .to_string();
+
+        let cases = [
+            (
+                "test_sync_conflict_replace",
+                ConflictResolution::Replace,
+                vec![(1, 10), (2, 200), (3, 30)],
+            ),
+            (
+                "test_sync_conflict_ignore",
+                ConflictResolution::Ignore,
+                vec![(1, 10), (2, 20), (3, 30)],
+            ),
+            (
+                "test_sync_conflict_fail",
+                ConflictResolution::Fail,
+                vec![(1, 10), (2, 20)],
+            ),
+        ];
+
+        for (table_name, on_conflict, expected) in cases {
+            let conn = duckdb_connect().unwrap();
+            conn.execute_batch(&format!("DROP TABLE IF EXISTS {}", table_name))
+                .unwrap();
+            drop(conn);
+
+            for config in [&initial_config, &update_config] {
+                let result = sync(
+                    connector_type::LOCAL_FILE_CSV.to_string(),
+                    table_name.to_string(),
+                    config.clone(),
+                    schema.clone(),
+                    None,
+                    Some(vec!["id".to_string()]),
+                    Some(on_conflict),
+                    None,
+                    None,
+                )
+                .await;
+
+                if matches!(on_conflict, ConflictResolution::Fail) && config == &update_config {
+                    assert!(matches!(result, Err(AppError::DbError(_))));
+                } else {
+                    result.unwrap();
+                }
+            }
+
+            assert_eq!(read_id_values(table_name), expected);
+
+            let conn = duckdb_connect().unwrap();
+            let staging_exists: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) > 0 FROM information_schema.tables WHERE table_name = ?",
+                    [format!("_streaksight_staging_{}", table_name)],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert!(!staging_exists);
+        }
+    }
+
+    fn column_names(table_name: &str) -> Vec<String> {
+        let conn = duckdb_connect().unwrap();
+        describe_table(&conn, table_name)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sync_schema_evolution() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let initial_path = temp_dir.join("test_sync_evolution_initial.csv");
+        let update_path = temp_dir.join("test_sync_evolution_update.csv");
+        std::fs::write(&initial_path, "id,value,note\n1,10,a\n").unwrap();
+        std::fs::write(&update_path, "id,value,extra\n2,20,x\n").unwrap();
+        let schema =
+            serde_json::json!({ "columns": [{"name": "id", "type": "number"}] }).to_string();
+
+        let cases = [
+            (
+                "test_sync_evolution_strict",
+                SchemaEvolution::Strict,
+                Err("new columns: extra; removed columns: note"),
+            ),
+            (
+                "test_sync_evolution_add",
+                SchemaEvolution::AddNewColumns,
+                Ok(vec!["id", "value", "note", "extra"]),
+            ),
+            (
+                "test_sync_evolution_drop",
+                SchemaEvolution::DropRemovedColumns,
+                Err("new columns: extra"),
+            ),
+            (
+                "test_sync_evolution_full",
+                SchemaEvolution::FullSync,
+                Ok(vec!["id", "value", "extra"]),
+            ),
+        ];
+
+        for (table_name, schema_evolution, expected) in cases {
+            let conn = duckdb_connect().unwrap();
+            conn.execute_batch(&format!("DROP TABLE IF EXISTS {}", table_name))
+                .unwrap();
+            drop(conn);
+
+            let mut results = Vec::new();
+            for path in [&initial_path, &update_path] {
+                let config = serde_json::json!({ "filePath": path.to_string_lossy() }).to_string();
+                results.push(
+                    sync(
+                        connector_type::LOCAL_FILE_CSV.to_string(),
+                        table_name.to_string(),
+                        config,
+                        schema.clone(),
+                        None,
+                        Some(vec!["id".to_string()]),
+                        None,
+                        None,
+                        Some(schema_evolution),
+                    )
+                    .await,
+                );
+            }
+            let update_result = results.pop().unwrap();
+            assert!(results[0].is_ok());
+
+            match expected {
+                Ok(columns) => {
+                    update_result.unwrap();
+                    assert_eq!(column_names(table_name), columns);
+                    let conn = duckdb_connect().unwrap();
+                    let count: i64 = conn
+                        .query_row(&format!("SELECT COUNT(*) FROM {}", table_name), [], |row| {
+                            row.get(0)
+                        })
+                        .unwrap();
+                    assert_eq!(count, 2);
+                }
+                Err(rejected) => {
+                    assert_eq!(
+                        update_result.unwrap_err(),
+                        AppError::ValidationError(format!(
+                            "Source schema of {} changed ({})",
+                            table_name, rejected
+                        ))
+                    );
+                    assert_eq!(column_names(table_name), vec!["id", "value", "note"]);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_include_row_hash() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let csv_path = temp_dir.join("test_sync_row_hash.csv");
+        std::fs::write(&csv_path, "id,name\n1,Alice\n2,Bob\n").unwrap();
+        let config = serde_json::json!({ "filePath": csv_path.to_string_lossy() }).to_string();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "DROP TABLE IF EXISTS test_sync_row_hash; DROP TABLE IF EXISTS test_sync_no_row_hash;",
+        )
+        .unwrap();
+        drop(conn);
+
+        for (table_name, include_row_hash) in [
+            ("test_sync_row_hash", Some(true)),
+            ("test_sync_no_row_hash", None),
+        ] {
+            sync(
+                connector_type::LOCAL_FILE_CSV.to_string(),
+                table_name.to_string(),
+                config.clone(),
+                "{}".to_string(),
+                None,
+                None,
+                None,
+                include_row_hash,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let conn = duckdb_connect().unwrap();
+        let hashes: Vec<(String, String)> = conn
+            .prepare(
+                "SELECT _row_hash, md5(CAST(ROW(id, name) AS VARCHAR)) \
+                 FROM test_sync_row_hash ORDER BY id",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(hashes.len(), 2);
+        for (stored, expected) in &hashes {
+            assert_eq!(stored, expected);
+        }
+        assert_ne!(hashes[0].0, hashes[1].0);
+
+        let has_hash_column: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM information_schema.columns \
+                 WHERE table_name = 'test_sync_no_row_hash' AND column_name = '_row_hash'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(!has_hash_column);
+    }
+
+    #[tokio::test]
+    async fn test_sync_primary_key_not_in_schema() {
+        let schema = serde_json::json!({
+            "columns": [
+                {"name": "id", "type": "number"},
+                {"name": "blob", "type": "string", "skip": true}
+            ]
+        })
+        .to_string();
+
+        for pk in ["missing", "blob"] {
+            let result = sync(
+                connector_type::LOCAL_FILE_CSV.to_string(),
+                "test_sync_missing_pk".to_string(),
+                "{}".to_string(),
+                schema.clone(),
+                None,
+                Some(vec![pk.to_string()]),
+                Some(ConflictResolution::Replace),
+                None,
+                None,
+            )
+            .await;
+            assert_eq!(
+                result.unwrap_err(),
+                AppError::ValidationError(format!(
+                    "Primary key column not found in schema: {}",
+                    pk
+                ))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_empty() {
+        let result = sync_all(vec![], None).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result,
+            serde_json::json!({ "successes": [], "failures": [] })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_and_drop_macro() {
+        setup_test_env();
+
+        create_macro(
+            "test_add_macro".to_string(),
+            vec!["a".to_string(), "b".to_string()],
+            "a + b".to_string(),
+        )
+        .await
+        .unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        let sum: i64 = conn
+            .query_row("SELECT test_add_macro(2, 3)", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sum, 5);
+
+        let result = list_macros().await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let macro_info = result["macros"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|m| m["name"] == "test_add_macro")
+            .unwrap();
+        assert_eq!(macro_info["parameters"], serde_json::json!(["a", "b"]));
+
+        drop_macro("test_add_macro".to_string()).await.unwrap();
+
+        let result = list_macros().await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert!(!result["macros"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|m| m["name"] == "test_add_macro"));
+    }
+
+    #[tokio::test]
+    async fn test_call_macro() {
+        setup_test_env();
+
+        create_macro(
+            "test_mul_macro".to_string(),
+            vec!["a".to_string(), "b".to_string()],
+            "a * b".to_string(),
+        )
+        .await
+        .unwrap();
+
+        let result = call_macro(
+            "test_mul_macro".to_string(),
+            vec![serde_json::json!(6), serde_json::json!(7)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, "42");
+
+        let result = call_macro(
+            "test_mul_macro".to_string(),
+            vec![serde_json::json!(1.5), serde_json::json!(2)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, "3.0");
+    }
+
+    #[tokio::test]
+    async fn test_call_macro_invalid_name() {
+        let result = call_macro("abs(1); --".to_string(), vec![]).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid macro name".to_string())
+        );
+    }
+
+    #[test]
+    fn test_json_to_duckdb_value() {
+        assert_eq!(
+            json_to_duckdb_value(&serde_json::json!(null)).unwrap(),
+            duckdb::types::Value::Null
+        );
+        assert_eq!(
+            json_to_duckdb_value(&serde_json::json!(42)).unwrap(),
+            duckdb::types::Value::BigInt(42)
+        );
+        assert_eq!(
+            json_to_duckdb_value(&serde_json::json!("hi")).unwrap(),
+            duckdb::types::Value::Text("hi".to_string())
+        );
+        assert!(json_to_duckdb_value(&serde_json::json!([1])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_macro_invalid_names() {
+        let result = create_macro("bad name".to_string(), vec![], "1".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid macro name".to_string())
+        );
+
+        let result = create_macro(
+            "test_macro".to_string(),
+            vec!["x); DROP TABLE t; --".to_string()],
+            "1".to_string(),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError(
+                "Invalid macro parameter name: x); DROP TABLE t; --".to_string()
+            )
+        );
+
+        let result = drop_macro("".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid macro name".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_sequence_and_next_value() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute("DROP SEQUENCE IF EXISTS test_seq", [])
+            .unwrap();
+        drop(conn);
+
+        create_sequence("test_seq".to_string(), 10, 5)
+            .await
+            .unwrap();
+
+        assert_eq!(next_value("test_seq".to_string()).await.unwrap(), 10);
+        assert_eq!(next_value("test_seq".to_string()).await.unwrap(), 15);
+        assert_eq!(next_value("test_seq".to_string()).await.unwrap(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_sequence_invalid_name() {
+        let result = create_sequence("seq'; --".to_string(), 1, 1).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid sequence name".to_string())
+        );
+
+        let result = next_value("seq')".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid sequence name".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_list_and_drop_schema() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute("DROP SCHEMA IF EXISTS test_schema CASCADE", [])
+            .unwrap();
+        drop(conn);
+
+        create_schema("test_schema".to_string()).await.unwrap();
+        create_schema("test_schema".to_string()).await.unwrap();
+
+        let result = list_schemas().await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let schemas = result["schemas"].as_array().unwrap();
+        assert!(schemas.contains(&serde_json::json!("main")));
+        assert!(schemas.contains(&serde_json::json!("test_schema")));
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("CREATE TABLE test_schema.items (id INTEGER)")
+            .unwrap();
+        drop(conn);
+
+        let result = drop_schema("test_schema".to_string(), false).await;
+        assert!(matches!(result, Err(AppError::DbError(_))));
+
+        drop_schema("test_schema".to_string(), true).await.unwrap();
+
+        let result = list_schemas().await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert!(!result["schemas"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("test_schema")));
+    }
+
+    fn migration(version: &str, sql: &str) -> Migration {
+        Migration {
+            version: version.to_string(),
+            sql: sql.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_migrations() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "DROP TABLE IF EXISTS _streaksight_migrations;
+             DROP TABLE IF EXISTS test_migrated;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let migrations = || {
+            vec![
+                migration("001", "CREATE TABLE test_migrated (id INTEGER)"),
+                migration("002", "ALTER TABLE test_migrated ADD COLUMN name VARCHAR"),
+            ]
+        };
+
+        let result = apply_migrations(migrations()).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({ "applied": ["001", "002"], "skipped": [] })
+        );
+
+        let result = apply_migrations(migrations()).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({ "applied": [], "skipped": ["001", "002"] })
+        );
+
+        let history = get_migration_history().await.unwrap();
+        let history: serde_json::Value = serde_json::from_str(&history).unwrap();
+        let history = history["migrations"].as_array().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0]["version"], "001");
+        assert_eq!(history[1]["version"], "002");
+        assert_eq!(
+            history[0]["checksum"],
+            sha256_hex("CREATE TABLE test_migrated (id INTEGER)")
+        );
+        assert!(history[0]["applied_at"].as_str().is_some());
+
+        let mut tampered = migrations();
+        tampered[1].sql = "ALTER TABLE test_migrated ADD COLUMN email VARCHAR".to_string();
+        let result = apply_migrations(tampered).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError(
+                "Migration 002 was modified after it was applied".to_string()
+            )
+        );
+
+        let result = apply_migrations(vec![migration(
+            "003",
+            "ALTER TABLE missing ADD COLUMN x INT",
+        )])
+        .await;
+        assert!(matches!(result, Err(AppError::DbError(_))));
+
+        let history = get_migration_history().await.unwrap();
+        let history: serde_json::Value = serde_json::from_str(&history).unwrap();
+        assert_eq!(history["migrations"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_query_templates() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("DROP TABLE IF EXISTS _streaksight_query_templates")
+            .unwrap();
+        drop(conn);
+
+        let template = serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "{{table_name}}"}},
+                {"id": "2", "type": "sort", "data": {"order": [{"column": "{{column_name}}", "direction": "desc"}]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+
+        create_template(
+            "top_rows".to_string(),
+            "Rows sorted by a column".to_string(),
+            template.clone(),
+        )
+        .await
+        .unwrap();
+        create_template(
+            "all_rows".to_string(),
+            "Every row".to_string(),
+            serde_json::json!({
+                "selected_node_id": "1",
+                "nodes": [{"id": "1", "type": "table", "data": {"table_name": "{{table_name}}"}}],
+                "edges": []
+            })
+            .to_string(),
+        )
+        .await
+        .unwrap();
+
+        let result = create_template("top_rows".to_string(), String::new(), template).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Template already exists: top_rows".to_string())
+        );
+        let result = create_template("broken".to_string(), String::new(), "{}".to_string()).await;
+        assert!(
+            matches!(result, Err(AppError::ValidationError(message)) if message.starts_with("Invalid node graph template:"))
+        );
+
+        let result = list_templates().await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({"templates": [
+                {"name": "all_rows", "description": "Every row"},
+                {"name": "top_rows", "description": "Rows sorted by a column"}
+            ]})
+        );
+
+        let bindings = HashMap::from([
+            ("table_name".to_string(), "orders".to_string()),
+            ("column_name".to_string(), "amount".to_string()),
+        ]);
+        let graph = apply_template("top_rows".to_string(), bindings.clone())
+            .await
+            .unwrap();
+        let graph: query_builder::NodeGraph = serde_json::from_str(&graph).unwrap();
+        assert_eq!(
+            generate_query_sql(&graph, None).unwrap(),
+            "SELECT * FROM orders ORDER BY amount DESC"
+        );
+
+        let result = apply_template(
+            "top_rows".to_string(),
+            HashMap::from([("table_name".to_string(), "orders".to_string())]),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Unknown template variable: column_name".to_string())
+        );
+
+        let result = apply_template(
+            "top_rows".to_string(),
+            HashMap::from([
+                (
+                    "table_name".to_string(),
+                    "orders; DROP TABLE orders".to_string(),
+                ),
+                ("column_name".to_string(), "amount".to_string()),
+            ]),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+
+        let result = apply_template("missing".to_string(), bindings).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Template not found: missing".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_schema_invalid_name() {
+        let result = create_schema("s; DROP TABLE t".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid schema name".to_string())
+        );
+
+        let result = drop_schema("".to_string(), true).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid schema name".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_duckdb_version() {
+        setup_test_env();
+
+        let result = get_duckdb_version().await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        // Note: The duckdb crate shares its major and minor version with the library it bundles
+        let crate_version = result["crate_version"].as_str().unwrap();
+        let release: Vec<&str> = crate_version.split('.').take(2).collect();
+        assert_eq!(release.len(), 2);
+        assert!(result["duckdb_version"]
+            .as_str()
+            .unwrap()
+            .starts_with(&format!("v{}.", release.join("."))));
+    }
+
+    #[tokio::test]
+    async fn test_command_error_code() {
+        let error = drop_table("bad name".to_string()).await.unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+
+        assert_eq!(error["code"], "VALIDATION_ERROR");
+        assert_eq!(error["message"], "Invalid table name");
+    }
+
+    #[derive(Clone, Default)]
+    struct MockSizeEmitter {
+        updates: Arc<Mutex<Vec<TableSizeUpdate>>>,
+    }
+
+    impl TableSizeEmitter for MockSizeEmitter {
+        fn emit_size(&self, update: &TableSizeUpdate) -> Result<(), String> {
+            self.updates.lock().unwrap().push(update.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitor_table_size() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_monitor_table AS SELECT * FROM range(3) t(id)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let emitter = MockSizeEmitter::default();
+        spawn_table_size_monitor("test_monitor_table".to_string(), 10, emitter.clone()).unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        stop_monitor("test_monitor_table".to_string())
+            .await
+            .unwrap();
+
+        let emitted = emitter.updates.lock().unwrap().len();
+        assert!(emitted > 0);
+        assert_eq!(emitter.updates.lock().unwrap()[0].row_count, 3);
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(emitter.updates.lock().unwrap().len(), emitted);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_aggregation() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_incremental_source (category VARCHAR, amount INTEGER);
+             INSERT INTO test_incremental_source VALUES ('a', 1), ('a', 2), ('b', 3);
+             DROP TABLE IF EXISTS test_incremental_dest;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_incremental_source"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [{"function": "SUM", "column": "amount"}]
+                }}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+        let totals = || -> Vec<(String, i64)> {
+            let conn = duckdb_connect().unwrap();
+            conn.prepare("SELECT * FROM test_incremental_dest ORDER BY category")
+                .unwrap()
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        create_incremental_aggregation(
+            "test_incremental_source".to_string(),
+            node_graph.clone(),
+            "test_incremental_dest".to_string(),
+            60_000,
+        )
+        .await
+        .unwrap();
+        assert_eq!(totals(), vec![("a".to_string(), 3), ("b".to_string(), 3)]);
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("INSERT INTO test_incremental_source VALUES ('b', 4), ('c', 5)")
+            .unwrap();
+        drop(conn);
+
+        let graph: query_builder::NodeGraph = serde_json::from_str(&node_graph).unwrap();
+        let sql = generate_query_sql(&graph, None).unwrap();
+        refresh_aggregation("test_incremental_dest", &sql).unwrap();
+        assert_eq!(
+            totals(),
+            vec![
+                ("a".to_string(), 3),
+                ("b".to_string(), 7),
+                ("c".to_string(), 5)
+            ]
+        );
+
+        let refresh = aggregation_refreshes()
+            .lock()
+            .unwrap()
+            .get("test_incremental_dest")
+            .unwrap()
+            .abort_handle();
+        stop_incremental_aggregation("test_incremental_dest".to_string())
+            .await
+            .unwrap();
+        assert!(!aggregation_refreshes()
+            .lock()
+            .unwrap()
+            .contains_key("test_incremental_dest"));
+        while !refresh.is_finished() {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_incremental_aggregation_invalid_args() {
+        let graph = |node_type: &str| {
+            serde_json::json!({
+                "selected_node_id": "2",
+                "nodes": [
+                    {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                    {"id": "2", "type": node_type, "data": {"dimensions": [], "metrics": [], "columns": []}}
+                ],
+                "edges": [{"source": "1", "target": "2"}]
+            })
+            .to_string()
+        };
+        let cases = [
+            (
+                "orders",
+                graph("aggregation"),
+                "bad name",
+                10,
+                "Invalid table name",
+            ),
+            (
+                "orders",
+                graph("aggregation"),
+                "totals",
+                0,
+                "Interval must be greater than zero",
+            ),
+            (
+                "orders",
+                graph("select"),
+                "totals",
+                10,
+                "Node graph has no aggregation node",
+            ),
+            (
+                "refunds",
+                graph("aggregation"),
+                "totals",
+                10,
+                "Node graph does not read from refunds",
+            ),
+        ];
+
+        for (source_table, node_graph, dest_table, interval_ms, expected) in cases {
+            let result = create_incremental_aggregation(
+                source_table.to_string(),
+                node_graph,
+                dest_table.to_string(),
+                interval_ms,
+            )
+            .await;
+            assert_eq!(
+                result.unwrap_err(),
+                AppError::ValidationError(expected.to_string())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitor_table_size_invalid_args() {
+        let result =
+            spawn_table_size_monitor("bad name".to_string(), 10, MockSizeEmitter::default());
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid table name".to_string())
+        );
+
+        let result =
+            spawn_table_size_monitor("test_table".to_string(), 0, MockSizeEmitter::default());
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Interval must be greater than zero".to_string())
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct MockFileChangeEmitter {
+        events: Arc<Mutex<Vec<FileChanged>>>,
+    }
+
+    impl FileChangeEmitter for MockFileChangeEmitter {
+        fn emit_file_changed(&self, event: &FileChanged) -> Result<(), String> {
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_watch_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watched.csv");
+        std::fs::write(&path, "id\n1\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let emitter = MockFileChangeEmitter::default();
+        spawn_file_watcher(path.clone(), 100, emitter.clone()).unwrap();
+
+        for i in 2..5 {
+            std::fs::write(&path, format!("id\n{}\n", i)).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        std::fs::write(dir.path().join("other.csv"), "id\n").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        // Note: The burst of writes is debounced into a single event
+        assert_eq!(
+            *emitter.events.lock().unwrap(),
+            vec![FileChanged { path: path.clone() }]
+        );
+
+        stop_file_watcher(&path).unwrap();
+        std::fs::write(&path, "id\n5\n").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        assert_eq!(emitter.events.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_watch_file_missing() {
+        let result = spawn_file_watcher(
+            "/nonexistent/streaksight/file.csv".to_string(),
+            100,
+            MockFileChangeEmitter::default(),
+        );
+        assert!(result.unwrap_err().starts_with("Failed to resolve path"));
+    }
+
+    #[tokio::test]
+    async fn test_export_table_compressed() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_export_table AS SELECT * FROM range(5) t(id)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+
+        let csv_path = temp_dir.join("test_export.csv");
+        let exported = export_table(
+            "test_export_table".to_string(),
+            csv_path.to_string_lossy().to_string(),
+            "csv".to_string(),
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(exported, format!("{}.gz", csv_path.to_string_lossy()));
+        assert!(std::path::Path::new(&exported).exists());
+
+        let conn = duckdb_connect().unwrap();
+        let count: i64 = conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM read_csv_auto('{}')", exported),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 5);
+        drop(conn);
+
+        let parquet_path = temp_dir.join("test_export.parquet");
+        let exported = export_table(
+            "test_export_table".to_string(),
+            parquet_path.to_string_lossy().to_string(),
+            "parquet".to_string(),
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(exported, parquet_path.to_string_lossy());
+
+        let conn = duckdb_connect().unwrap();
+        let count: i64 = conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM read_parquet('{}')", exported),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 5);
+    }
+
+    #[tokio::test]
+    async fn test_export_table_unsupported_format() {
+        let result = export_table(
+            "test_export_table".to_string(),
+            "out.xlsx".to_string(),
+            "xlsx".to_string(),
+            false,
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Unsupported export format: xlsx".to_string())
+        );
+    }
+
+    #[test]
+    fn test_duckdb_connect_readonly() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("CREATE OR REPLACE TABLE test_readonly AS SELECT 1 AS id")
+            .unwrap();
+        drop(conn);
+
+        let conn = duckdb_connect_readonly().unwrap();
+        let id: i32 = conn
+            .query_row("SELECT id FROM test_readonly", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(id, 1);
+
+        for sql in [
+            "INSERT INTO test_readonly VALUES (2)",
+            "CREATE TABLE test_readonly_created (id INTEGER)",
+            "DROP TABLE test_readonly",
+        ] {
+            let error = conn.execute_batch(sql).unwrap_err().to_string();
+            assert!(error.contains("read-only"), "{}: {}", sql, error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_query_cache() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_query_cache AS SELECT * FROM range(3) t(id)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let graph = serde_json::json!({
+            "selected_node_id": "1",
+            "nodes": [{"id": "1", "type": "table", "data": {"table_name": "test_query_cache"}}],
+            "edges": []
+        });
+        let node_graph = parse_node_graph(&graph.to_string()).unwrap();
+        let pretty_graph =
+            parse_node_graph(&serde_json::to_string_pretty(&graph).unwrap()).unwrap();
+
+        // Note: A cache of its own keeps other tests' queries and invalidations out of this one
+        let cache = Mutex::new(lru::LruCache::new(
+            std::num::NonZeroUsize::new(QUERY_CACHE_CAPACITY).unwrap(),
+        ));
+        let key = query_cache_key(&node_graph, 1, 100, false).unwrap();
+        assert_eq!(query_cache_key(&pretty_graph, 1, 100, false).unwrap(), key);
+        assert_ne!(query_cache_key(&node_graph, 2, 100, false).unwrap(), key);
+
+        let result = cached_query_page(&cache, node_graph.clone(), 1, 100, false).unwrap();
+        assert_eq!(cache.lock().unwrap().peek(&key), Some(&result));
+        assert_eq!(
+            cached_query_page(&cache, pretty_graph, 1, 100, false).unwrap(),
+            result
+        );
+        assert_eq!(cache.lock().unwrap().len(), 1);
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("INSERT INTO test_query_cache VALUES (3)")
+            .unwrap();
+        drop(conn);
+        cache.lock().unwrap().clear();
+
+        let result: serde_json::Value =
+            serde_json::from_str(&cached_query_page(&cache, node_graph, 1, 100, false).unwrap())
+                .unwrap();
+        assert_eq!(result["row_count"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_run_query_row_numbers() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_row_numbers AS SELECT * FROM range(25) t(id)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_row_numbers"}},
+                {"id": "2", "type": "sort", "data": {"order": [
+                    {"column": "id", "direction": "asc"}
+                ]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+
+        for (page, expected) in [(1, 1..=10), (3, 21..=25)] {
+            let result = run_query(
+                node_graph.clone(),
+                Some(page),
+                Some(10),
+                Some(true),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+            let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+            assert_eq!(result["columns"][0], serde_json::json!({ "name": "_rn" }));
+            let row_numbers: Vec<i64> = result["rows"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|row| {
+                    assert_eq!(
+                        row["_rn"].as_i64().unwrap(),
+                        row["id"].as_i64().unwrap() + 1
+                    );
+                    row["_rn"].as_i64().unwrap()
+                })
+                .collect();
+            assert_eq!(row_numbers, expected.collect::<Vec<_>>());
+        }
+
+        let result = run_query(node_graph, Some(1), Some(10), None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["columns"][0],
+            serde_json::json!({ "name": "id", "type": "number" })
+        );
+        assert!(result["rows"][0].get("_rn").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_run_query_column_metadata() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_column_metadata AS
+             SELECT * FROM (VALUES
+                (1, 'ab', CAST(NULL AS VARCHAR), DATE '2024-01-01'),
+                (2, 'héllo wörld', NULL, DATE '2024-01-02'),
+                (3, NULL, NULL, NULL)
+             ) t(id, name, note, created)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "1",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_column_metadata"}}
+            ],
+            "edges": []
+        })
+        .to_string();
+
+        let result = run_query(node_graph, None, None, Some(true), None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result["columns"],
+            serde_json::json!([
+                {"name": "_rn"},
+                {"name": "id", "type": "number"},
+                {"name": "name", "type": "string", "max_length": 11},
+                {"name": "note", "type": "string", "max_length": 0},
+                {"name": "created", "type": "date"}
+            ])
+        );
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_column_metadata AS
+             SELECT id, CASE WHEN id = 1200 THEN repeat('x', 50) ELSE 'y' END AS name
+             FROM range(1500) t(id)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_column_metadata"}},
+                {"id": "2", "type": "sort", "data": {"order": [
+                    {"column": "id", "direction": "asc"}
+                ]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+
+        let result = run_query(node_graph, None, Some(1500), None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(result["row_count"], 1500);
+        assert_eq!(result["columns"][1]["max_length"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_query_schema_change() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_schema_change AS SELECT 1 AS id, 'a' AS name",
+        )
+        .unwrap();
+        drop(conn);
+        invalidate_query_cache();
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "1",
+            "nodes": [{"id": "1", "type": "table", "data": {"table_name": "test_schema_change"}}],
+            "edges": []
+        })
+        .to_string();
+
+        let result = run_query(node_graph.clone(), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let schema_version = result["schema_version"].as_str().unwrap().to_string();
+        assert_eq!(schema_version.len(), 64);
+        assert!(result.get("schema_changed").is_none());
+
+        let result = run_query(
+            node_graph.clone(),
+            None,
+            None,
+            None,
+            Some(schema_version.clone()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["schema_version"], schema_version.as_str());
+        assert_eq!(result["schema_changed"], false);
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("ALTER TABLE test_schema_change ALTER COLUMN id TYPE BIGINT")
+            .unwrap();
+        drop(conn);
+        invalidate_query_cache();
+
+        let result = run_query(
+            node_graph,
+            None,
+            None,
+            None,
+            Some(schema_version.clone()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_ne!(result["schema_version"], schema_version.as_str());
+        assert_eq!(result["schema_changed"], true);
+    }
+
+    #[tokio::test]
+    async fn test_run_query_filter_on_metric() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_metric_filter (customer_id INTEGER, amount DOUBLE);
+             INSERT INTO test_metric_filter VALUES (1, 20), (1, 5), (2, 8), (2, 9), (3, 50);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_ids": ["3"],
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_metric_filter"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["customer_id"],
+                    "metrics": [
+                        {"function": "SUM", "column": "amount"},
+                        {"function": "COUNT(*)", "column": ""}
+                    ]
+                }},
+                {"id": "3", "type": "filter", "data": {"conditions": [
+                    {"column": "sum(amount)", "operator": ">", "value": 10},
+                    {"column": "count_star()", "operator": ">=", "value": 2}
+                ]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}, {"source": "2", "target": "3"}]
+        })
+        .to_string();
+
+        let result = run_query(node_graph, None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        let mut rows = result["rows"].as_array().unwrap().clone();
+        rows.sort_by_key(|row| row["customer_id"].as_i64());
+        assert_eq!(
+            rows,
+            vec![
+                serde_json::json!({ "customer_id": 1, "sum(amount)": 25.0, "count_star()": 2 }),
+                serde_json::json!({ "customer_id": 2, "sum(amount)": 17.0, "count_star()": 2 })
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_query_drop_duplicates() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_drop_duplicates (user_id INTEGER, page TEXT);
+             INSERT INTO test_drop_duplicates VALUES (1, 'home'), (2, 'about'), (1, 'pricing'), (2, 'home'), (3, 'home');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_ids": ["3"],
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_drop_duplicates"}},
+                {"id": "2", "type": "drop_duplicates", "data": {"columns": ["user_id"]}},
+                {"id": "3", "type": "sort", "data": {"order": [{"column": "user_id", "direction": "asc"}]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}, {"source": "2", "target": "3"}]
+        })
+        .to_string();
+
+        let result = run_query(node_graph.clone(), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result["rows"],
+            serde_json::json!([
+                { "user_id": 1, "page": "home" },
+                { "user_id": 2, "page": "about" },
+                { "user_id": 3, "page": "home" }
+            ])
+        );
+
+        let result = run_query(node_graph, Some(1), Some(10), Some(true), None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result["columns"],
+            serde_json::json!([
+                {"name": "_rn"},
+                {"name": "user_id", "type": "number"},
+                {"name": "page", "type": "string", "max_length": 5}
+            ])
+        );
+        assert_eq!(
+            result["rows"],
+            serde_json::json!([
+                { "_rn": 1, "user_id": 1, "page": "home" },
+                { "_rn": 2, "user_id": 2, "page": "about" },
+                { "_rn": 3, "user_id": 3, "page": "home" }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_query_drop_duplicates_then_filter() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_drop_duplicates_filter (user_id INTEGER, page TEXT);
+             INSERT INTO test_drop_duplicates_filter VALUES (1, 'home'), (2, 'about'), (1, 'pricing'), (2, 'home'), (3, 'home');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let filtered_rows = |page: &'static str| async move {
+            let node_graph = serde_json::json!({
+                "selected_node_ids": ["4"],
+                "nodes": [
+                    {"id": "1", "type": "table", "data": {"table_name": "test_drop_duplicates_filter"}},
+                    {"id": "2", "type": "drop_duplicates", "data": {"columns": ["user_id"]}},
+                    {"id": "3", "type": "filter", "data": {"conditions": [{"column": "page", "operator": "==", "value": page}]}},
+                    {"id": "4", "type": "sort", "data": {"order": [{"column": "user_id", "direction": "asc"}]}}
+                ],
+                "edges": [
+                    {"source": "1", "target": "2"},
+                    {"source": "2", "target": "3"},
+                    {"source": "3", "target": "4"}
+                ]
+            })
+            .to_string();
+            let result = run_query(node_graph, None, None, None, None, None, None)
+                .await
+                .unwrap();
+            let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+            result["rows"].clone()
+        };
+
+        assert_eq!(filtered_rows("pricing").await, serde_json::json!([]));
+        assert_eq!(
+            filtered_rows("home").await,
+            serde_json::json!([
+                { "user_id": 1, "page": "home" },
+                { "user_id": 3, "page": "home" }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_query_random_sort() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_random_sort AS SELECT CAST(i AS INTEGER) AS id FROM range(20) t(i);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let shuffled_ids = |seed: u64| async move {
+            let node_graph = serde_json::json!({
+                "selected_node_ids": ["2"],
+                "nodes": [
+                    {"id": "1", "type": "table", "data": {"table_name": "test_random_sort"}},
+                    {"id": "2", "type": "sort", "data": {"order": [], "random_seed": seed}}
+                ],
+                "edges": [{"source": "1", "target": "2"}]
+            })
+            .to_string();
+            let result = run_query(node_graph, None, None, None, None, None, None)
+                .await
+                .unwrap();
+            let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+            result["rows"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|row| row["id"].as_i64().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let ids = shuffled_ids(42).await;
+        assert_ne!(ids, (0..20).collect::<Vec<_>>());
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+
+        assert_eq!(shuffled_ids(42).await, ids);
+        assert_ne!(shuffled_ids(7).await, ids);
+    }
+
+    #[tokio::test]
+    async fn test_run_query_natural_join() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_join_orders (customer_id INTEGER, amount INTEGER);
+             INSERT INTO test_join_orders VALUES (1, 10), (2, 20), (3, 30);
+             CREATE OR REPLACE TABLE test_join_customers (customer_id INTEGER, name TEXT);
+             INSERT INTO test_join_customers VALUES (1, 'Alice'), (2, 'Bob');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let graph = |joined: &str| {
+            serde_json::json!({
+                "selected_node_ids": ["3"],
+                "nodes": [
+                    {"id": "1", "type": "table", "data": {"table_name": "test_join_orders"}},
+                    {"id": "2", "type": "join", "data": {"table_name": joined, "join_type": "natural"}},
+                    {"id": "3", "type": "sort", "data": {"order": [{"column": "customer_id", "direction": "asc"}]}}
+                ],
+                "edges": [{"source": "1", "target": "2"}, {"source": "2", "target": "3"}]
+            })
+            .to_string()
+        };
+
+        let result = run_query(
+            graph("test_join_customers"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["rows"],
+            serde_json::json!([
+                { "customer_id": 1, "amount": 10, "name": "Alice" },
+                { "customer_id": 2, "amount": 20, "name": "Bob" }
+            ])
+        );
+
+        let result = run_query(
+            graph("test_join_missing"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Table not found: test_join_missing".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_query_anti_join() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_anti_customers (customer_id INTEGER, name TEXT);
+             INSERT INTO test_anti_customers VALUES (1, 'Alice'), (2, 'Bob'), (3, 'Carol');
+             CREATE OR REPLACE TABLE test_anti_orders (customer_id INTEGER);
+             INSERT INTO test_anti_orders VALUES (1), (3), (3);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_ids": ["2"],
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_anti_customers"}},
+                {"id": "2", "type": "join", "data": {"table_name": "test_anti_orders", "join_type": "anti", "keys": ["customer_id"]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+
+        let result = run_query(node_graph.clone(), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["rows"],
+            serde_json::json!([{ "customer_id": 2, "name": "Bob" }])
+        );
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("INSERT INTO test_anti_orders VALUES (2)")
+            .unwrap();
+        drop(conn);
+        invalidate_query_cache();
+
+        let result = run_query(node_graph, None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["row_count"], 0);
+        assert_eq!(result["rows"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_cte_pagination_matches_subquery_pagination() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_cte_pages AS SELECT range AS id, range % 3 AS bucket FROM range(20)",
+        )
+        .unwrap();
+
+        let graph: query_builder::NodeGraph = serde_json::from_value(serde_json::json!({
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_cte_pages"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "bucket", "operator": "!=", "value": 1}]}},
+                {"id": "3", "type": "sort", "data": {"order": [{"column": "id", "direction": "desc"}]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}, {"source": "2", "target": "3"}]
+        }))
+        .unwrap();
+
+        for pagination in [(5, 0), (5, 5), (5, 10)] {
+            let results: Vec<serde_json::Value> = [false, true]
+                .into_iter()
+                .map(|use_cte_for_pagination| {
+                    let options = query_builder::QueryOptions {
+                        use_cte_for_pagination,
+                        ..Default::default()
+                    };
+                    let sql =
+                        query_builder::generate_sql_with_options(&graph, Some(pagination), options)
+                            .unwrap();
+                    query_result_json(&conn, &sql).unwrap()
+                })
+                .collect();
+            assert_eq!(results[0], results[1]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_query_cursor_pagination() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_cursor_pages AS SELECT range AS id FROM range(1, 8)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_ids": ["2"],
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_cursor_pages"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "id", "operator": "!=", "value": 4}]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+
+        let mut cursor_value = None;
+        let mut pages = Vec::new();
+        loop {
+            let cursor = query_builder::CursorPagination {
+                order_column: "id".to_string(),
+                cursor_value: cursor_value.clone(),
+                page_size: 3,
+            };
+            let result = run_query(
+                node_graph.clone(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(cursor),
+            )
+            .await
+            .unwrap();
+            let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+            pages.push(result["rows"].clone());
+            if result["next_cursor"].is_null() {
+                break;
+            }
+            cursor_value = Some(result["next_cursor"].clone());
+        }
+
+        assert_eq!(
+            pages,
+            vec![
+                serde_json::json!([{ "id": 1 }, { "id": 2 }, { "id": 3 }]),
+                serde_json::json!([{ "id": 5 }, { "id": 6 }, { "id": 7 }]),
+                serde_json::json!([]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_query_cursor_order_column_not_selected() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_cursor_unselected AS SELECT range AS id, 'user_' || range AS name FROM range(1, 8)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_ids": ["2"],
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_cursor_unselected"}},
+                {"id": "2", "type": "select", "data": {"columns": ["name"]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+        let cursor = query_builder::CursorPagination {
+            order_column: "id".to_string(),
+            cursor_value: None,
+            page_size: 3,
+        };
+
+        let result = run_query(node_graph, None, None, None, None, None, Some(cursor)).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Order column is not selected by the query: id".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_query_multiple_selected_nodes() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_query_stages AS SELECT range AS id FROM range(5)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_ids": ["1", "2"],
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_query_stages"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "id", "operator": ">=", "value": 3}]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+
+        let result = run_query(node_graph, None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let stages = result.as_array().unwrap();
+        assert_eq!(stages.len(), 2);
+
+        assert_eq!(stages[0]["node_id"], "1");
+        assert_eq!(
+            stages[0]["sql"],
+            "SELECT * FROM (SELECT * FROM test_query_stages) AS subquery LIMIT 100 OFFSET 0"
+        );
+        assert_eq!(stages[0]["row_count"], 5);
+
+        assert_eq!(stages[1]["node_id"], "2");
+        assert_eq!(
+            stages[1]["sql"],
+            "SELECT * FROM (SELECT * FROM test_query_stages WHERE id >= 3) AS subquery LIMIT 100 OFFSET 0"
+        );
+        assert_eq!(stages[1]["row_count"], 2);
+        assert_eq!(
+            stages[1]["columns"],
+            serde_json::json!([{ "name": "id", "type": "number" }])
+        );
+        assert_eq!(
+            stages[1]["rows"],
+            serde_json::json!([{ "id": 3 }, { "id": 4 }])
+        );
+
+        let single = serde_json::json!({
+            "selected_node_ids": ["2"],
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_query_stages"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "id", "operator": ">=", "value": 3}]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+        let result = run_query(single, None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["row_count"], 2);
+        assert!(result.get("node_id").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_run_query_template_variables() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_template_vars AS
+             SELECT range AS id, 'user_' || range AS name FROM range(10)",
+        )
+        .unwrap();
+        drop(conn);
+        invalidate_query_cache();
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_template_vars"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "id", "operator": ">=", "value": "{{min_id}}"},
+                    {"column": "name", "operator": "!=", "value": "user_{{ skipped }}"}
+                ]}},
+                {"id": "3", "type": "sort", "data": {"order": [{"column": "{{sort_column}}", "direction": "desc"}]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        })
+        .to_string();
+        let variables = HashMap::from([
+            ("min_id".to_string(), serde_json::json!(6)),
+            ("skipped".to_string(), serde_json::json!(8)),
+            ("sort_column".to_string(), serde_json::json!("id")),
+        ]);
+
+        let result = run_query(
+            node_graph.clone(),
+            None,
+            None,
+            None,
+            None,
+            Some(variables.clone()),
+            None,
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let ids: Vec<i64> = result["rows"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| row["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, vec![9, 7, 6]);
+
+        let mut injected = variables.clone();
+        injected.insert(
+            "sort_column".to_string(),
+            serde_json::json!("id; DROP TABLE test_template_vars"),
+        );
+        let result = run_query(
+            node_graph.clone(),
+            None,
+            None,
+            None,
+            None,
+            Some(injected),
+            None,
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError(
+                "Invalid value for template placeholder in column: \"id; DROP TABLE test_template_vars\""
+                    .to_string()
+            )
+        );
+
+        let result = run_query(node_graph, None, None, None, None, None, None).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Unknown template variable: min_id".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply_template_variables() {
+        let variables = HashMap::from([
+            ("ids".to_string(), serde_json::json!([1, 2, 3])),
+            ("status".to_string(), serde_json::json!("it's")),
+            ("columns".to_string(), serde_json::json!(["id", "name"])),
+            ("nested".to_string(), serde_json::json!({"a": 1})),
+        ]);
+        let node_graph = |data: serde_json::Value| {
+            serde_json::json!({
+                "selected_node_id": "1",
+                "nodes": [{"id": "1", "type": "filter", "data": data}],
+                "edges": []
+            })
+            .to_string()
+        };
+
+        let graph = apply_template_variables(
+            &node_graph(serde_json::json!({"conditions": [
+                {"column": "id", "operator": "in", "value": "{{ids}}"},
+                {"column": "status", "operator": "==", "value": "{{status}}"},
+                {"column": "note", "operator": "==", "value": "{{ left as is"}
+            ]})),
+            &variables,
+        )
+        .unwrap();
+        assert_eq!(
+            graph.nodes[0].data,
+            serde_json::json!({"conditions": [
+                {"column": "id", "operator": "in", "value": [1, 2, 3]},
+                {"column": "status", "operator": "==", "value": "it's"},
+                {"column": "note", "operator": "==", "value": "{{ left as is"}
+            ]})
+        );
+
+        let graph = apply_template_variables(
+            &node_graph(serde_json::json!({"columns": "{{columns}}"})),
+            &variables,
+        )
+        .unwrap();
+        assert_eq!(
+            graph.nodes[0].data,
+            serde_json::json!({"columns": ["id", "name"]})
+        );
+
+        let result = apply_template_variables(
+            &node_graph(serde_json::json!({"conditions": [
+                {"column": "id", "operator": "==", "value": "x{{nested}}"}
+            ]})),
+            &variables,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            "Template variable nested must be a string, number or boolean"
+        );
+
+        let result = apply_template_variables(
+            &node_graph(serde_json::json!({"table_name": "{{ids}}"})),
+            &variables,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            "Invalid value for template placeholder in table_name: [1,2,3]"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_infer_filter_type() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_filter_types AS
+             SELECT range::INTEGER AS id, range % 2 = 0 AS even, 'item' || range AS label
+             FROM range(10)",
+        )
+        .unwrap();
+
+        let infer = |column: &str, value: serde_json::Value| {
+            infer_filter_type(column, &value, &conn, "test_filter_types").map(|e| e.to_string())
+        };
+        assert_eq!(infer("id", serde_json::json!("7")).unwrap(), "7");
+        assert_eq!(infer("even", serde_json::json!("false")).unwrap(), "false");
+        assert_eq!(infer("label", serde_json::json!("7")).unwrap(), "'7'");
+        assert_eq!(infer("missing", serde_json::json!("7")).unwrap(), "'7'");
+        assert_eq!(
+            infer("id", serde_json::json!("seven")).unwrap_err(),
+            "Cannot convert 'seven' to INTEGER"
+        );
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_filter_types"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "id", "operator": ">=", "value": "4"},
+                    {"column": "even", "operator": "==", "value": "true"},
+                    {"column": "id", "operator": "in", "value": ["4", "8", "9"]}
+                ]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+
+        let result = run_query(node_graph.clone(), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let ids: Vec<i64> = result["rows"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| row["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, vec![4, 8]);
+        assert_eq!(get_query_row_count(node_graph).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_query_to_file() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_query_to_file AS \
+             SELECT id, 'item_' || id AS name FROM range(10) t(id)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_query_to_file"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "id", "operator": ">=", "value": 4}
+                ]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+
+        let dir = tempfile::tempdir().unwrap();
+        for (format, file_name, reader) in [
+            ("csv", "result.csv", "read_csv_auto"),
+            ("json", "result.json", "read_json_auto"),
+        ] {
+            let path = dir.path().join(file_name).to_string_lossy().to_string();
+
+            let result = run_query_to_file(node_graph.clone(), path.clone(), format.to_string())
+                .await
+                .unwrap();
+            let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+            assert_eq!(
+                result,
+                serde_json::json!({ "rows_written": 6, "path": path })
+            );
+
+            let conn = duckdb_connect().unwrap();
+            let (count, first_name): (i64, String) = conn
+                .query_row(
+                    &format!("SELECT COUNT(*), MIN(name) FROM {}('{}')", reader, path),
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!((count, first_name.as_str()), (6, "item_4"));
+        }
+
+        let result =
+            run_query_to_file(node_graph, "result.xlsx".to_string(), "xlsx".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Unsupported export format: xlsx".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_primary_key_candidates() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_pk_candidates (id INTEGER, email VARCHAR, country VARCHAR, code VARCHAR);
+             INSERT INTO test_pk_candidates VALUES
+                 (1, 'a@example.com', 'JP', 'x'),
+                 (2, 'b@example.com', 'JP', NULL),
+                 (3, 'c@example.com', 'US', 'y');
+             CREATE OR REPLACE TABLE test_pk_candidates_empty (id INTEGER);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = detect_primary_key_candidates("test_pk_candidates".to_string())
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "total_count": 3,
+                "columns": [
+                    {"name": "id", "distinct_count": 3},
+                    {"name": "email", "distinct_count": 3},
+                    {"name": "country", "distinct_count": 2},
+                    {"name": "code", "distinct_count": 2}
+                ],
+                "candidates": ["id", "email"]
+            })
+        );
+
+        let result = detect_primary_key_candidates("test_pk_candidates_empty".to_string())
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["candidates"], serde_json::json!([]));
+
+        let result = detect_primary_key_candidates("bad name".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid table name".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_suggest_pivot() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_pivot_sales AS
+                 SELECT i AS id,
+                        CASE WHEN i % 2 = 0 THEN 'east' ELSE 'west' END AS region,
+                        CASE WHEN i % 3 = 0 THEN 'online' ELSE 'store' END AS channel,
+                        'order_' || i AS reference
+                 FROM range(30) t(i);
+             CREATE OR REPLACE TABLE test_pivot_single (id INTEGER, status VARCHAR);
+             INSERT INTO test_pivot_single VALUES (1, 'open'), (2, 'closed'), (3, NULL);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = suggest_pivot("test_pivot_sales".to_string()).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "candidates": [
+                    {
+                        "column": "region",
+                        "distinct_count": 2,
+                        "sql": r#"PIVOT test_pivot_sales ON "region" USING COUNT(*) GROUP BY "channel""#
+                    },
+                    {
+                        "column": "channel",
+                        "distinct_count": 2,
+                        "sql": r#"PIVOT test_pivot_sales ON "channel" USING COUNT(*) GROUP BY "region""#
+                    }
+                ]
+            })
+        );
+
+        let conn = duckdb_connect().unwrap();
+        let sql = result["candidates"][0]["sql"].as_str().unwrap();
+        let rows: Vec<(String, i64, i64)> = conn
+            .prepare(&format!("SELECT * FROM ({}) ORDER BY channel", sql))
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![("online".to_string(), 5, 5), ("store".to_string(), 10, 10)]
+        );
+        drop(conn);
+
+        let result = suggest_pivot("test_pivot_single".to_string())
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["candidates"][0]["sql"],
+            r#"PIVOT (SELECT "status" FROM test_pivot_single) ON "status" USING COUNT(*)"#
+        );
+
+        let result = suggest_pivot("bad name".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid table name".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_estimate_table_size_bytes() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_size_estimate AS
+             SELECT range::INTEGER AS id, 1.5::DOUBLE AS amount, 'x' AS name
+             FROM range(1000)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let size = estimate_table_size_bytes("test_size_estimate".to_string())
+            .await
+            .unwrap();
+        assert_eq!(size, 1000 * (4 + 8 + 16));
+
+        let result = estimate_table_size_bytes("test_size_estimate_missing".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::DbError("Table not found: test_size_estimate_missing".to_string())
+        );
+
+        let result = estimate_table_size_bytes("bad name".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid table name".to_string())
+        );
+    }
+
+    // Note: Serves one file as an S3-compatible endpoint, answering ranged GETs with 206 as S3 does
+    fn serve_s3_object(body: &'static str) -> std::net::SocketAddr {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    break;
+                };
+                let mut request = [0u8; 4096];
+                let n = stream.read(&mut request).unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..n]);
+                let range = request.lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    if !name.eq_ignore_ascii_case("range") {
+                        return None;
+                    }
+                    let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+                    let last = body.len() - 1;
+                    Some((
+                        start.parse().ok()?,
+                        end.parse().map_or(last, |end: usize| end.min(last)),
+                    ))
+                });
+                let (status, content) = match range {
+                    Some((start, end)) => ("206 Partial Content", &body[start..=end]),
+                    None => ("200 OK", body),
+                };
+                let content_range = range
+                    .map(|(start, end)| {
+                        format!("Content-Range: bytes {}-{}/{}\r\n", start, end, body.len())
+                    })
+                    .unwrap_or_default();
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: text/csv\r\nAccept-Ranges: bytes\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    content_range,
+                    content.len(),
+                    if request.starts_with("HEAD") { "" } else { content }
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        addr
+    }
+
+    // Note: Needs the httpfs extension, which DuckDB downloads on first use, so it is skipped offline
+    #[test]
+    fn test_ingest_url() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        if let Err(e) = conn.execute_batch("INSTALL httpfs; LOAD httpfs;") {
+            eprintln!("Skipping test_ingest_url: httpfs is unavailable: {}", e);
+            return;
+        }
+
+        let addr = serve_s3_object("id,name\n1,apple\n2,banana\n3,cherry\n");
+        conn.execute_batch(&format!(
+            "SET s3_endpoint = '{}'; SET s3_use_ssl = false; SET s3_url_style = 'path';
+             SET s3_region = 'us-east-1';
+             DROP TABLE IF EXISTS test_ingest_url;",
+            addr
+        ))
+        .unwrap();
+
+        let url = "s3://test-bucket/orders.csv";
+        let row_count = ingest_url(&conn, url, "test_ingest_url", "csv").unwrap();
+        assert_eq!(row_count, 3);
+
+        let name: String = conn
+            .query_row("SELECT name FROM test_ingest_url WHERE id = 2", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(name, "banana");
+
+        let http_url = format!("http://{}/test-bucket/orders.csv", addr);
+        let result = ingest_url(&conn, &http_url, "test_ingest_url_http", "csv");
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError(format!(
+                "Unsupported URL scheme: {} (expected https:// or s3://)",
+                http_url
+            ))
+        );
+
+        let result = ingest_url(&conn, url, "test_ingest_url_xlsx", "xlsx");
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Unsupported file format: xlsx".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingest_from_url_validation() {
+        let result = ingest_from_url(
+            "file:///etc/passwd".to_string(),
+            "test_ingest".to_string(),
+            "csv".to_string(),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError(
+                "Unsupported URL scheme: file:///etc/passwd (expected https:// or s3://)"
+                    .to_string()
+            )
+        );
+
+        let result = ingest_from_url(
+            "https://example.com/data.csv".to_string(),
+            "bad name".to_string(),
+            "csv".to_string(),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid table name".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_test_data() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_generated (
+                id INTEGER, small TINYINT, amount DECIMAL(9,2), ratio DOUBLE, active BOOLEAN,
+                name VARCHAR, born DATE, seen TIMESTAMP, key UUID, tags VARCHAR[]
+            )",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = generate_test_data("test_generated".to_string(), 50)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({ "table_name": "test_generated", "rows_inserted": 50 })
+        );
+
+        let conn = duckdb_connect().unwrap();
+        let counts: (i64, i64, i64, i64, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), COUNT(id), COUNT(*) FILTER (born > DATE '2000-01-01'), COUNT(*) FILTER (name LIKE 'val_%'), COUNT(tags)
+                 FROM test_generated",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )
+            .unwrap();
+        assert_eq!(counts, (50, 50, 50, 50, 0));
+
+        let result = generate_test_data("test_generated_missing".to_string(), 5).await;
+        assert!(matches!(result, Err(AppError::DbError(_))));
+
+        let result = generate_test_data("bad name".to_string(), 5).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid table name".to_string())
+        );
+    }
+
+    #[test]
+    fn test_random_value_expr() {
+        assert_eq!(
+            random_value_expr("BIGINT"),
+            "CAST(floor(random() * 100) AS BIGINT)"
+        );
+        assert_eq!(
+            random_value_expr("INTEGER"),
+            "CAST(floor(random() * 100) AS INTEGER)"
+        );
+        assert_eq!(random_value_expr("INTERVAL"), "NULL");
+        assert_eq!(random_value_expr("INTEGER[]"), "NULL");
+        assert_eq!(random_value_expr("boolean"), "random() < 0.5");
+    }
+
+    #[test]
+    fn test_estimated_column_width() {
+        assert_eq!(estimated_column_width("BOOLEAN"), 1);
+        assert_eq!(estimated_column_width("integer"), 4);
+        assert_eq!(estimated_column_width("TIMESTAMP WITH TIME ZONE"), 8);
+        assert_eq!(estimated_column_width("DECIMAL(18,3)"), 8);
+        assert_eq!(estimated_column_width("HUGEINT"), 16);
+        assert_eq!(estimated_column_width("VARCHAR[]"), 16);
+    }
+
+    #[test]
+    fn test_sql_audit_reasons() {
+        let cases: [(&str, &[&str]); 8] = [
+            ("SELECT * FROM orders WHERE id = 1;", &[]),
+            ("SELECT '--; UNION SELECT' AS note, \"a;b\" FROM t", &[]),
+            (
+                "SELECT * FROM users WHERE name = 'x' -- AND active",
+                &["Contains a SQL comment"],
+            ),
+            (
+                "SELECT * FROM users /* hidden */",
+                &["Contains a SQL comment"],
+            ),
+            (
+                "SELECT 1; DROP TABLE users",
+                &["Contains multiple statements"],
+            ),
+            (
+                "SELECT id FROM a UNION ALL SELECT password FROM users",
+                &["Contains UNION SELECT"],
+            ),
+            (
+                "SELECT * FROM users WHERE name = '' OR '1' = '1'",
+                &["Contains an always-true OR condition"],
+            ),
+            (
+                "SELECT * FROM users WHERE name = 'x",
+                &["Cannot be tokenized"],
+            ),
+        ];
+
+        for (sql, expected) in cases {
+            let reasons = sql_audit_reasons(sql);
+            assert_eq!(reasons.len(), expected.len(), "{}: {:?}", sql, reasons);
+            for (reason, expected) in reasons.iter().zip(expected) {
+                assert!(reason.starts_with(expected), "{}: {}", sql, reason);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sql_injection_audit() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE _streaksight_query_history (sql VARCHAR);
+             INSERT INTO _streaksight_query_history VALUES
+                 ('SELECT * FROM orders'),
+                 (NULL),
+                 ('SELECT 1 OR 1 = 1');
+             DROP TABLE IF EXISTS _streaksight_saved_queries;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = sql_injection_audit().await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!([{
+                "table": "_streaksight_query_history",
+                "rowid": 2,
+                "sql": "SELECT 1 OR 1 = 1",
+                "reasons": ["Contains an always-true OR condition"]
+            }])
+        );
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("DROP TABLE _streaksight_query_history")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_test_connection() {
+        setup_test_env();
+
+        let result = test_connection().await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["status"], "ok");
+        assert_eq!(
+            result["path"],
+            duckdb_path().unwrap().to_string_lossy().as_ref()
+        );
+
+        let conn = duckdb_connect().unwrap();
+        let ping_tables: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = '_ping'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(ping_tables, 0);
+    }
+
+    #[test]
+    fn test_workspace_staging_dir_cleanup() {
+        let staging = workspace_staging_dir("test").unwrap();
+        let path = staging.path().to_path_buf();
+        std::fs::create_dir_all(path.join("database")).unwrap();
+        std::fs::write(path.join("database/schema.sql"), "").unwrap();
+        assert!(path.is_dir());
+
+        drop(staging);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_workspace_archive_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("workspace.zip");
+
+        let source_connectors = dir.path().join("source_connectors");
+        std::fs::create_dir_all(source_connectors.join("github")).unwrap();
+        std::fs::write(source_connectors.join("StripeConnector.js"), "// stripe").unwrap();
+        std::fs::write(
+            source_connectors.join("github/GithubIssuesConnector.js"),
+            "// github",
+        )
+        .unwrap();
+        std::fs::write(
+            source_connectors.join("github/connector.json"),
+            r#"{ "id": "GithubIssues", "label": "GitHub Issues" }"#,
+        )
+        .unwrap();
+        std::fs::write(source_connectors.join("notes.txt"), "not a connector").unwrap();
+
+        let source = Connection::open_in_memory().unwrap();
+        source
+            .execute_batch(
+                "CREATE TABLE orders AS SELECT range AS id FROM range(3);
+                 CREATE TABLE _streaksight_saved_queries (name VARCHAR, sql VARCHAR, node_graph VARCHAR);
+                 INSERT INTO _streaksight_saved_queries VALUES ('all orders', 'SELECT * FROM orders', NULL);",
+            )
+            .unwrap();
+
+        let exported = write_workspace_archive(&source, &source_connectors, &archive_path).unwrap();
+        assert_eq!(exported["queries"], 1);
+        assert_eq!(exported["connectors"], 3);
+
+        let entries: Vec<String> =
+            zip::ZipArchive::new(std::fs::File::open(&archive_path).unwrap())
+                .unwrap()
+                .file_names()
+                .map(str::to_string)
+                .collect();
+        assert!(entries.contains(&"database/schema.sql".to_string()));
+        assert!(entries.contains(&"queries/1.json".to_string()));
+        assert!(entries.contains(&"connectors/github/connector.json".to_string()));
+        assert!(!entries.contains(&"connectors/notes.txt".to_string()));
+
+        let target_connectors = dir.path().join("target_connectors");
+        std::fs::create_dir_all(&target_connectors).unwrap();
+        std::fs::write(target_connectors.join("StripeConnector.js"), "// old").unwrap();
+        let mut target = Connection::open_in_memory().unwrap();
+        target
+            .execute_batch("CREATE TABLE orders (id INTEGER); INSERT INTO orders VALUES (42);")
+            .unwrap();
+
+        let result = read_workspace_archive(&mut target, &target_connectors, &archive_path, false);
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError(format!(
+                "Workspace import would overwrite: orders, {}",
+                target_connectors.join("StripeConnector.js").display()
+            ))
+        );
+        let ids: Vec<i64> = target
+            .prepare("SELECT id FROM orders")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, vec![42]);
+
+        let imported =
+            read_workspace_archive(&mut target, &target_connectors, &archive_path, true).unwrap();
+        assert_eq!(
+            imported["tables"],
+            serde_json::json!(["_streaksight_saved_queries", "orders"])
+        );
+
+        let ids: Vec<i64> = target
+            .prepare("SELECT id FROM orders ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, vec![0, 1, 2]);
+        let query_name: String = target
+            .query_row("SELECT name FROM _streaksight_saved_queries", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(query_name, "all orders");
+        assert_eq!(
+            std::fs::read_to_string(target_connectors.join("StripeConnector.js")).unwrap(),
+            "// stripe"
+        );
+        assert!(target_connectors.join("github/connector.json").is_file());
+    }
+
+    #[test]
+    fn test_workspace_archive_reimport_sequence_and_macro() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("workspace.zip");
+        let connectors_dir = dir.path().join("connectors");
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE SEQUENCE order_ids START 1;
+             CREATE TABLE orders (id INTEGER DEFAULT nextval('order_ids'), amount INTEGER);
+             INSERT INTO orders (amount) VALUES (10), (20);
+             CREATE MACRO double_amount(a) AS a * 2;
+             CREATE MACRO large_orders() AS TABLE SELECT * FROM orders WHERE amount > 15;
+             CREATE VIEW order_totals AS SELECT SUM(amount) AS total FROM orders;",
+        )
+        .unwrap();
+
+        write_workspace_archive(&conn, &connectors_dir, &archive_path).unwrap();
+
+        let result = read_workspace_archive(&mut conn, &connectors_dir, &archive_path, false);
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError(
+                "Workspace import would overwrite: double_amount, large_orders, order_ids, order_totals, orders"
+                    .to_string()
+            )
+        );
+
+        let imported =
+            read_workspace_archive(&mut conn, &connectors_dir, &archive_path, true).unwrap();
+        assert_eq!(imported["tables"], serde_json::json!(["orders"]));
+
+        conn.execute_batch("INSERT INTO orders (amount) VALUES (30)")
+            .unwrap();
+        let rows: Vec<(i64, i64)> = conn
+            .prepare("SELECT id, double_amount(amount) FROM orders ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows, vec![(1, 20), (2, 40), (3, 60)]);
+        let large: i64 = conn
+            .query_row("SELECT COUNT(*) FROM large_orders()", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(large, 2);
+        let total: i64 = conn
+            .query_row("SELECT total FROM order_totals", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(total, 60);
+    }
+
+    #[tokio::test]
+    async fn test_flatten_json_column() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            r#"CREATE OR REPLACE TABLE test_flatten_events (id INTEGER, payload VARCHAR);
+               INSERT INTO test_flatten_events VALUES
+                   (1, '{"kind": "click", "meta": {"x": 2}}'),
+                   (2, '{"kind": "view", "page.url": "/home"}'),
+                   (3, NULL),
+                   (4, '[1, 2]');
+               DROP TABLE IF EXISTS test_flatten_events_wide;"#,
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = flatten_json_column(
+            "test_flatten_events".to_string(),
+            "payload".to_string(),
+            "test_flatten_events_wide".to_string(),
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["columns"],
+            serde_json::json!(["kind", "meta", "page.url"])
+        );
+
+        let conn = duckdb_connect().unwrap();
+        let columns: Vec<String> = describe_table(&conn, "test_flatten_events_wide")
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(columns, vec!["id", "payload", "kind", "meta", "page.url"]);
+
+        let rows: Vec<(i32, Option<String>, Option<String>)> = conn
+            .prepare(
+                r#"SELECT id, CAST(meta AS VARCHAR), CAST("page.url" AS VARCHAR)
+                   FROM test_flatten_events_wide ORDER BY id"#,
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (1, Some(r#"{"x":2}"#.to_string()), None),
+                (2, None, Some(r#""/home""#.to_string())),
+                (3, None, None),
+                (4, None, None),
+            ]
+        );
+        drop(conn);
+
+        let result = flatten_json_column(
+            "test_flatten_events_wide".to_string(),
+            "payload".to_string(),
+            "test_flatten_events_wider".to_string(),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError(
+                "JSON key kind conflicts with an existing column".to_string()
+            )
+        );
+
+        let result = flatten_json_column(
+            "test_flatten_events".to_string(),
+            "missing".to_string(),
+            "test_flatten_events_wider".to_string(),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Column not found: missing".to_string())
+        );
+    }
+
+    #[test]
+    fn test_json_key_path() {
+        assert_eq!(json_key_path("kind"), r#"'$."kind"'"#);
+        assert_eq!(json_key_path("page.url"), r#"'$."page.url"'"#);
+        assert_eq!(
+            json_key_path(r#"it's "quoted""#),
+            r#"'$."it''s \"quoted\""'"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hash_column() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_hash_users (id INTEGER, email VARCHAR, code INTEGER);
+             INSERT INTO test_hash_users VALUES
+                 (1, 'alice@example.com', 42),
+                 (2, NULL, NULL);
+             CREATE OR REPLACE TABLE test_hash_users_copy AS SELECT * FROM test_hash_users;",
+        )
+        .unwrap();
+        drop(conn);
+
+        for table in ["test_hash_users", "test_hash_users_copy"] {
+            hash_column(
+                table.to_string(),
+                "email".to_string(),
+                HashAlgorithm::Sha256,
+                Some("pepper".to_string()),
+            )
+            .await
+            .unwrap();
+            hash_column(
+                table.to_string(),
+                "code".to_string(),
+                HashAlgorithm::Md5,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let conn = duckdb_connect().unwrap();
+        let read = |table: &str| -> Vec<(Option<String>, Option<String>)> {
+            conn.prepare(&format!("SELECT email, code FROM {} ORDER BY id", table))
+                .unwrap()
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        let rows = read("test_hash_users");
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    Some(sha256_hex("pepperalice@example.com")),
+                    Some("a1d0c6e83f027327d8461063f4ac58a6".to_string())
+                ),
+                (None, None),
+            ]
+        );
+        assert_eq!(read("test_hash_users_copy"), rows);
+        drop(conn);
+
+        let result = hash_column(
+            "test_hash_users".to_string(),
+            "missing".to_string(),
+            HashAlgorithm::Md5,
+            None,
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Column not found: missing".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_fake_data_for_schema() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_fake_source (
+                 id TINYINT, price DECIMAL(6, 2), score DOUBLE, active BOOLEAN,
+                 name VARCHAR, born DATE, seen TIMESTAMP, tags VARCHAR[]
+             );
+             DROP TABLE IF EXISTS test_fake_dest;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = generate_fake_data_for_schema(
+            "test_fake_source".to_string(),
+            25,
+            "test_fake_dest".to_string(),
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["rows_inserted"], 25);
+
+        let conn = duckdb_connect().unwrap();
+        assert_eq!(
+            describe_table(&conn, "test_fake_dest").unwrap(),
+            describe_table(&conn, "test_fake_source").unwrap()
+        );
+        let (rows, names, tags): (i64, i64, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), COUNT(DISTINCT name), COUNT(tags) FROM test_fake_dest",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((rows, names, tags), (25, 25, 0));
+        drop(conn);
+
+        let result = generate_fake_data_for_schema(
+            "test_fake_source".to_string(),
+            5,
+            "test_fake_dest".to_string(),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Table already exists: test_fake_dest".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audit_column_access() {
+        setup_test_env();
+
+        let graph = |table_name: &str, node: serde_json::Value| {
+            serde_json::json!({
+                "selected_node_id": "2",
+                "nodes": [
+                    {"id": "1", "type": "table", "data": {"table_name": table_name}},
+                    node
+                ],
+                "edges": [{"source": "1", "target": "2"}]
+            })
+            .to_string()
+        };
+        let saved = [
+            (
+                "selected",
+                graph(
+                    "orders",
+                    serde_json::json!({"id": "2", "type": "select", "data": {"columns": ["id", "Amount"]}}),
+                ),
+            ),
+            (
+                "filtered",
+                graph(
+                    "orders",
+                    serde_json::json!({"id": "2", "type": "filter", "data": {"conditions": [
+                        {"column": "amount", "operator": ">", "value": 10}
+                    ]}}),
+                ),
+            ),
+            (
+                "sorted_other_column",
+                graph(
+                    "orders",
+                    serde_json::json!({"id": "2", "type": "sort", "data": {"order": [
+                        {"column": "id", "direction": "asc"}
+                    ]}}),
+                ),
+            ),
+            (
+                "other_table",
+                graph(
+                    "refunds",
+                    serde_json::json!({"id": "2", "type": "select", "data": {"columns": ["amount"]}}),
+                ),
+            ),
+            (
+                "broken",
+                graph(
+                    "orders",
+                    serde_json::json!({"id": "2", "type": "select", "data": {"columns": "amount"}}),
+                ),
+            ),
+        ];
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE _streaksight_saved_queries \
+             (name VARCHAR, sql VARCHAR, node_graph VARCHAR)",
+        )
+        .unwrap();
+        for (name, node_graph) in &saved {
+            conn.execute(
+                "INSERT INTO _streaksight_saved_queries VALUES (?, NULL, ?)",
+                [name, node_graph.as_str()],
+            )
+            .unwrap();
+        }
+        drop(conn);
+
+        let result = audit_column_access("orders".to_string(), "amount".to_string())
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "queries": ["selected", "filtered"],
+                "unreadable": ["broken"]
+            })
+        );
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("DROP TABLE _streaksight_saved_queries")
+            .unwrap();
+        drop(conn);
+
+        let result = audit_column_access("orders".to_string(), "amount".to_string())
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({ "queries": [], "unreadable": [] })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merge_table() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_merge_target (id INTEGER, name VARCHAR, price INTEGER);
+             INSERT INTO test_merge_target VALUES (1, 'a', 10), (2, 'b', 20);
+             CREATE OR REPLACE TABLE test_merge_source (id INTEGER, name VARCHAR, price INTEGER);
+             INSERT INTO test_merge_source VALUES (2, 'B', 200), (3, 'c', 30);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = merge_table(
+            "test_merge_source".to_string(),
+            "test_merge_target".to_string(),
+            vec!["id".to_string()],
+            vec!["price".to_string()],
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({ "rows_updated": 1, "rows_inserted": 1 })
+        );
+
+        let conn = duckdb_connect().unwrap();
+        let rows: Vec<(i32, String, i32)> = conn
+            .prepare("SELECT id, name, price FROM test_merge_target ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (1, "a".to_string(), 10),
+                (2, "b".to_string(), 200),
+                (3, "c".to_string(), 30)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merge_table_validation() {
+        let cases = [
+            (
+                "src; DROP TABLE x",
+                vec!["id"],
+                vec![],
+                "Invalid identifier: src; DROP TABLE x",
+            ),
+            (
+                "src",
+                vec![],
+                vec!["price"],
+                "At least one match key is required",
+            ),
+            (
+                "src",
+                vec!["id"],
+                vec!["id", "price"],
+                "Match key cannot be updated: id",
+            ),
+        ];
+
+        for (source, match_keys, update_columns, expected) in cases {
+            let result = merge_table(
+                source.to_string(),
+                "target".to_string(),
+                match_keys.into_iter().map(String::from).collect(),
+                update_columns.into_iter().map(String::from).collect(),
+            )
+            .await;
+            assert_eq!(
+                result.unwrap_err(),
+                AppError::ValidationError(expected.to_string())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_column_correlation() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_corr_table (x INTEGER, y DOUBLE, label VARCHAR);
+             INSERT INTO test_corr_table VALUES (1, 2.0, 'a'), (2, 4.0, 'b'), (3, 6.0, 'c');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let corr = column_correlation(
+            "test_corr_table".to_string(),
+            "x".to_string(),
+            "x".to_string(),
+        )
+        .await
+        .unwrap();
+        assert!((corr - 1.0).abs() < 1e-9);
+
+        let corr = column_correlation(
+            "test_corr_table".to_string(),
+            "x".to_string(),
+            "y".to_string(),
+        )
+        .await
+        .unwrap();
+        assert!((corr - 1.0).abs() < 1e-9);
+
+        let result = column_correlation(
+            "test_corr_table".to_string(),
+            "x".to_string(),
+            "label".to_string(),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Column label is not numeric (type VARCHAR)".to_string())
+        );
+
+        let result = column_correlation(
+            "test_corr_table".to_string(),
+            "x".to_string(),
+            "missing".to_string(),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Column not found: test_corr_table.missing".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_join_preview() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_join_users (id INTEGER, name VARCHAR);
+             INSERT INTO test_join_users VALUES (1, 'Alice'), (2, 'Bob'), (3, 'Carol');
+             CREATE OR REPLACE TABLE test_join_orders (user_id INTEGER, total INTEGER);
+             INSERT INTO test_join_orders VALUES (1, 100), (1, 200), (2, 50);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = join_preview(
+            "test_join_users".to_string(),
+            "test_join_orders".to_string(),
+            "id".to_string(),
+            "user_id".to_string(),
+            "inner".to_string(),
+            2,
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(result["row_count"], 2);
+        assert_eq!(
+            result["columns"],
+            serde_json::json!([
+                {"name": "id"},
+                {"name": "name"},
+                {"name": "user_id"},
+                {"name": "total"}
+            ])
+        );
+
+        let result = join_preview(
+            "test_join_users".to_string(),
+            "test_join_orders".to_string(),
+            "id".to_string(),
+            "user_id".to_string(),
+            "left".to_string(),
+            10,
+        )
+        .await
+        .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["row_count"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_join_preview_invalid_args() {
+        let result = join_preview(
+            "users".to_string(),
+            "orders".to_string(),
+            "id".to_string(),
+            "user_id".to_string(),
+            "cross".to_string(),
+            10,
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Unsupported join type: cross".to_string())
+        );
+
+        let result = join_preview(
+            "users; DROP TABLE users".to_string(),
+            "orders".to_string(),
+            "id".to_string(),
+            "user_id".to_string(),
+            "inner".to_string(),
+            10,
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid identifier: users; DROP TABLE users".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_query_schema() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_schema_products (category VARCHAR, price DOUBLE);
+             INSERT INTO test_schema_products VALUES ('a', 1.0), ('b', 2.0);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_schema_products"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 0}]}},
+                {"id": "3", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [
+                        {"function": "COUNT(*)", "column": ""},
+                        {"function": "AVG", "column": "price"},
+                        {"function": "MAX", "column": "price"}
+                    ]
+                }}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let result = get_query_schema(node_graph.to_string()).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result,
+            serde_json::json!([
+                {"name": "category", "type": "VARCHAR", "nullable": true},
+                {"name": "count_star()", "type": "BIGINT", "nullable": true},
+                {"name": "avg(price)", "type": "DOUBLE", "nullable": true},
+                {"name": "max(price)", "type": "DOUBLE", "nullable": true}
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_node_graph_to_json_schema() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_json_schema_events (
+                 id BIGINT NOT NULL, amount DECIMAL(10, 2), ok BOOLEAN, day DATE,
+                 created_at TIMESTAMP, label VARCHAR, tags VARCHAR[]
+             );
+             INSERT INTO test_json_schema_events VALUES (1, NULL, true, NULL, NULL, NULL, NULL);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = r#"{
+            "selected_node_id": "1",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_json_schema_events"}}
+            ],
+            "edges": []
+        }"#;
+
+        let result = node_graph_to_json_schema(node_graph.to_string())
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "amount": {"type": ["number", "null"]},
+                    "ok": {"type": ["boolean", "null"]},
+                    "day": {"type": ["string", "null"], "format": "date"},
+                    "created_at": {"type": ["string", "null"], "format": "date-time"},
+                    "label": {"type": ["string", "null"]},
+                    "tags": {"type": ["array", "null"], "items": {"type": "string"}}
+                },
+                "required": ["id", "amount", "ok", "day", "created_at", "label", "tags"]
+            })
+        );
+
+        let rows = run_query(node_graph.to_string(), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let rows: serde_json::Value = serde_json::from_str(&rows).unwrap();
+        let validator = jsonschema::validator_for(&result).unwrap();
+        assert_eq!(rows["rows"].as_array().unwrap().len(), 1);
+        for row in rows["rows"].as_array().unwrap() {
+            assert!(validator.is_valid(row), "{}", row);
+        }
+    }
+
+    #[test]
+    fn test_json_schema_for_type() {
+        assert_eq!(
+            json_schema_for_type("HUGEINT"),
+            serde_json::json!({"type": "integer"})
+        );
+        assert_eq!(
+            json_schema_for_type("TIME"),
+            serde_json::json!({"type": "string", "format": "time"})
+        );
+        assert_eq!(
+            json_schema_for_type("INTERVAL"),
+            serde_json::json!({"type": "string"})
+        );
+        assert_eq!(
+            json_schema_for_type("INTEGER[][]"),
+            serde_json::json!({
+                "type": "array",
+                "items": {"type": "array", "items": {"type": "integer"}}
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_schema_to_table() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_apply_schema (id VARCHAR, price VARCHAR, name VARCHAR);
+             INSERT INTO test_apply_schema VALUES ('1', '9.5', 'a'), ('2', '3.25', 'b');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let schema = serde_json::json!({
+            "columns": [
+                {"name": "id", "type": "INTEGER"},
+                {"name": "price", "type": "number"},
+                {"name": "name", "type": "string"}
+            ]
+        });
+        let result = apply_schema_to_table("test_apply_schema".to_string(), schema)
+            .await
+            .unwrap();
+        assert_eq!(result, r#"["id","price"]"#);
+
+        let conn = duckdb_connect().unwrap();
+        let total: f64 = conn
+            .query_row(
+                "SELECT SUM(id) + SUM(price) FROM test_apply_schema",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(total, 15.75);
+    }
+
+    #[tokio::test]
+    async fn test_apply_schema_to_table_rolls_back() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_apply_schema_rollback (id VARCHAR, label VARCHAR);
+             INSERT INTO test_apply_schema_rollback VALUES ('1', 'x');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let schema = serde_json::json!({
+            "columns": [
+                {"name": "id", "type": "INTEGER"},
+                {"name": "label", "type": "INTEGER"}
+            ]
+        });
+        let result = apply_schema_to_table("test_apply_schema_rollback".to_string(), schema).await;
+        assert!(matches!(result, Err(AppError::DbError(_))));
+
+        let conn = duckdb_connect().unwrap();
+        let id_type: String = conn
+            .query_row(
+                "SELECT data_type FROM information_schema.columns \
+                 WHERE table_name = 'test_apply_schema_rollback' AND column_name = 'id'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(id_type, "VARCHAR");
+    }
+
+    #[tokio::test]
+    async fn test_sql_stream_delivers_rows_incrementally() {
+        setup_test_env();
+
+        let (mut rx, handle) = spawn_sql_stream("SELECT * FROM range(100000) t(n)".to_string(), 1);
+
+        assert_eq!(
+            rx.recv().await.unwrap().unwrap(),
+            serde_json::json!({"n": 0})
+        );
+        assert_eq!(
+            rx.recv().await.unwrap().unwrap(),
+            serde_json::json!({"n": 1})
+        );
+
+        // Note: The producer is blocked on the bounded channel instead of reading every row
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(!handle.is_finished());
+
+        drop(rx);
+        handle.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_sql_stream_from_js() {
+        setup_test_env();
+
+        let output_path = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("test_sql_stream.json");
+        let _ = std::fs::remove_file(&output_path);
+
+        let script = format!(
+            r#"(async () => {{
+                   const rows = [];
+                   for await (const row of streaksight.runSQLStream("SELECT * FROM range(5) t(n)")) {{
+                       rows.push(row.n);
+                   }}
+
+                   const reader = streaksight.runSQLStream("SELECT * FROM range(1000000) t(n)").getReader();
+                   const first = await reader.read();
+                   await reader.cancel();
+
+                   let error = null;
+                   try {{
+                       await streaksight.runSQLStream("SELECT * FROM missing_table").getReader().read();
+                   }} catch (e) {{
+                       error = e.message;
+                   }}
+
+                   await streaksight.writeFile("{}", JSON.stringify({{ rows, first, error }}));
+               }})()"#,
+            output_path.to_string_lossy().replace('\\', "/")
+        );
+
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        runtime.execute_script("<sql_stream>", script).unwrap();
+        runtime.run_event_loop(Default::default()).await.unwrap();
+
+        let output: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&output_path).unwrap()).unwrap();
+        assert_eq!(output["rows"], serde_json::json!([0, 1, 2, 3, 4]));
+        assert_eq!(
+            output["first"],
+            serde_json::json!({"done": false, "value": {"n": 0}})
+        );
+        assert!(output["error"]
+            .as_str()
+            .unwrap()
+            .contains("Failed to prepare SQL"));
+    }
+
+    #[tokio::test]
+    async fn test_run_pragma_from_js() {
+        setup_test_env();
+
+        let output_path = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("test_run_pragma.json");
+        let _ = std::fs::remove_file(&output_path);
+
+        let script = format!(
+            r#"(async () => {{
+                   const platform = await streaksight.runPragma("platform");
+                   let error = null;
+                   try {{
+                       await streaksight.runPragma("memory_limit='1GB'");
+                   }} catch (e) {{
+                       error = e.message;
+                   }}
+
+                   await streaksight.writeFile("{}", JSON.stringify({{ platform, error }}));
+               }})()"#,
+            output_path.to_string_lossy().replace('\\', "/")
+        );
+
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        runtime.execute_script("<run_pragma>", script).unwrap();
+        runtime.run_event_loop(Default::default()).await.unwrap();
+
+        let output: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&output_path).unwrap()).unwrap();
+        assert_eq!(output["platform"].as_array().unwrap().len(), 1);
+        assert!(output["platform"][0]["platform"].is_string());
+        assert_eq!(output["error"], "Pragma not allowed: memory_limit='1GB'");
+    }
+
+    #[tokio::test]
+    async fn test_get_system_info_permission() {
+        use deno_core::{JsRuntime, RuntimeOptions};
+
+        let dir = tempfile::tempdir().unwrap();
+        for system_info in [false, true] {
+            let output_path = dir.path().join(format!("system_info_{}.json", system_info));
+            let script = format!(
+                r#"(async () => {{
+                       let info = null;
+                       let error = null;
+                       try {{
+                           info = streaksight.getSystemInfo();
+                       }} catch (e) {{
+                           error = e.message;
+                       }}
+                       await streaksight.writeFile("{}", JSON.stringify({{ info, error }}));
+                   }})()"#,
+                output_path.to_string_lossy().replace('\\', "/")
+            );
+
+            let mut runtime = JsRuntime::new(RuntimeOptions {
+                extensions: vec![streaksight_ext::init()],
+                ..Default::default()
+            });
+            runtime
+                .op_state()
+                .borrow_mut()
+                .put(connector_registry::ConnectorPermissions {
+                    system_info,
+                    ..Default::default()
+                });
+            runtime.execute_script("<system_info>", script).unwrap();
+            runtime.run_event_loop(Default::default()).await.unwrap();
+
+            let output: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&output_path).unwrap()).unwrap();
+            if system_info {
+                assert!(output["info"]["cpu_count"].as_u64().unwrap() >= 1);
+                assert!(output["info"]["available_memory_bytes"].as_u64().is_some());
+                assert_eq!(output["info"]["os"], std::env::consts::OS);
+                assert!(output["error"].is_null());
+            } else {
+                assert!(output["info"].is_null());
+                assert!(output["error"]
+                    .as_str()
+                    .unwrap()
+                    .contains("Connector does not have the system_info permission"));
+            }
         }
-        rows_data.push(serde_json::Value::Object(row_obj));
     }
 
-    let columns_info: Vec<serde_json::Value> = column_names
-        .iter()
-        .map(|name| {
-            serde_json::json!({
-                "name": name
-            })
-        })
-        .collect();
+    #[tokio::test]
+    async fn test_infer_csv_schema() {
+        setup_test_env();
+
+        let csv_path = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("test_infer_schema.csv");
+        std::fs::write(
+            &csv_path,
+            "id;name;active;score;joined\n1;Alice;true;95.5;2024-01-15\n2;Bob;false;87.3;2024-02-01\n",
+        )
+        .unwrap();
+
+        let schema =
+            schema_inference::infer_csv_schema(csv_path.to_str().unwrap(), ';', true, 100).unwrap();
+
+        assert_eq!(
+            schema,
+            serde_json::json!({
+                "columns": [
+                    {"name": "id", "type": "number"},
+                    {"name": "name", "type": "string"},
+                    {"name": "active", "type": "boolean"},
+                    {"name": "score", "type": "number"},
+                    {"name": "joined", "type": "date"}
+                ]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_infer_csv_schema_without_header() {
+        setup_test_env();
+
+        let csv_path = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("test_infer_schema_no_header.csv");
+        std::fs::write(&csv_path, "1,Alice\n2,Bob\n").unwrap();
+
+        let schema =
+            schema_inference::infer_csv_schema(csv_path.to_str().unwrap(), ',', false, 10).unwrap();
+
+        assert_eq!(
+            schema,
+            serde_json::json!({
+                "columns": [
+                    {"name": "column0", "type": "number"},
+                    {"name": "column1", "type": "string"}
+                ]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_infer_csv_schema_missing_file() {
+        setup_test_env();
+
+        let result = schema_inference::infer_csv_schema("/nonexistent/file.csv", ',', true, 10);
+        assert!(result.unwrap_err().starts_with("Failed to"));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_with_encoding() {
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let latin1_path = temp_dir.join("test_latin1.csv");
+        let output_path = temp_dir.join("test_latin1_output.json");
+        let _ = std::fs::remove_file(&output_path);
+
+        // Note: "Jos\xe9" and "M\xfcller" are "José" and "Müller" in Latin-1
+        std::fs::write(&latin1_path, b"id,name\n1,Jos\xe9\n2,M\xfcller\n").unwrap();
+
+        let script = format!(
+            r#"(async () => {{
+                   const contents = await streaksight.readFileWithEncoding("{}", "latin1");
+                   let error = null;
+                   try {{
+                       await streaksight.readFileWithEncoding("{}", "not-an-encoding");
+                   }} catch (e) {{
+                       error = e.message;
+                   }}
+                   await streaksight.writeFile("{}", JSON.stringify({{ contents, error }}));
+               }})()"#,
+            latin1_path.to_string_lossy().replace('\\', "/"),
+            latin1_path.to_string_lossy().replace('\\', "/"),
+            output_path.to_string_lossy().replace('\\', "/")
+        );
+
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        runtime.execute_script("<read_encoding>", script).unwrap();
+        runtime.run_event_loop(Default::default()).await.unwrap();
+
+        let output: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&output_path).unwrap()).unwrap();
+        assert_eq!(output["contents"], "id,name\n1,José\n2,Müller\n");
+        assert_eq!(output["error"], "Unsupported encoding: not-an-encoding");
+    }
+
+    #[tokio::test]
+    async fn test_read_file_chunked() {
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let text_path = temp_dir.join("test_read_chunked.txt");
+        let output_path = temp_dir.join("test_read_chunked_output.json");
+        let _ = std::fs::remove_file(&output_path);
+
+        std::fs::write(&text_path, "id,name\n1,José\n2,Müller\n").unwrap();
+
+        let script = format!(
+            r#"(async () => {{
+                   const path = "{}";
+                   const chunks = [];
+                   let chunk;
+                   while ((chunk = await streaksight.readFileChunked(path, 5)) !== "") {{
+                       chunks.push(chunk);
+                   }}
+                   const restarted = await streaksight.readFileChunked(path, 5);
+                   const atOffset = await streaksight.readFileChunked(path, 4, 10);
+                   const offsetChunks = [];
+                   let offset = 0;
+                   for (;;) {{
+                       const result = await streaksight.readFileChunked(path, 5, offset);
+                       if (result.chunk === "") break;
+                       offsetChunks.push(result.chunk);
+                       offset = result.nextOffset;
+                   }}
+                   const huge = await streaksight.readFileChunked(path, 1e12, 24);
+                   let error = null;
+                   try {{
+                       await streaksight.readFileChunked(path, 1, 13);
+                   }} catch (e) {{
+                       error = e.message;
+                   }}
+                   await streaksight.writeFile("{}", JSON.stringify({{ chunks, restarted, atOffset, offsetChunks, offset, huge, error }}));
+               }})()"#,
+            text_path.to_string_lossy().replace('\\', "/"),
+            output_path.to_string_lossy().replace('\\', "/")
+        );
+
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        runtime.execute_script("<read_chunked>", script).unwrap();
+        runtime.run_event_loop(Default::default()).await.unwrap();
+
+        let output: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&output_path).unwrap()).unwrap();
+        assert_eq!(
+            output["chunks"],
+            serde_json::json!(["id,na", "me\n1,", "José", "\n2,M", "ülle", "r\n"])
+        );
+        assert_eq!(output["restarted"], "id,na");
+        assert_eq!(
+            output["atOffset"],
+            serde_json::json!({ "chunk": "Jos", "nextOffset": 13 })
+        );
+        assert_eq!(output["offsetChunks"], output["chunks"]);
+        assert_eq!(output["offset"], 26);
+        assert_eq!(
+            output["huge"],
+            serde_json::json!({ "chunk": "r\n", "nextOffset": 26 })
+        );
+        assert_eq!(
+            output["error"],
+            "Chunk size is too small to hold a complete character"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_csv_connector_sync_with_encoding() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let csv_path = temp_dir.join("test_sync_latin1.csv");
+        std::fs::write(&csv_path, b"id,name\n1,Jos\xe9\n2,M\xfcller\n").unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("DROP TABLE IF EXISTS test_csv_latin1")
+            .unwrap();
+        drop(conn);
+
+        let connector_path = std::env::current_dir()
+            .unwrap()
+            .join("src/LocalFileCSVConnector.js");
+        let test_js_path = temp_dir.join("test_encoding_plugin.js");
+        std::fs::write(
+            &test_js_path,
+            format!(
+                r#"import {{ sync }} from "{}";
+                   await sync("test_csv_latin1", {{ filePath: "{}", encoding: "latin1" }}, {{}});"#,
+                connector_path.to_string_lossy().replace('\\', "/"),
+                csv_path.to_string_lossy().replace('\\', "/")
+            ),
+        )
+        .unwrap();
+
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        let module_path = deno_core::ModuleSpecifier::from_file_path(&test_js_path).unwrap();
+        execute_deno_module(&mut runtime, &module_path)
+            .await
+            .unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        let names: Vec<String> = conn
+            .prepare("SELECT name FROM test_csv_latin1 ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(names, vec!["José", "Müller"]);
+        assert_eq!(
+            transcoded_copies_left("streaksight_utf8_test_csv_latin1_"),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sample_column_widths() {
+        setup_test_env();
+
+        let csv_path = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("test_column_widths.csv");
+        std::fs::write(
+            &csv_path,
+            format!("id,note,blob\n1,,abc\n22,é,{}\n", "x".repeat(500)),
+        )
+        .unwrap();
+
+        let widths =
+            schema_inference::sample_column_widths(csv_path.to_str().unwrap(), "csv", 100).unwrap();
+
+        assert_eq!(
+            widths,
+            serde_json::json!({
+                "columns": [
+                    {"name": "id", "width": 2},
+                    {"name": "note", "width": 2},
+                    {"name": "blob", "width": 500}
+                ]
+            })
+        );
+
+        let result = schema_inference::sample_column_widths(csv_path.to_str().unwrap(), "xml", 100);
+        assert_eq!(result.unwrap_err(), "Unsupported file format: xml");
+    }
+
+    #[tokio::test]
+    async fn test_sample_rows() {
+        setup_test_env();
+
+        let csv_path = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("test_sample_rows.csv");
+        std::fs::write(&csv_path, "id,name\n1,Alice\n2,Bob\n3,Charlie\n").unwrap();
+
+        let rows = schema_inference::sample_rows(csv_path.to_str().unwrap(), "csv", 2).unwrap();
+
+        assert_eq!(
+            rows,
+            serde_json::json!([
+                {"id": 1, "name": "Alice"},
+                {"id": 2, "name": "Bob"}
+            ])
+        );
+
+        let rows = schema_inference::sample_rows(csv_path.to_str().unwrap(), "csv", 0).unwrap();
+        assert_eq!(rows, serde_json::json!([]));
+
+        let result = schema_inference::sample_rows(csv_path.to_str().unwrap(), "xml", 2);
+        assert_eq!(result.unwrap_err(), "Unsupported file format: xml");
+    }
+
+    #[tokio::test]
+    async fn test_csv_connector_skips_large_columns() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let csv_path = temp_dir.join("test_large_columns.csv");
+        let output_path = temp_dir.join("test_large_columns_schema.json");
+        std::fs::write(
+            &csv_path,
+            format!("id,name,image\n1,Alice,{}\n2,Bob,\n", "A".repeat(2048)),
+        )
+        .unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("DROP TABLE IF EXISTS test_large_columns")
+            .unwrap();
+        drop(conn);
+
+        let connector_path = std::env::current_dir()
+            .unwrap()
+            .join("src/LocalFileCSVConnector.js");
+        let test_js_path = temp_dir.join("test_large_columns_plugin.js");
+        std::fs::write(
+            &test_js_path,
+            format!(
+                r#"import {{ discovery, sync }} from "{}";
+                   const config = {{ filePath: "{}", largeObjectThreshold: "1024" }};
+                   const schema = await discovery(config);
+                   await streaksight.writeFile("{}", JSON.stringify(schema));
+                   await sync("test_large_columns", config, schema);"#,
+                connector_path.to_string_lossy().replace('\\', "/"),
+                csv_path.to_string_lossy().replace('\\', "/"),
+                output_path.to_string_lossy().replace('\\', "/")
+            ),
+        )
+        .unwrap();
+
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        let module_path = deno_core::ModuleSpecifier::from_file_path(&test_js_path).unwrap();
+        execute_deno_module(&mut runtime, &module_path)
+            .await
+            .unwrap();
+
+        let schema: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&output_path).unwrap()).unwrap();
+        assert_eq!(
+            schema["columns"],
+            serde_json::json!([
+                {"name": "id", "type": "number"},
+                {"name": "name", "type": "string"},
+                {"name": "image", "type": "string", "skip": true}
+            ])
+        );
+
+        let conn = duckdb_connect().unwrap();
+        let columns: Vec<String> = conn
+            .prepare(
+                "SELECT column_name FROM information_schema.columns \
+                 WHERE table_name = 'test_large_columns' ORDER BY ordinal_position",
+            )
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(columns, vec!["id", "name"]);
+    }
+
+    #[tokio::test]
+    async fn test_json_connector_file_paths() {
+        setup_test_env();
+
+        let temp_dir = std::env::temp_dir().join("streaksight_test");
+        let small_path = temp_dir.join("test_flag_columns_small.csv");
+        let large_path = temp_dir.join("test_flag_columns_large.csv");
+        let output_path = temp_dir.join("test_json_connector_file_paths.json");
+        std::fs::write(&small_path, "id,image\n1,\n").unwrap();
+        std::fs::write(&large_path, format!("id,image\n2,{}\n", "A".repeat(2048))).unwrap();
+
+        let connector_path = std::env::current_dir()
+            .unwrap()
+            .join("src/LocalFileJSONConnector.js");
+        let test_js_path = temp_dir.join("test_json_connector_file_paths_plugin.js");
+        std::fs::write(
+            &test_js_path,
+            format!(
+                r#"import {{ discovery }} from "{}";
+                   let error = null;
+                   try {{
+                       await discovery({{ filePath: "", files: "" }});
+                   }} catch (e) {{
+                       error = e.message;
+                   }}
+                   const schema = await streaksight.flagLargeColumns(
+                       {{ columns: [{{ name: "id" }}, {{ name: "image" }}] }},
+                       ["{}", "{}"],
+                       "csv",
+                       "1024"
+                   );
+                   await streaksight.writeFile("{}", JSON.stringify({{ error, schema }}));"#,
+                connector_path.to_string_lossy().replace('\\', "/"),
+                small_path.to_string_lossy().replace('\\', "/"),
+                large_path.to_string_lossy().replace('\\', "/"),
+                output_path.to_string_lossy().replace('\\', "/")
+            ),
+        )
+        .unwrap();
+
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        let module_path = deno_core::ModuleSpecifier::from_file_path(&test_js_path).unwrap();
+        execute_deno_module(&mut runtime, &module_path)
+            .await
+            .unwrap();
+
+        let output: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&output_path).unwrap()).unwrap();
+        assert_eq!(output["error"], "filePath or files is required");
+        assert_eq!(
+            output["schema"]["columns"],
+            serde_json::json!([{"name": "id"}, {"name": "image", "skip": true}])
+        );
+    }
+
+    #[test]
+    fn test_glob_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "logs_2024-01-02.csv",
+            "logs_2024-01-01.csv",
+            "other.csv",
+            "logs.txt",
+        ] {
+            std::fs::write(dir.path().join(name), "id\n1\n").unwrap();
+        }
+        std::fs::create_dir(dir.path().join("logs_dir.csv")).unwrap();
+
+        let dir_str = dir.path().to_str().unwrap();
+        let paths = glob_files(dir_str, "logs_*.csv").unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                dir.path()
+                    .join("logs_2024-01-01.csv")
+                    .to_string_lossy()
+                    .to_string(),
+                dir.path()
+                    .join("logs_2024-01-02.csv")
+                    .to_string_lossy()
+                    .to_string(),
+            ]
+        );
+
+        assert!(glob_files(dir_str, "*.json").unwrap().is_empty());
+        assert!(glob_files(dir_str, "[")
+            .unwrap_err()
+            .starts_with("Invalid glob pattern"));
+        assert!(
+            glob_files(dir.path().join("missing").to_str().unwrap(), "*")
+                .unwrap_err()
+                .starts_with("Failed to read directory")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_csv_connector_sync_file_pattern() {
+        setup_test_env();
+
+        let data_dir = tempfile::tempdir().unwrap();
+        std::fs::write(data_dir.path().join("day_1.csv"), "id,name\n1,Alice\n").unwrap();
+        std::fs::write(
+            data_dir.path().join("day_2.csv"),
+            "id,name\n2,Bob\n3,Carol\n",
+        )
+        .unwrap();
+        std::fs::write(data_dir.path().join("summary.csv"), "id,name\n99,Skip\n").unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch("DROP TABLE IF EXISTS test_csv_pattern")
+            .unwrap();
+        drop(conn);
+
+        let connector_path = std::env::current_dir()
+            .unwrap()
+            .join("src/LocalFileCSVConnector.js");
+        let test_js_path = data_dir.path().join("test_pattern_plugin.js");
+        std::fs::write(
+            &test_js_path,
+            format!(
+                r#"import {{ discovery, sync }} from "{}";
+                   const config = {{ filePath: "{}", filePattern: "day_*.csv" }};
+                   const schema = await discovery(config);
+                   await sync("test_csv_pattern", config, schema);"#,
+                connector_path.to_string_lossy().replace('\\', "/"),
+                data_dir
+                    .path()
+                    .join("day_1.csv")
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            ),
+        )
+        .unwrap();
+
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
+            extensions: vec![streaksight_ext::init()],
+            ..Default::default()
+        });
+        let module_path = deno_core::ModuleSpecifier::from_file_path(&test_js_path).unwrap();
+        execute_deno_module(&mut runtime, &module_path)
+            .await
+            .unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        let ids: Vec<i64> = conn
+            .prepare("SELECT id FROM test_csv_pattern ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_null_to_json() {
+        assert_eq!(null_to_json(NullRepr::JsonNull), serde_json::Value::Null);
+        assert_eq!(null_to_json(NullRepr::EmptyString), serde_json::json!(""));
+        assert_eq!(
+            null_to_json(NullRepr::NullString),
+            serde_json::json!("NULL")
+        );
+    }
+
+    #[test]
+    fn test_null_repr_deserialize() {
+        let modes: Vec<NullRepr> =
+            serde_json::from_str(r#"["json_null", "empty_string", "null_string"]"#).unwrap();
+        assert_eq!(
+            modes,
+            vec![
+                NullRepr::JsonNull,
+                NullRepr::EmptyString,
+                NullRepr::NullString
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_null_representation() {
+        // Note: Keeps the default so that other tests still see JSON nulls
+        set_null_representation(NullRepr::JsonNull).await.unwrap();
+        assert_eq!(
+            duckdb_value_to_json(duckdb::types::ValueRef::Null),
+            serde_json::Value::Null
+        );
+
+        let result = set_null_representation(NullRepr::EmptyString).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Null representation already set".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_strict_mode() {
+        // Note: Keeps the default so that other tests may still use expressions
+        set_strict_mode(false).await.unwrap();
+
+        let graph: query_builder::NodeGraph = serde_json::from_value(serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "select", "data": {"columns": ["upper(name)"]}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        }))
+        .unwrap();
+        assert_eq!(
+            generate_query_sql(&graph, None).unwrap(),
+            "SELECT upper(name) FROM orders"
+        );
+
+        let result = set_strict_mode(true).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Strict mode already set".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply_type_mapping() {
+        let mappings = vec![
+            TypeMapping {
+                contains: "bigint".to_string(),
+                maps_to: "string".to_string(),
+            },
+            TypeMapping {
+                contains: "TIME".to_string(),
+                maps_to: "string".to_string(),
+            },
+        ];
+
+        assert_eq!(apply_type_mapping("BIGINT", &mappings), "string");
+        assert_eq!(apply_type_mapping("TIMESTAMP", &mappings), "string");
+        assert_eq!(apply_type_mapping("INTEGER", &mappings), "number");
+        assert_eq!(apply_type_mapping("DATE", &mappings), "date");
+        assert_eq!(apply_type_mapping("BIGINT", &[]), "number");
+    }
+
+    #[tokio::test]
+    async fn test_set_type_mappings() {
+        setup_test_env();
+
+        let result = set_type_mappings(vec![TypeMapping {
+            contains: "UUID".to_string(),
+            maps_to: "uuid".to_string(),
+        }])
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid type mapping target: uuid".to_string())
+        );
+
+        let result = set_type_mappings(vec![TypeMapping {
+            contains: String::new(),
+            maps_to: "string".to_string(),
+        }])
+        .await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Type mapping pattern must not be empty".to_string())
+        );
+
+        // Note: Only HUGEINT is remapped so that other table_schema tests keep the defaults
+        set_type_mappings(vec![TypeMapping {
+            contains: "HUGEINT".to_string(),
+            maps_to: "string".to_string(),
+        }])
+        .await
+        .unwrap();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_type_mapping (big HUGEINT, small INTEGER)",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = table_schema("test_type_mapping".to_string()).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["columns"],
+            serde_json::json!([
+                {"name": "big", "type": "string"},
+                {"name": "small", "type": "number"}
+            ])
+        );
+
+        let result = set_type_mappings(vec![]).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Type mappings already set".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_node_graph_as_sql() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("query.sql");
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "price", "operator": ">", "value": 10}
+                ]}},
+                {"id": "3", "type": "select", "data": {"columns": ["id", "name"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        })
+        .to_string();
+
+        export_node_graph_as_sql(node_graph, path.to_string_lossy().to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "SELECT\n  id,\n  name\nFROM\n  products\nWHERE\n  price > 10\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_node_graph_as_sql_write_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing").join("query.sql");
+        let node_graph = serde_json::json!({
+            "selected_node_id": "1",
+            "nodes": [{"id": "1", "type": "table", "data": {"table_name": "products"}}],
+            "edges": []
+        })
+        .to_string();
+
+        let result = export_node_graph_as_sql(node_graph, path.to_string_lossy().to_string()).await;
+        assert!(matches!(result, Err(AppError::IoError(_))));
+    }
+
+    #[derive(Default)]
+    struct MockClipboard {
+        text: Option<String>,
+        fail: bool,
+    }
+
+    impl SqlClipboard for MockClipboard {
+        fn set_text(&mut self, text: String) -> Result<(), String> {
+            if self.fail {
+                return Err("clipboard unavailable".to_string());
+            }
+            self.text = Some(text);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_copy_sql_to_clipboard() {
+        let node_graph = serde_json::json!({
+            "selected_node_id": "1",
+            "nodes": [{"id": "1", "type": "table", "data": {"table_name": "products"}}],
+            "edges": []
+        })
+        .to_string();
+
+        let mut clipboard = MockClipboard::default();
+        copy_sql_to_clipboard(&node_graph, &mut clipboard).unwrap();
+        assert_eq!(clipboard.text.as_deref(), Some("SELECT * FROM products"));
+
+        let mut clipboard = MockClipboard {
+            fail: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            copy_sql_to_clipboard(&node_graph, &mut clipboard),
+            Err(AppError::IoError(
+                "Failed to copy SQL to clipboard: clipboard unavailable".to_string()
+            ))
+        );
+
+        let result = copy_sql_to_clipboard("not json", &mut MockClipboard::default());
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_explain_node_graph() {
+        let node_graph = serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "limit", "data": {"limit": 10}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        })
+        .to_string();
+
+        assert_eq!(
+            explain_node_graph(node_graph).await.unwrap(),
+            "Takes the `orders` table and returns the top 10 results."
+        );
+
+        let result = explain_node_graph("not json".to_string()).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_expand_star() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_expand_star (id INTEGER, name VARCHAR, price DOUBLE)",
+        )
+        .unwrap();
+
+        let graph: query_builder::NodeGraph = serde_json::from_value(serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_expand_star"}},
+                {"id": "2", "type": "select", "data": {"columns": []}},
+                {"id": "3", "type": "select", "data": {"columns": []}},
+                {"id": "4", "type": "select", "data": {"columns": ["id"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "1", "target": "4"}
+            ]
+        }))
+        .unwrap();
+
+        let expanded = expand_star(&graph, &conn).unwrap();
+
+        assert_eq!(
+            expanded.nodes[1].data,
+            serde_json::json!({"columns": ["id", "name", "price"]})
+        );
+        // Note: Only the node directly after the table is expanded
+        assert_eq!(expanded.nodes[2].data, serde_json::json!({"columns": []}));
+        assert_eq!(
+            expanded.nodes[3].data,
+            serde_json::json!({"columns": ["id"]})
+        );
+        assert_eq!(
+            query_builder::generate_sql(&expanded, None).unwrap(),
+            "SELECT id, name, price FROM test_expand_star"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expand_star_missing_table() {
+        setup_test_env();
+
+        let conn = duckdb_connect().unwrap();
+        let graph: query_builder::NodeGraph = serde_json::from_value(serde_json::json!({
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "test_expand_star_missing"}},
+                {"id": "2", "type": "select", "data": {"columns": []}}
+            ],
+            "edges": [{"source": "1", "target": "2"}]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            expand_star(&graph, &conn).unwrap_err(),
+            "Table not found: test_expand_star_missing"
+        );
+    }
 
-    let result = serde_json::json!({
-        "columns": columns_info,
-        "rows": rows_data,
-        "row_count": rows_data.len()
-    });
+    #[tokio::test]
+    async fn test_import_sql_as_node_graph_round_trips_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("query.sql");
+        std::fs::write(
+            &path,
+            "SELECT\n  id,\n  name\nFROM\n  products\nWHERE\n  price > 10\n",
+        )
+        .unwrap();
+
+        let result = import_sql_as_node_graph(path.to_string_lossy().to_string())
+            .await
+            .unwrap();
 
-    Ok(result.to_string())
-}
+        let graph: query_builder::NodeGraph = serde_json::from_str(&result).unwrap();
+        assert_eq!(graph.selected_node_id(), "3");
+        assert_eq!(
+            query_builder::generate_sql(&graph, None).unwrap(),
+            "SELECT id, name FROM products WHERE price > 10"
+        );
+    }
 
-#[tauri::command]
-async fn get_query_row_count(node_graph: String) -> Result<i64, String> {
-    let graph: query_builder::NodeGraph = serde_json::from_str(&node_graph)
-        .map_err(|e| format!("Failed to parse node graph: {}", e))?;
+    #[tokio::test]
+    async fn test_import_sql_as_node_graph_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("query.sql");
+
+        let result = import_sql_as_node_graph(path.to_string_lossy().to_string()).await;
+        assert!(matches!(result, Err(AppError::IoError(_))));
+
+        std::fs::write(&path, "WITH t AS (SELECT 1) SELECT * FROM t").unwrap();
+        let result = import_sql_as_node_graph(path.to_string_lossy().to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError(
+                "Unsupported SQL: CTEs (WITH clauses) are not supported".to_string()
+            )
+        );
+    }
 
-    let sql = query_builder::generate_sql(&graph, None)?;
+    #[tokio::test]
+    async fn test_assert_query_result() {
+        setup_test_env();
 
-    let count_sql = format!("SELECT COUNT(*) FROM ({}) AS subquery", sql);
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_assert_users (id INTEGER, email VARCHAR);
+             INSERT INTO test_assert_users VALUES (1, 'a@x'), (2, NULL), (2, 'c@x'), (3, NULL);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let node_graph = serde_json::json!({
+            "selected_node_id": "1",
+            "nodes": [{"id": "1", "type": "table", "data": {"table_name": "test_assert_users"}}],
+            "edges": []
+        })
+        .to_string();
+        let assertions: Vec<Assertion> = serde_json::from_value(serde_json::json!([
+            {"type": "no_nulls", "column": "id"},
+            {"type": "no_nulls", "column": "email"},
+            {"type": "row_count_gte", "value": 4},
+            {"type": "row_count_gte", "value": 10},
+            {"type": "all_unique", "column": "id"},
+            {"type": "all_unique", "column": "email"}
+        ]))
+        .unwrap();
+
+        let result = assert_query_result(node_graph, assertions).await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result["passed"],
+            serde_json::json!(["no_nulls(id)", "row_count_gte(4)", "all_unique(email)"])
+        );
+        assert_eq!(
+            result["failed"],
+            serde_json::json!([
+                "no_nulls(email): found 2 NULL values",
+                "row_count_gte(10): found 4 rows",
+                "all_unique(id): found 1 duplicate values"
+            ])
+        );
+    }
 
-    let conn = duckdb_connect().map_err(|e| e.to_string())?;
+    #[tokio::test]
+    async fn test_assert_query_result_missing_column() {
+        let node_graph = serde_json::json!({
+            "selected_node_id": "1",
+            "nodes": [{"id": "1", "type": "table", "data": {"table_name": "products"}}],
+            "edges": []
+        })
+        .to_string();
+        let assertions: Vec<Assertion> =
+            serde_json::from_value(serde_json::json!([{"type": "all_unique"}])).unwrap();
+
+        let result = assert_query_result(node_graph, assertions).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("all_unique() requires a column".to_string())
+        );
+    }
 
-    let count: i64 = conn
-        .query_row(&count_sql, [], |row| row.get(0))
-        .map_err(|e| format!("Failed to get row count: {}", e))?;
+    #[tokio::test]
+    async fn test_generate_create_table_sql() {
+        setup_test_env();
 
-    Ok(count)
-}
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_ddl (
+                id INTEGER NOT NULL,
+                name VARCHAR DEFAULT 'x',
+                price DECIMAL(9,2) DEFAULT 1.5,
+                tags VARCHAR[],
+                \"weird col\" BIGINT DEFAULT (1 + 2)
+            )",
+        )
+        .unwrap();
+        drop(conn);
+
+        let sql = generate_create_table_sql("test_ddl".to_string())
+            .await
+            .unwrap();
 
-use duckdb::Connection;
-use std::path::PathBuf;
-use std::sync::OnceLock;
-use tauri::{path::BaseDirectory, Manager};
+        assert_eq!(
+            sql,
+            "CREATE TABLE test_ddl (id INTEGER NOT NULL, name VARCHAR DEFAULT 'x', \
+             price DECIMAL(9,2) DEFAULT 1.5, tags VARCHAR[], \"weird col\" BIGINT DEFAULT (1 + 2))"
+        );
 
-static APP_DATA_PATH: OnceLock<PathBuf> = OnceLock::new();
+        let result = generate_create_table_sql("test_ddl_missing".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::DbError("Table not found: test_ddl_missing".to_string())
+        );
+    }
 
-pub fn set_app_data_path(path: PathBuf) {
-    APP_DATA_PATH.set(path).ok();
-}
+    #[test]
+    fn test_build_create_table_sql_unknown_type() {
+        let columns = [TableColumn {
+            name: "payload".to_string(),
+            data_type: "NOT A TYPE(".to_string(),
+            nullable: false,
+            default: None,
+        }];
+
+        assert_eq!(
+            build_create_table_sql("events", &columns).unwrap(),
+            "CREATE TABLE events (payload NOT A TYPE( NOT NULL)"
+        );
+    }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .setup(|app| {
-            let app_data_path = app.path().resolve("data", BaseDirectory::AppData)?;
-            std::fs::create_dir_all(&app_data_path)?;
-            set_app_data_path(app_data_path);
-            Ok(())
-        })
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_fs::init())
-        .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![
-            config,
-            discovery,
-            sync,
-            tables,
-            table_schema,
-            run_query,
-            get_query_row_count,
-            drop_table
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
+    #[tokio::test]
+    async fn test_get_table_checksum() {
+        setup_test_env();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use deno_core::{error::AnyError, JsRuntime, RuntimeOptions};
-    use std::rc::Rc;
-    use std::sync::Once;
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_checksum_a (id INTEGER, name VARCHAR);
+             INSERT INTO test_checksum_a VALUES (1, 'x'), (2, 'y'), (3, NULL);
+             CREATE OR REPLACE TABLE test_checksum_b (id INTEGER, name VARCHAR);
+             INSERT INTO test_checksum_b VALUES (3, NULL), (2, 'y'), (1, 'x');
+             CREATE OR REPLACE TABLE test_checksum_c (id INTEGER, name VARCHAR);
+             INSERT INTO test_checksum_c VALUES (1, 'x'), (2, 'z'), (3, NULL);
+             CREATE OR REPLACE TABLE test_checksum_empty (id INTEGER);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let a = get_table_checksum("test_checksum_a".to_string())
+            .await
+            .unwrap();
+        let b = get_table_checksum("test_checksum_b".to_string())
+            .await
+            .unwrap();
+        let c = get_table_checksum("test_checksum_c".to_string())
+            .await
+            .unwrap();
 
-    static INIT: Once = Once::new();
+        assert_eq!(a.len(), 32);
+        assert!(a.chars().all(|ch| ch.is_ascii_hexdigit()));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        // Note: An empty table hashes the empty string
+        assert_eq!(
+            get_table_checksum("test_checksum_empty".to_string())
+                .await
+                .unwrap(),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+    }
 
-    fn setup_test_env() {
-        INIT.call_once(|| {
-            let temp_dir = std::env::temp_dir().join("streaksight_test");
-            let _ = std::fs::remove_file(temp_dir.join("database.duckdb"));
-            let _ = std::fs::remove_file(temp_dir.join("database.duckdb.wal"));
-            std::fs::create_dir_all(&temp_dir).unwrap();
-            set_app_data_path(temp_dir);
-        });
+    #[tokio::test]
+    async fn test_get_table_checksum_invalid_name() {
+        let result = get_table_checksum("bad name; --".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid table name".to_string())
+        );
     }
 
-    async fn run_plugin_test(test_csv_path: &str) -> Result<(), AnyError> {
-        let current_dir = std::env::current_dir()?;
-        let connector_path = current_dir.join("src/LocalFileCSVConnector.js");
+    #[tokio::test]
+    async fn test_schema_diff() {
+        setup_test_env();
 
-        let test_js = format!(
-            r#"import {{ discovery, sync }} from "{}";
-               export async function main() {{
-                   const config = {{
-                       filePath: "{}",
-                       delimiter: ",",
-                       hasHeader: true
-                   }};
-                   const schema = await discovery(config);
-                   console.log("Schema:", JSON.stringify(schema));
-                   await sync("test_csv_table", config, schema);
-                   console.log("Sync completed");
-               }}"#,
-            connector_path.to_str().unwrap().replace("\\", "/"),
-            test_csv_path
+        let conn = duckdb_connect().unwrap();
+        conn.execute_batch(
+            "CREATE OR REPLACE TABLE test_diff_a (id INTEGER, name VARCHAR, score INTEGER, legacy VARCHAR);
+             CREATE OR REPLACE TABLE test_diff_b (id INTEGER, name VARCHAR, score DOUBLE, email VARCHAR);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = schema_diff("test_diff_a".to_string(), "test_diff_b".to_string())
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "added": [{"name": "email", "type": "VARCHAR"}],
+                "removed": [{"name": "legacy", "type": "VARCHAR"}],
+                "changed": [{"name": "score", "from": "INTEGER", "to": "DOUBLE"}]
+            })
         );
 
-        let test_js_path = std::env::temp_dir()
-            .join("streaksight_test")
-            .join("test_plugin.js");
-        std::fs::write(&test_js_path, test_js)?;
+        let result = schema_diff("test_diff_a".to_string(), "test_diff_a".to_string())
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({ "added": [], "removed": [], "changed": [] })
+        );
+    }
 
-        let plugin_module =
-            deno_core::resolve_path(test_js_path.to_str().unwrap(), &std::env::current_dir()?)?;
+    #[tokio::test]
+    async fn test_schema_diff_invalid_args() {
+        let result = schema_diff("test_diff_a".to_string(), "b; DROP TABLE a".to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Invalid table name".to_string())
+        );
 
-        let mut rt = JsRuntime::new(RuntimeOptions {
+        setup_test_env();
+        let result = schema_diff(
+            "test_diff_missing_a".to_string(),
+            "test_diff_missing_b".to_string(),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::DbError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_load_runtime_js_error_contains_path() {
+        let mut runtime = JsRuntime::new(RuntimeOptions {
             module_loader: Some(Rc::new(deno_core::FsModuleLoader)),
             extensions: vec![streaksight_ext::init()],
             ..Default::default()
         });
 
-        let id = rt.load_main_es_module(&plugin_module).await?;
-        let eval = rt.mod_evaluate(id);
-        rt.run_event_loop(Default::default()).await?;
-        eval.await?;
+        let missing_dir = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("missing")
+            .join("src-tauri");
+        let expected_path = missing_dir.join("src/runtime.js");
 
-        let script = format!(
-            r#"import("{}").then(m => m.main())"#,
-            plugin_module.as_str()
-        );
-        rt.execute_script("<call_main>", script)?;
-        rt.run_event_loop(Default::default()).await?;
+        let err = load_runtime_js(&mut runtime, &missing_dir)
+            .await
+            .unwrap_err();
 
-        Ok(())
+        assert!(err.contains(&expected_path.display().to_string()));
     }
 
     #[tokio::test]
-    async fn test_csv_connector_sync() {
-        setup_test_env();
+    async fn test_set_temp_dir() {
+        let dir = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("connector_tmp");
 
-        // Create test CSV file
-        let test_csv = r#"id,name,active,score
-1,Alice,true,95.5
-2,Bob,false,87.3
-3,Charlie,true,92.1"#;
+        set_temp_dir(dir.to_string_lossy().to_string())
+            .await
+            .unwrap();
+        assert!(dir.is_dir());
+        assert_eq!(temp_dir(), dir);
 
-        let temp_dir = std::env::temp_dir().join("streaksight_test");
-        let csv_path = temp_dir.join("test_sync.csv");
-        std::fs::write(&csv_path, test_csv).unwrap();
+        let result = set_temp_dir(std::env::temp_dir().to_string_lossy().to_string()).await;
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::ValidationError("Temp directory already set".to_string())
+        );
+        assert_eq!(temp_dir(), dir);
+    }
 
-        run_plugin_test(csv_path.to_str().unwrap()).await.unwrap();
+    #[test]
+    fn test_retry_on_lock_succeeds_after_retries() {
+        let mut attempts = 0;
+        let result: Result<&str, String> = retry_on_lock(|| {
+            attempts += 1;
+            if attempts < 3 {
+                Err("IO Error: Could not set lock on file".to_string())
+            } else {
+                Ok("connected")
+            }
+        });
 
-        let conn = duckdb_connect().unwrap();
-        let mut stmt = conn
-            .prepare("SELECT id, name, active, score FROM test_csv_table ORDER BY id")
-            .unwrap();
-        let mut rows = stmt.query([]).unwrap();
+        assert_eq!(result.unwrap(), "connected");
+        assert_eq!(attempts, 3);
+    }
 
-        let row = rows.next().unwrap().unwrap();
-        let id: f64 = row.get(0).unwrap();
-        let name: String = row.get(1).unwrap();
-        let active: bool = row.get(2).unwrap();
-        let score: f64 = row.get(3).unwrap();
-        assert_eq!(id, 1.0);
-        assert_eq!(name, "Alice");
-        assert_eq!(active, true);
-        assert_eq!(score, 95.5);
+    #[test]
+    fn test_retry_on_lock_gives_up() {
+        let mut attempts = 0;
+        let result: Result<(), String> = retry_on_lock(|| {
+            attempts += 1;
+            Err("IO Error: Could not set lock on file: Conflicting lock is held".to_string())
+        });
 
-        let row = rows.next().unwrap().unwrap();
-        let id: f64 = row.get(0).unwrap();
-        let name: String = row.get(1).unwrap();
-        let active: bool = row.get(2).unwrap();
-        let score: f64 = row.get(3).unwrap();
-        assert_eq!(id, 2.0);
-        assert_eq!(name, "Bob");
-        assert_eq!(active, false);
-        assert_eq!(score, 87.3);
+        assert!(result.is_err());
+        assert_eq!(attempts, LOCK_RETRY_LIMIT + 1);
+    }
 
-        let row = rows.next().unwrap().unwrap();
-        let id: f64 = row.get(0).unwrap();
-        let name: String = row.get(1).unwrap();
-        let active: bool = row.get(2).unwrap();
-        let score: f64 = row.get(3).unwrap();
-        assert_eq!(id, 3.0);
-        assert_eq!(name, "Charlie");
-        assert_eq!(active, true);
-        assert_eq!(score, 92.1);
+    #[test]
+    fn test_retry_on_lock_retries_sharing_violation() {
+        let mut attempts = 0;
+        let result: Result<&str, String> = retry_on_lock(|| {
+            attempts += 1;
+            if attempts < 2 {
+                Err("IO Error: Cannot open file \"database.duckdb\": The process cannot access the file because it is being used by another process.".to_string())
+            } else {
+                Ok("connected")
+            }
+        });
 
-        assert!(rows.next().unwrap().is_none());
+        assert_eq!(result.unwrap(), "connected");
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_retry_on_lock_ignores_other_errors() {
+        for message in [
+            "Catalog Error: Table does not exist",
+            "IO Error: Cannot open file \"database.duckdb\": Permission denied",
+        ] {
+            let mut attempts = 0;
+            let result: Result<(), String> = retry_on_lock(|| {
+                attempts += 1;
+                Err(message.to_string())
+            });
+
+            assert!(result.is_err());
+            assert_eq!(attempts, 1);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_retry_on_lock_on_multi_thread_runtime() {
+        let mut attempts = 0;
+        let result: Result<&str, String> = retry_on_lock(|| {
+            attempts += 1;
+            if attempts < 2 {
+                Err("IO Error: Could not set lock on file".to_string())
+            } else {
+                Ok("connected")
+            }
+        });
+
+        assert_eq!(result.unwrap(), "connected");
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("price"), "\"price\"");
+        assert_eq!(quote_identifier("Order \"Date\""), "\"Order \"\"Date\"\"\"");
     }
 
     #[test]
@@ -739,6 +10560,80 @@ mod tests {
         assert!(path.to_str().unwrap().contains("LocalFileJSONConnector.js"));
     }
 
+    #[tokio::test]
+    async fn test_user_connectors() {
+        setup_test_env();
+
+        let connectors_dir = std::env::temp_dir()
+            .join("streaksight_test")
+            .join("connectors");
+        std::fs::create_dir_all(&connectors_dir).unwrap();
+        let user_path = connectors_dir.join("TestUserSourceConnector.js");
+        std::fs::write(&user_path, "").unwrap();
+
+        assert_eq!(resolve_connector_path("TestUserSource").unwrap(), user_path);
+        assert!(validate_connector_type("TestUserSource").is_ok());
+
+        let result = list_connector_types().await.unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let ids: Vec<&str> = result["connector_types"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            &ids[..2],
+            [
+                connector_type::LOCAL_FILE_CSV,
+                connector_type::LOCAL_FILE_JSON
+            ]
+        );
+        assert!(result["connector_types"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!({
+                "id": "TestUserSource",
+                "path": user_path.to_string_lossy(),
+                "label": "Test User Source",
+                "icon": null,
+                "version": null,
+                "permissions": {
+                    "http": false,
+                    "file_read": false,
+                    "file_write": false,
+                    "system_info": false
+                }
+            })));
+
+        let manifest = describe_connector(connector_type::LOCAL_FILE_CSV.to_string())
+            .await
+            .unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+        assert_eq!(manifest["label"], "Local CSV/TSV File");
+        assert_eq!(
+            manifest["permissions"],
+            serde_json::json!({
+                "http": false,
+                "file_read": true,
+                "file_write": true,
+                "system_info": false
+            })
+        );
+
+        std::fs::remove_file(&user_path).unwrap();
+        assert_eq!(
+            validate_connector_type("TestUserSource").unwrap_err(),
+            AppError::ValidationError("Unknown connector type".to_string())
+        );
+        assert_eq!(
+            describe_connector("TestUserSource".to_string())
+                .await
+                .unwrap_err(),
+            AppError::ValidationError("Unknown connector type".to_string())
+        );
+    }
+
     #[test]
     fn test_resolve_connector_path_unknown() {
         let result = resolve_connector_path("UnknownType");
@@ -776,4 +10671,52 @@ mod tests {
         let value = duckdb_value_to_json(duckdb::types::ValueRef::Date32(0));
         assert_eq!(value, serde_json::Value::String("1970-01-01".to_string()));
     }
+
+    #[test]
+    fn test_duckdb_value_to_json_timestamp() {
+        use duckdb::types::{TimeUnit, ValueRef};
+
+        let cases = [
+            (
+                ValueRef::Timestamp(TimeUnit::Microsecond, 0),
+                "1970-01-01T00:00:00Z",
+            ),
+            (
+                ValueRef::Timestamp(TimeUnit::Microsecond, 1_704_103_200_000_000),
+                "2024-01-01T10:00:00Z",
+            ),
+            (
+                ValueRef::Timestamp(TimeUnit::Microsecond, 1_704_103_200_123_456),
+                "2024-01-01T10:00:00.123456Z",
+            ),
+            (
+                ValueRef::Timestamp(TimeUnit::Millisecond, 1_704_103_200_500),
+                "2024-01-01T10:00:00.500Z",
+            ),
+            (
+                ValueRef::Timestamp(TimeUnit::Second, -86_400),
+                "1969-12-31T00:00:00Z",
+            ),
+            (
+                ValueRef::Timestamp(TimeUnit::Nanosecond, 1_000_000_000),
+                "1970-01-01T00:00:01Z",
+            ),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(duckdb_value_to_json(value), serde_json::json!(expected));
+        }
+    }
+
+    #[test]
+    fn test_duckdb_value_to_json_timestamptz() {
+        let conn = Connection::open_in_memory().unwrap();
+        let value = conn
+            .query_row("SELECT TIMESTAMPTZ '2024-01-01 12:00:00+02'", [], |row| {
+                Ok(duckdb_value_to_json(row.get_ref(0)?))
+            })
+            .unwrap();
+
+        assert_eq!(value, serde_json::json!("2024-01-01T10:00:00Z"));
+    }
 }
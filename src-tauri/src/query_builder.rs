@@ -1,22 +1,57 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlparser::ast::{
-    BinaryOperator, Expr, Function, FunctionArg, FunctionArgExpr, FunctionArgumentList,
-    FunctionArguments, GroupByExpr, Ident, LimitClause, ObjectName, OrderBy, OrderByExpr,
-    OrderByKind, OrderByOptions, SelectItem, SetExpr, Statement, UnaryOperator, Value,
-    ValueWithSpan,
+    BinaryOperator, CastKind, DataType, Expr, Function, FunctionArg, FunctionArgExpr,
+    FunctionArgumentList, FunctionArguments, GroupByExpr, Ident, LimitClause, ObjectName, OrderBy,
+    OrderByExpr, OrderByKind, OrderByOptions, Query, SelectItem, SetExpr, Statement, TableFactor,
+    Top, TopQuantity, UnaryOperator, Value, ValueWithSpan, WindowSpec, WindowType,
 };
 use sqlparser::dialect::DuckDbDialect;
 use sqlparser::parser::Parser;
 use sqlparser::tokenizer::Span;
+use std::sync::OnceLock;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NodeGraph {
-    pub selected_node_id: String,
+    // Note: Graphs saved before multi-selection carry a single selected_node_id
+    #[serde(
+        alias = "selected_node_id",
+        deserialize_with = "deserialize_selected_node_ids"
+    )]
+    pub selected_node_ids: Vec<String>,
     pub nodes: Vec<Node>,
     pub edges: Vec<Edge>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl NodeGraph {
+    // Note: Operations that produce a single result use the first selected node
+    pub fn selected_node_id(&self) -> &str {
+        self.selected_node_ids.first().map_or("", String::as_str)
+    }
+}
+
+fn deserialize_selected_node_ids<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let ids = match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(id) => vec![id],
+        OneOrMany::Many(ids) => ids,
+    };
+    if ids.is_empty() {
+        return Err(serde::de::Error::custom(
+            "at least one node must be selected",
+        ));
+    }
+    Ok(ids)
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Node {
     pub id: String,
     #[serde(rename = "type")]
@@ -24,7 +59,7 @@ pub struct Node {
     pub data: serde_json::Value,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Edge {
     pub source: String,
     pub target: String,
@@ -33,8 +68,27 @@ pub struct Edge {
 #[derive(Debug, Deserialize)]
 struct TableNodeData {
     table_name: String,
+    #[serde(default)]
+    schema: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TableFunctionNodeData {
+    function_name: String,
+    #[serde(default)]
+    args: Vec<serde_json::Value>,
 }
 
+const ALLOWED_TABLE_FUNCTIONS: &[&str] = &[
+    "read_csv",
+    "read_csv_auto",
+    "read_parquet",
+    "read_json",
+    "read_json_auto",
+    "range",
+    "generate_series",
+];
+
 #[derive(Debug, Deserialize)]
 struct SelectNodeData {
     #[serde(default)]
@@ -45,6 +99,9 @@ struct SelectNodeData {
 struct SortNodeData {
     #[serde(default)]
     order: Vec<OrderByData>,
+    // Note: Only used when no order is given; shuffles the rows instead
+    #[serde(default)]
+    random_seed: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -53,6 +110,54 @@ struct LimitNodeData {
     limit: Option<i64>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JoinType {
+    Natural,
+    Anti,
+}
+
+#[derive(Debug, Deserialize)]
+struct JoinNodeData {
+    table_name: String,
+    #[serde(default)]
+    schema: Option<String>,
+    join_type: JoinType,
+    // Note: Only anti joins take key columns; a natural join matches every column of the same name
+    #[serde(default)]
+    keys: Vec<String>,
+}
+
+impl JoinNodeData {
+    fn qualified_name(&self) -> String {
+        match &self.schema {
+            Some(schema) => format!("{}.{}", schema, self.table_name),
+            None => self.table_name.clone(),
+        }
+    }
+
+    fn clause(&self, table: &str) -> Result<String, String> {
+        match self.join_type {
+            JoinType::Natural if self.keys.is_empty() => Ok(format!("NATURAL JOIN {}", table)),
+            JoinType::Natural => Err("Natural join does not take key columns".to_string()),
+            JoinType::Anti if self.keys.is_empty() => {
+                Err("Anti join requires at least one key column".to_string())
+            }
+            JoinType::Anti => Ok(format!(
+                "ANTI JOIN {} USING ({})",
+                table,
+                self.keys.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DropDuplicatesNodeData {
+    #[serde(default)]
+    columns: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 enum OrderDirection {
@@ -97,6 +202,8 @@ struct FilterCondition {
     value: serde_json::Value,
     #[serde(default)]
     negate: bool,
+    #[serde(skip)]
+    quoted: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -130,142 +237,344 @@ struct Metric {
     column: String,
 }
 
-pub fn generate_sql(
-    node_graph: &NodeGraph,
-    pagination: Option<(i64, i64)>,
-) -> Result<String, String> {
-    let path = build_path(node_graph)?;
-
-    let mut table_name = String::new();
-    let mut columns = Vec::<String>::new();
-    let mut order_by_list = Vec::<OrderByData>::new();
-    let mut limit_value: Option<i64> = None;
-    let mut filter_conditions = Vec::<FilterCondition>::new();
-    let mut aggregation_data: Option<AggregationNodeData> = None;
-    let mut has_select_before_aggregation = false;
-
-    for node in &path {
-        match node.node_type.as_str() {
-            "table" => {
-                let table_data: TableNodeData = serde_json::from_value(node.data.clone())
-                    .map_err(|e| format!("Failed to parse table node data: {}", e))?;
-                table_name = table_data.table_name;
-            }
-            "select" => {
-                let select_data: SelectNodeData = serde_json::from_value(node.data.clone())
-                    .map_err(|e| format!("Failed to parse select node data: {}", e))?;
-                columns = select_data.columns;
-                if aggregation_data.is_none() {
-                    has_select_before_aggregation = true;
-                }
-            }
-            "sort" => {
-                let sort_data: SortNodeData = serde_json::from_value(node.data.clone())
-                    .map_err(|e| format!("Failed to parse sort node data: {}", e))?;
-                order_by_list = sort_data.order;
-            }
-            "limit" => {
-                let limit_data: LimitNodeData = serde_json::from_value(node.data.clone())
-                    .map_err(|e| format!("Failed to parse limit node data: {}", e))?;
-                limit_value = limit_data.limit;
+// Note: Node types the query builder does not know (e.g. charts) may carry any data
+const NODE_GRAPH_SCHEMA: &str = r##"{
+    "$schema": "https://json-schema.org/draft/2020-12/schema",
+    "type": "object",
+    "required": ["nodes", "edges"],
+    "oneOf": [
+        { "required": ["selected_node_id"] },
+        { "required": ["selected_node_ids"] }
+    ],
+    "additionalProperties": false,
+    "properties": {
+        "selected_node_id": { "type": "string" },
+        "selected_node_ids": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+        "nodes": { "type": "array", "items": { "$ref": "#/$defs/node" } },
+        "edges": { "type": "array", "items": { "$ref": "#/$defs/edge" } }
+    },
+    "$defs": {
+        "edge": {
+            "type": "object",
+            "required": ["source", "target"],
+            "additionalProperties": false,
+            "properties": {
+                "source": { "type": "string" },
+                "target": { "type": "string" }
             }
-            "filter" => {
-                let filter_data: FilterNodeData = serde_json::from_value(node.data.clone())
-                    .map_err(|e| format!("Failed to parse filter node data: {}", e))?;
-                filter_conditions.extend(filter_data.conditions);
+        },
+        "node": {
+            "type": "object",
+            "required": ["id", "type", "data"],
+            "additionalProperties": false,
+            "properties": {
+                "id": { "type": "string" },
+                "type": { "type": "string" },
+                "data": { "type": "object" }
+            },
+            "allOf": [
+                { "$ref": "#/$defs/typed_data/table" },
+                { "$ref": "#/$defs/typed_data/table_function" },
+                { "$ref": "#/$defs/typed_data/select" },
+                { "$ref": "#/$defs/typed_data/sort" },
+                { "$ref": "#/$defs/typed_data/limit" },
+                { "$ref": "#/$defs/typed_data/filter" },
+                { "$ref": "#/$defs/typed_data/aggregation" },
+                { "$ref": "#/$defs/typed_data/drop_duplicates" },
+                { "$ref": "#/$defs/typed_data/join" }
+            ]
+        },
+        "typed_data": {
+            "table": {
+                "if": { "properties": { "type": { "const": "table" } } },
+                "then": { "properties": { "data": {
+                    "required": ["table_name"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "table_name": { "type": "string" },
+                        "schema": { "type": ["string", "null"] }
+                    }
+                } } }
+            },
+            "table_function": {
+                "if": { "properties": { "type": { "const": "table_function" } } },
+                "then": { "properties": { "data": {
+                    "required": ["function_name"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "function_name": { "type": "string" },
+                        "args": { "type": "array", "items": { "$ref": "#/$defs/scalar" } }
+                    }
+                } } }
+            },
+            "select": {
+                "if": { "properties": { "type": { "const": "select" } } },
+                "then": { "properties": { "data": {
+                    "additionalProperties": false,
+                    "properties": {
+                        "columns": { "type": "array", "items": { "type": "string" } }
+                    }
+                } } }
+            },
+            "sort": {
+                "if": { "properties": { "type": { "const": "sort" } } },
+                "then": { "properties": { "data": {
+                    "additionalProperties": false,
+                    "properties": {
+                        "order": { "type": "array", "items": {
+                            "type": "object",
+                            "required": ["column", "direction"],
+                            "additionalProperties": false,
+                            "properties": {
+                                "column": { "type": "string" },
+                                "direction": { "enum": ["asc", "desc"] }
+                            }
+                        } },
+                        "random_seed": { "type": "integer", "minimum": 0 }
+                    }
+                } } }
+            },
+            "limit": {
+                "if": { "properties": { "type": { "const": "limit" } } },
+                "then": { "properties": { "data": {
+                    "additionalProperties": false,
+                    "properties": {
+                        "limit": { "type": ["integer", "null"] }
+                    }
+                } } }
+            },
+            "filter": {
+                "if": { "properties": { "type": { "const": "filter" } } },
+                "then": { "properties": { "data": {
+                    "additionalProperties": false,
+                    "properties": {
+                        "conditions": { "type": "array", "items": {
+                            "type": "object",
+                            "required": ["column", "operator", "value"],
+                            "additionalProperties": false,
+                            "properties": {
+                                "column": { "type": "string" },
+                                "operator": { "enum": ["==", "!=", ">", "<", ">=", "<=", "in"] },
+                                "value": { "anyOf": [
+                                    { "$ref": "#/$defs/scalar" },
+                                    { "type": "object" },
+                                    { "type": "array", "items": { "anyOf": [
+                                        { "$ref": "#/$defs/scalar" },
+                                        { "type": "object" }
+                                    ] } }
+                                ] },
+                                "negate": { "type": "boolean" }
+                            }
+                        } }
+                    }
+                } } }
+            },
+            "aggregation": {
+                "if": { "properties": { "type": { "const": "aggregation" } } },
+                "then": { "properties": { "data": {
+                    "additionalProperties": false,
+                    "properties": {
+                        "dimensions": { "type": "array", "items": { "type": "string" } },
+                        "metrics": { "type": "array", "items": {
+                            "type": "object",
+                            "required": ["function"],
+                            "additionalProperties": false,
+                            "properties": {
+                                "function": {
+                                    "enum": ["COUNT(*)", "COUNT", "SUM", "AVG", "MAX", "MIN"]
+                                },
+                                "column": { "type": "string" }
+                            }
+                        } }
+                    }
+                } } }
+            },
+            "drop_duplicates": {
+                "if": { "properties": { "type": { "const": "drop_duplicates" } } },
+                "then": { "properties": { "data": {
+                    "additionalProperties": false,
+                    "properties": {
+                        "columns": { "type": "array", "items": { "type": "string" } }
+                    }
+                } } }
+            },
+            "join": {
+                "if": { "properties": { "type": { "const": "join" } } },
+                "then": { "properties": { "data": {
+                    "required": ["table_name", "join_type"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "table_name": { "type": "string" },
+                        "schema": { "type": ["string", "null"] },
+                        "join_type": { "enum": ["natural", "anti"] },
+                        "keys": { "type": "array", "items": { "type": "string" } }
+                    }
+                } } }
             }
-            "aggregation" => {
-                let agg_data: AggregationNodeData = serde_json::from_value(node.data.clone())
-                    .map_err(|e| format!("Failed to parse aggregation node data: {}", e))?;
+        },
+        "scalar": { "type": ["string", "number", "boolean"] }
+    }
+}"##;
 
-                if has_select_before_aggregation {
-                    return Err("Cannot use Aggregation after Select node. Please remove the Select node or reorder the nodes.".to_string());
-                }
+fn node_graph_validator() -> &'static jsonschema::Validator {
+    static VALIDATOR: OnceLock<jsonschema::Validator> = OnceLock::new();
+    VALIDATOR.get_or_init(|| {
+        let schema =
+            serde_json::from_str(NODE_GRAPH_SCHEMA).expect("node graph schema is valid JSON");
+        jsonschema::validator_for(&schema).expect("node graph schema is a valid JSON Schema")
+    })
+}
 
-                aggregation_data = Some(agg_data);
-            }
-            _ => {
-                return Err(format!("Unsupported node type: {}", node.node_type));
-            }
-        }
-    }
+pub fn validate_node_graph_schema(json: &str) -> Result<(), Vec<String>> {
+    let instance: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| vec![format!("Failed to parse node graph: {}", e)])?;
 
-    if table_name.is_empty() {
-        return Err("No table node found in path".to_string());
-    }
+    validate_node_graph_value(&instance)
+}
 
-    let dialect = DuckDbDialect {};
-    let base_sql = format!("SELECT * FROM {}", table_name);
-    let mut ast = Parser::parse_sql(&dialect, &base_sql)
-        .map_err(|e| format!("Failed to parse base SQL: {}", e))?;
+fn validate_node_graph_value(instance: &serde_json::Value) -> Result<(), Vec<String>> {
+    let errors: Vec<String> = node_graph_validator()
+        .iter_errors(instance)
+        .map(|e| format!("{} at '{}'", e, e.instance_path()))
+        .collect();
 
-    if ast.is_empty() {
-        return Err("Failed to generate base AST".to_string());
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
     }
+}
 
-    if let Statement::Query(ref mut query) = ast[0] {
-        if let SetExpr::Select(ref mut select) = *query.body {
-            if let Some(agg) = &aggregation_data {
-                if !agg.dimensions.is_empty() || !agg.metrics.is_empty() {
-                    select.projection = build_aggregation_projection(agg)?;
-                }
-            } else if !columns.is_empty() {
-                select.projection = columns
-                    .iter()
-                    .map(|col| SelectItem::UnnamedExpr(Expr::Identifier(Ident::new(col))))
-                    .collect();
-            }
+// Note: The schema is checked on the raw JSON, since deserializing drops the unknown fields it rejects
+pub fn parse_node_graph(json: &str) -> Result<NodeGraph, String> {
+    validate_node_graph_schema(json)
+        .map_err(|errors| format!("Invalid node graph: {}", errors.join("; ")))?;
+    serde_json::from_str(json).map_err(|e| format!("Failed to parse node graph: {}", e))
+}
 
-            if !filter_conditions.is_empty() {
-                if let Ok(where_expr) = build_where_expr(&filter_conditions) {
-                    select.selection = Some(where_expr);
-                }
-            }
+pub fn node_graph_from_value(instance: serde_json::Value) -> Result<NodeGraph, String> {
+    validate_node_graph_value(&instance)
+        .map_err(|errors| format!("Invalid node graph: {}", errors.join("; ")))?;
+    serde_json::from_value(instance).map_err(|e| format!("Failed to parse node graph: {}", e))
+}
 
-            if let Some(agg) = &aggregation_data {
-                if !agg.dimensions.is_empty() {
-                    select.group_by = GroupByExpr::Expressions(
-                        agg.dimensions
-                            .iter()
-                            .map(|dim| Expr::Identifier(Ident::new(dim)))
-                            .collect(),
-                        vec![],
-                    );
-                }
-            }
-        }
+#[derive(Debug, Clone, Copy)]
+pub struct QueryOptions {
+    // Note: When false, the query runs through to the end of the selected node's downstream chain
+    pub upstream_only: bool,
+    // Note: When true, every column and table name must be a plain identifier rather than an expression
+    pub strict: bool,
+    // Note: When true, pagination reads from a `paginated` CTE instead of wrapping the query in a subquery
+    pub use_cte_for_pagination: bool,
+}
 
-        if !order_by_list.is_empty() {
-            let order_by_exprs: Vec<OrderByExpr> = order_by_list
-                .iter()
-                .map(|o| OrderByExpr {
-                    expr: Expr::Identifier(Ident::new(&o.column)),
-                    options: OrderByOptions {
-                        asc: Some(matches!(o.direction, OrderDirection::Asc)),
-                        nulls_first: None,
-                    },
-                    with_fill: None,
-                })
-                .collect();
-            query.order_by = Some(OrderBy {
-                kind: OrderByKind::Expressions(order_by_exprs),
-                interpolate: None,
-            });
+impl Default for QueryOptions {
+    fn default() -> Self {
+        Self {
+            upstream_only: true,
+            strict: false,
+            use_cte_for_pagination: false,
         }
+    }
+}
 
-        if let Some(limit) = limit_value {
-            query.limit_clause = Some(LimitClause::LimitOffset {
-                limit: Some(Expr::Value(ValueWithSpan {
-                    value: Value::Number(limit.to_string(), false),
-                    span: Span::empty(),
-                })),
-                offset: None,
-                limit_by: Vec::new(),
-            });
+#[derive(Debug, Clone, Deserialize)]
+pub struct CursorPagination {
+    pub order_column: String,
+    // Note: None fetches the first page
+    #[serde(default)]
+    pub cursor_value: Option<serde_json::Value>,
+    pub page_size: usize,
+}
+
+const QUERY_NODE_TYPES: &[&str] = &[
+    "table",
+    "table_function",
+    "select",
+    "sort",
+    "limit",
+    "filter",
+    "aggregation",
+    "drop_duplicates",
+    "join",
+];
+
+// Note: The app goes through generate_sql_with_options to honour strict mode; this stays for the fuzz target
+#[allow(dead_code)]
+pub fn generate_sql(
+    node_graph: &NodeGraph,
+    pagination: Option<(i64, i64)>,
+) -> Result<String, String> {
+    generate_sql_with_options(node_graph, pagination, QueryOptions::default())
+}
+
+pub fn generate_sql_with_options(
+    node_graph: &NodeGraph,
+    pagination: Option<(i64, i64)>,
+    options: QueryOptions,
+) -> Result<String, String> {
+    let end_node_id = if options.upstream_only {
+        node_graph.selected_node_id().to_string()
+    } else {
+        find_downstream_end(node_graph)?
+    };
+    let path = build_path(node_graph, &end_node_id)?;
+    check_node_order(&path)?;
+
+    // Note: Each node shared with another query branch becomes a CTE the rest of the path reads from;
+    // a CTE reads like a subquery, so it changes where the SQL goes but not which rows come back
+    let aggregated_before = |end: usize| path[..end].iter().any(|n| n.node_type == "aggregation");
+    let mut ctes = Vec::<(String, String)>::new();
+    let mut segment_start = 0;
+    for (i, node) in path.iter().enumerate().take(path.len() - 1) {
+        if query_successors(node_graph, &node.id).count() > 1
+            && !cte_cut_reorders(&path[segment_start..], i - segment_start)
+        {
+            let name = format!("cte_{}", ctes.len() + 1);
+            let source = ctes.last().map(|(name, _)| name.as_str());
+            let cte_sql = build_select_sql(
+                &path[segment_start..=i],
+                source,
+                options.strict,
+                aggregated_before(segment_start),
+            )?;
+            ctes.push((name, cte_sql));
+            segment_start = i + 1;
         }
     }
 
-    let inner_sql = ast[0].to_string();
+    let source = ctes.last().map(|(name, _)| name.as_str());
+    let select_sql = build_select_sql(
+        &path[segment_start..],
+        source,
+        options.strict,
+        aggregated_before(segment_start),
+    )?;
+
+    if let (Some((limit, offset)), true) = (pagination, options.use_cte_for_pagination) {
+        ctes.push((PAGINATION_CTE.to_string(), select_sql));
+        let ctes: Vec<String> = ctes
+            .iter()
+            .map(|(name, sql)| format!("{} AS ({})", name, sql))
+            .collect();
+        return Ok(format!(
+            "WITH {} SELECT * FROM {} LIMIT {} OFFSET {}",
+            ctes.join(", "),
+            PAGINATION_CTE,
+            limit,
+            offset
+        ));
+    }
+
+    let inner_sql = if ctes.is_empty() {
+        select_sql
+    } else {
+        let ctes: Vec<String> = ctes
+            .iter()
+            .map(|(name, sql)| format!("{} AS ({})", name, sql))
+            .collect();
+        format!("WITH {} {}", ctes.join(", "), select_sql)
+    };
 
     if let Some((limit, offset)) = pagination {
         Ok(format!(
@@ -277,202 +586,3252 @@ pub fn generate_sql(
     }
 }
 
-fn build_path(node_graph: &NodeGraph) -> Result<Vec<&Node>, String> {
-    let mut path = Vec::new();
-    let mut current_id = node_graph.selected_node_id.clone();
+const PAGINATION_CTE: &str = "paginated";
 
-    loop {
-        let current_node = node_graph
-            .nodes
-            .iter()
-            .find(|n| n.id == current_id)
-            .ok_or_else(|| format!("Node not found: {}", current_id))?;
+// Note: Undoes the wrapper generate_sql_with_options adds for pagination; SQL without it is returned as is
+#[allow(dead_code)]
+pub fn strip_pagination_wrapper(sql: &str) -> Result<String, String> {
+    let dialect = DuckDbDialect {};
+    let statements =
+        Parser::parse_sql(&dialect, sql).map_err(|e| format!("Failed to parse SQL: {}", e))?;
 
-        path.push(current_node);
+    let inner = match statements.as_slice() {
+        [Statement::Query(query)] => pagination_inner_query(query),
+        _ => None,
+    };
 
-        if let Some(edge) = node_graph.edges.iter().find(|e| e.target == current_id) {
-            current_id = edge.source.clone();
-        } else {
-            break;
-        }
-    }
+    Ok(inner.unwrap_or_else(|| sql.to_string()))
+}
 
-    path.reverse();
+fn pagination_inner_query(query: &Query) -> Option<String> {
+    if query.order_by.is_some() || query.limit_clause.is_none() {
+        return None;
+    }
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return None;
+    };
+    if !matches!(select.projection.as_slice(), [SelectItem::Wildcard(_)])
+        || select.selection.is_some()
+    {
+        return None;
+    }
+    let [from] = select.from.as_slice() else {
+        return None;
+    };
+    if !from.joins.is_empty() {
+        return None;
+    }
 
-    Ok(path)
+    match (&query.with, &from.relation) {
+        (
+            None,
+            TableFactor::Derived {
+                subquery,
+                alias: Some(alias),
+                ..
+            },
+        ) if alias.name.value == "subquery" => Some(subquery.to_string()),
+        (Some(with), TableFactor::Table { name, .. })
+            if name.to_string() == PAGINATION_CTE && !with.recursive =>
+        {
+            let (paginated, rest) = with.cte_tables.split_last()?;
+            if paginated.alias.name.value != PAGINATION_CTE {
+                return None;
+            }
+            if rest.is_empty() {
+                return Some(paginated.query.to_string());
+            }
+            let rest: Vec<String> = rest.iter().map(ToString::to_string).collect();
+            Some(format!("WITH {} {}", rest.join(", "), paginated.query))
+        }
+        _ => None,
+    }
 }
 
-fn build_where_expr(conditions: &[FilterCondition]) -> Result<Expr, String> {
-    if conditions.is_empty() {
-        return Err("No filter conditions provided".to_string());
+// Note: Only a query whose rows map one to one onto its source rows can take the cursor condition directly
+fn is_plain_select(query: &Query) -> bool {
+    if query.limit_clause.is_some() || query.fetch.is_some() {
+        return false;
     }
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return false;
+    };
+    select.distinct.is_none()
+        && select.having.is_none()
+        && select.qualify.is_none()
+        && matches!(&select.group_by, GroupByExpr::Expressions(exprs, _) if exprs.is_empty())
+        && select.projection.iter().all(|item| {
+            matches!(
+                item,
+                SelectItem::Wildcard(_) | SelectItem::UnnamedExpr(Expr::Identifier(_))
+            )
+        })
+}
 
-    let valid_conditions: Vec<&FilterCondition> = conditions
-        .iter()
-        .filter(|c| !is_empty_value(&c.value))
-        .collect();
+// Note: The next cursor is read from the order column, so a page that leaves it out cannot continue
+fn selects_column(query: &Query, column: &str) -> bool {
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return false;
+    };
+    select.projection.iter().any(|item| match item {
+        SelectItem::Wildcard(_) => true,
+        SelectItem::UnnamedExpr(Expr::Identifier(ident)) => ident.value == column,
+        _ => false,
+    })
+}
 
-    if valid_conditions.is_empty() {
-        return Err("No valid filter conditions (all have empty values)".to_string());
+pub fn cursor_paginate(sql: &str, cursor: &CursorPagination) -> Result<String, String> {
+    if !is_valid_identifier(&cursor.order_column) {
+        return Err(format!("Invalid order column: {}", cursor.order_column));
     }
 
-    let exprs: Result<Vec<Expr>, String> = valid_conditions
-        .iter()
-        .map(|c| condition_to_expr(c))
-        .collect();
-    let exprs = exprs?;
+    let dialect = DuckDbDialect {};
+    let statements =
+        Parser::parse_sql(&dialect, sql).map_err(|e| format!("Failed to parse SQL: {}", e))?;
+    let mut query = match statements.into_iter().next() {
+        Some(Statement::Query(query)) if is_plain_select(&query) => {
+            if !selects_column(&query, &cursor.order_column) {
+                return Err(format!(
+                    "Order column is not selected by the query: {}",
+                    cursor.order_column
+                ));
+            }
+            query
+        }
+        _ => {
+            let wrapped = format!("SELECT * FROM ({}) AS subquery", sql);
+            match Parser::parse_sql(&dialect, &wrapped)
+                .map_err(|e| format!("Failed to parse SQL: {}", e))?
+                .into_iter()
+                .next()
+            {
+                Some(Statement::Query(query)) => query,
+                _ => return Err("Expected a single query".to_string()),
+            }
+        }
+    };
 
-    // Note: Combine all conditions with AND
-    let mut result = exprs[0].clone();
-    for expr in &exprs[1..] {
-        result = Expr::BinaryOp {
-            left: Box::new(result),
-            op: BinaryOperator::And,
-            right: Box::new(expr.clone()),
+    let column = Expr::Identifier(Ident::new(&cursor.order_column));
+    if let (Some(value), SetExpr::Select(select)) = (&cursor.cursor_value, query.body.as_mut()) {
+        let condition = Expr::BinaryOp {
+            left: Box::new(column.clone()),
+            op: BinaryOperator::Gt,
+            right: Box::new(parse_value(value)?),
         };
+        select.selection = Some(match select.selection.take() {
+            Some(selection) => Expr::BinaryOp {
+                left: Box::new(Expr::Nested(Box::new(selection))),
+                op: BinaryOperator::And,
+                right: Box::new(condition),
+            },
+            None => condition,
+        });
     }
+    query.order_by = Some(OrderBy {
+        kind: OrderByKind::Expressions(vec![OrderByExpr {
+            expr: column,
+            options: OrderByOptions {
+                asc: None,
+                nulls_first: None,
+            },
+            with_fill: None,
+        }]),
+        interpolate: None,
+    });
+    query.limit_clause = Some(LimitClause::LimitOffset {
+        limit: Some(Expr::Value(ValueWithSpan {
+            value: Value::Number(cursor.page_size.to_string(), false),
+            span: Span::empty(),
+        })),
+        offset: None,
+        limit_by: Vec::new(),
+    });
 
-    Ok(result)
+    Ok(query.to_string())
 }
 
-fn is_empty_value(value: &serde_json::Value) -> bool {
-    match value {
-        serde_json::Value::String(s) => s.is_empty(),
-        serde_json::Value::Array(arr) => arr.is_empty() || arr.iter().all(is_empty_value),
-        _ => false,
+pub fn explain_node_graph(node_graph: &NodeGraph) -> Result<String, String> {
+    let path = build_path(node_graph, node_graph.selected_node_id())?;
+
+    if !matches!(
+        path.first().map(|n| n.node_type.as_str()),
+        Some("table" | "table_function")
+    ) {
+        return Err("No table node found in path".to_string());
     }
-}
 
-fn filter_operator_to_binary_operator(op: &FilterOperator) -> Option<BinaryOperator> {
-    match op {
-        FilterOperator::Eq => Some(BinaryOperator::Eq),
-        FilterOperator::NotEq => Some(BinaryOperator::NotEq),
-        FilterOperator::Gt => Some(BinaryOperator::Gt),
-        FilterOperator::Lt => Some(BinaryOperator::Lt),
-        FilterOperator::GtEq => Some(BinaryOperator::GtEq),
-        FilterOperator::LtEq => Some(BinaryOperator::LtEq),
-        FilterOperator::In => None,
+    let mut steps = Vec::<String>::new();
+
+    for node in &path {
+        let step = match node.node_type.as_str() {
+            "table" => {
+                let table_data: TableNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse table node data: {}", e))?;
+                let name = match &table_data.schema {
+                    Some(schema) => format!("{}.{}", schema, table_data.table_name),
+                    None => table_data.table_name,
+                };
+                Some(format!("takes the `{}` table", name))
+            }
+            "table_function" => {
+                let function_data: TableFunctionNodeData =
+                    serde_json::from_value(node.data.clone())
+                        .map_err(|e| format!("Failed to parse table function node data: {}", e))?;
+                let args = function_data
+                    .args
+                    .iter()
+                    .map(|arg| parse_value(arg).map(|e| e.to_string()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Some(format!(
+                    "takes the results of `{}({})`",
+                    function_data.function_name,
+                    args.join(", ")
+                ))
+            }
+            "select" => {
+                let select_data: SelectNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse select node data: {}", e))?;
+                (!select_data.columns.is_empty())
+                    .then(|| format!("selects columns `{}`", select_data.columns.join(", ")))
+            }
+            "sort" => {
+                let sort_data: SortNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse sort node data: {}", e))?;
+                let order: Vec<String> = sort_data
+                    .order
+                    .iter()
+                    .map(|o| {
+                        let direction = match o.direction {
+                            OrderDirection::Asc => "ascending",
+                            OrderDirection::Desc => "descending",
+                        };
+                        format!("`{}` {}", o.column, direction)
+                    })
+                    .collect();
+                match sort_data.random_seed {
+                    Some(seed) if order.is_empty() => {
+                        Some(format!("shuffles rows with seed {}", seed))
+                    }
+                    _ => (!order.is_empty()).then(|| format!("sorts by {}", order.join(", then "))),
+                }
+            }
+            "limit" => {
+                let limit_data: LimitNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse limit node data: {}", e))?;
+                limit_data
+                    .limit
+                    .map(|limit| format!("returns the top {} results", limit))
+            }
+            "filter" => {
+                let filter_data: FilterNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse filter node data: {}", e))?;
+                let conditions = filter_data
+                    .conditions
+                    .iter()
+                    .filter(|c| !is_empty_value(&c.value))
+                    .map(|c| condition_to_expr(c).map(|e| format!("`{}`", e)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (!conditions.is_empty())
+                    .then(|| format!("filters rows where {}", conditions.join(" and ")))
+            }
+            "aggregation" => {
+                let agg_data: AggregationNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse aggregation node data: {}", e))?;
+                let metrics = agg_data
+                    .metrics
+                    .iter()
+                    .map(|m| create_aggregate_function(m).map(|e| e.to_string()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match (agg_data.dimensions.is_empty(), metrics.is_empty()) {
+                    (true, true) => None,
+                    (true, false) => Some(format!("computes `{}`", metrics.join(", "))),
+                    (false, true) => {
+                        Some(format!("groups by `{}`", agg_data.dimensions.join(", ")))
+                    }
+                    (false, false) => Some(format!(
+                        "groups by `{}` and computes `{}`",
+                        agg_data.dimensions.join(", "),
+                        metrics.join(", ")
+                    )),
+                }
+            }
+            "drop_duplicates" => {
+                let dedup_data: DropDuplicatesNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse drop duplicates node data: {}", e))?;
+                (!dedup_data.columns.is_empty()).then(|| {
+                    format!(
+                        "keeps the first row for each `{}`",
+                        dedup_data.columns.join(", ")
+                    )
+                })
+            }
+            "join" => {
+                let join_data: JoinNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse join node data: {}", e))?;
+                Some(match join_data.join_type {
+                    JoinType::Natural => format!(
+                        "joins `{}` on its columns of the same name",
+                        join_data.qualified_name()
+                    ),
+                    JoinType::Anti => format!(
+                        "keeps rows with no match in `{}` on `{}`",
+                        join_data.qualified_name(),
+                        join_data.keys.join(", ")
+                    ),
+                })
+            }
+            _ => {
+                return Err(format!("Unsupported node type: {}", node.node_type));
+            }
+        };
+        steps.extend(step);
     }
-}
 
-fn condition_to_expr(condition: &FilterCondition) -> Result<Expr, String> {
-    let column_expr = Expr::Identifier(Ident::new(&condition.column));
+    let description = match steps.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, [first])) => format!("{} and {}", first, last),
+        Some((last, rest)) => format!("{}, and {}", rest.join(", "), last),
+        None => return Err("No table node found in path".to_string()),
+    };
 
-    let base_expr = if let Some(binary_op) = filter_operator_to_binary_operator(&condition.operator)
-    {
-        let value = parse_value(&condition.value)?;
-        Expr::BinaryOp {
-            left: Box::new(column_expr),
-            op: binary_op,
-            right: Box::new(value),
+    // Note: Every step starts with a lowercase ASCII verb
+    Ok(format!(
+        "{}{}.",
+        description[..1].to_uppercase(),
+        &description[1..]
+    ))
+}
+
+fn node_detail(node: &Node) -> Result<Option<String>, String> {
+    let detail = match node.node_type.as_str() {
+        "table" => {
+            let table_data: TableNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse table node data: {}", e))?;
+            Some(match table_data.schema {
+                Some(schema) => format!("{}.{}", schema, table_data.table_name),
+                None => table_data.table_name,
+            })
         }
-    } else {
-        let values = parse_array_values(&condition.value)?;
-        Expr::InList {
-            expr: Box::new(column_expr),
-            list: values,
-            negated: false,
+        "table_function" => {
+            let function_data: TableFunctionNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse table function node data: {}", e))?;
+            let args = function_data
+                .args
+                .iter()
+                .map(|arg| parse_value(arg).map(|e| e.to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(format!(
+                "{}({})",
+                function_data.function_name,
+                args.join(", ")
+            ))
+        }
+        "select" => {
+            let select_data: SelectNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse select node data: {}", e))?;
+            (!select_data.columns.is_empty()).then(|| select_data.columns.join(", "))
+        }
+        "filter" => {
+            let filter_data: FilterNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse filter node data: {}", e))?;
+            let conditions = filter_data
+                .conditions
+                .iter()
+                .filter(|c| !is_empty_value(&c.value))
+                .map(|c| condition_to_expr(c).map(|e| e.to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            (!conditions.is_empty()).then(|| conditions.join(" AND "))
+        }
+        "sort" => {
+            let sort_data: SortNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse sort node data: {}", e))?;
+            let order: Vec<String> = sort_data
+                .order
+                .iter()
+                .map(|o| {
+                    let direction = match o.direction {
+                        OrderDirection::Asc => "ASC",
+                        OrderDirection::Desc => "DESC",
+                    };
+                    format!("{} {}", o.column, direction)
+                })
+                .collect();
+            match sort_data.random_seed {
+                Some(seed) if order.is_empty() => Some(format!("random (seed {})", seed)),
+                _ => (!order.is_empty()).then(|| order.join(", ")),
+            }
+        }
+        "limit" => {
+            let limit_data: LimitNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse limit node data: {}", e))?;
+            limit_data.limit.map(|limit| limit.to_string())
+        }
+        "aggregation" => {
+            let agg_data: AggregationNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse aggregation node data: {}", e))?;
+            let metrics = agg_data
+                .metrics
+                .iter()
+                .map(|m| create_aggregate_function(m).map(|e| e.to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut parts = Vec::new();
+            if !agg_data.dimensions.is_empty() {
+                parts.push(format!("GROUP BY {}", agg_data.dimensions.join(", ")));
+            }
+            parts.extend(metrics);
+            (!parts.is_empty()).then(|| parts.join(", "))
         }
+        "drop_duplicates" => {
+            let dedup_data: DropDuplicatesNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse drop duplicates node data: {}", e))?;
+            (!dedup_data.columns.is_empty()).then(|| dedup_data.columns.join(", "))
+        }
+        "join" => {
+            let join_data: JoinNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse join node data: {}", e))?;
+            Some(join_data.clause(&join_data.qualified_name())?)
+        }
+        // Note: Charts and other non-query nodes are labelled with their type alone
+        _ => None,
     };
 
-    if condition.negate {
-        Ok(Expr::UnaryOp {
-            op: UnaryOperator::Not,
-            expr: Box::new(base_expr),
-        })
-    } else {
-        Ok(base_expr)
+    Ok(detail)
+}
+
+// Note: Diagram ids are generated from the node position because node ids may contain any character
+fn diagram_node_id(node_graph: &NodeGraph, id: &str) -> Result<String, String> {
+    node_graph
+        .nodes
+        .iter()
+        .position(|n| n.id == id)
+        .map(|i| format!("n{}", i + 1))
+        .ok_or_else(|| format!("Edge references unknown node: {}", id))
+}
+
+pub fn node_graph_to_mermaid(node_graph: &NodeGraph) -> Result<String, String> {
+    let mut lines = vec!["graph LR".to_string()];
+    for (i, node) in node_graph.nodes.iter().enumerate() {
+        let label = match node_detail(node)? {
+            Some(detail) => format!("{}: {}", node.node_type, detail),
+            None => node.node_type.clone(),
+        };
+        lines.push(format!(
+            "    n{}[\"{}\"]",
+            i + 1,
+            label.replace('"', "#quot;")
+        ));
+    }
+    for edge in &node_graph.edges {
+        lines.push(format!(
+            "    {} --> {}",
+            diagram_node_id(node_graph, &edge.source)?,
+            diagram_node_id(node_graph, &edge.target)?
+        ));
     }
+
+    Ok(lines.join("\n"))
 }
 
-fn parse_value(value: &serde_json::Value) -> Result<Expr, String> {
-    match value {
-        serde_json::Value::String(s) => Ok(Expr::Value(ValueWithSpan {
-            value: Value::SingleQuotedString(s.clone()),
-            span: Span::empty(),
-        })),
-        serde_json::Value::Number(n) => Ok(Expr::Value(ValueWithSpan {
-            value: Value::Number(n.to_string(), false),
-            span: Span::empty(),
-        })),
-        serde_json::Value::Bool(b) => Ok(Expr::Value(ValueWithSpan {
-            value: Value::Boolean(*b),
-            span: Span::empty(),
-        })),
-        _ => Err(format!("Unsupported value type: {:?}", value)),
+fn dot_shape(node_type: &str) -> &'static str {
+    match node_type {
+        "table" | "table_function" => "box",
+        "filter" => "diamond",
+        "aggregation" => "ellipse",
+        _ => "box, style=rounded",
     }
 }
 
-fn parse_array_values(value: &serde_json::Value) -> Result<Vec<Expr>, String> {
-    match value {
-        serde_json::Value::Array(arr) => arr.iter().map(parse_value).collect(),
-        _ => Err("Expected array for 'in' operator".to_string()),
+pub fn node_graph_to_dot(node_graph: &NodeGraph) -> Result<String, String> {
+    let mut lines = vec![
+        "digraph node_graph {".to_string(),
+        "    rankdir=LR;".to_string(),
+    ];
+    for (i, node) in node_graph.nodes.iter().enumerate() {
+        let label = match node_detail(node)? {
+            Some(detail) => format!("{}: {}", node.node_type, detail),
+            None => node.node_type.clone(),
+        };
+        lines.push(format!(
+            "    n{} [label=\"{}\", shape={}];",
+            i + 1,
+            label.replace('\\', "\\\\").replace('"', "\\\""),
+            dot_shape(&node.node_type)
+        ));
     }
+    for edge in &node_graph.edges {
+        lines.push(format!(
+            "    {} -> {};",
+            diagram_node_id(node_graph, &edge.source)?,
+            diagram_node_id(node_graph, &edge.target)?
+        ));
+    }
+    lines.push("}".to_string());
+
+    Ok(lines.join("\n"))
 }
 
-fn build_aggregation_projection(agg: &AggregationNodeData) -> Result<Vec<SelectItem>, String> {
-    let mut projection = Vec::new();
+// Note: Every node scores 1, plus 2 per filter condition, 3 per aggregation and 5 per join
+pub fn complexity_score(node_graph: &NodeGraph) -> u32 {
+    node_graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let extra = match node.node_type.as_str() {
+                "filter" => node.data["conditions"]
+                    .as_array()
+                    .map_or(0, |c| c.len() as u32 * 2),
+                "aggregation" => 3,
+                "join" => 5,
+                _ => 0,
+            };
+            1 + extra
+        })
+        .sum()
+}
 
-    for dim in &agg.dimensions {
-        projection.push(SelectItem::UnnamedExpr(Expr::Identifier(Ident::new(dim))));
+// Note: Node types whose SQL clause runs after this node type's when both merge into one SELECT
+fn evaluated_before(node_type: &str) -> &'static [&'static str] {
+    match node_type {
+        "filter" | "join" => &["aggregation", "drop_duplicates", "limit"],
+        "aggregation" => &["aggregation", "limit", "sort"],
+        "drop_duplicates" => &["drop_duplicates", "limit"],
+        "select" => &["aggregation"],
+        "sort" | "limit" => &["limit"],
+        _ => &[],
     }
+}
 
-    for metric in &agg.metrics {
-        let func_expr = create_aggregate_function(metric)?;
-        projection.push(SelectItem::UnnamedExpr(func_expr));
-    }
+// Note: Select, sort and limit merge into the SELECT in any order; any other node that SQL would
+// evaluate before an earlier one, such as a filter after an aggregation, reads it from a subquery
+fn subquery_split_index(path: &[&Node]) -> Option<usize> {
+    path.iter().enumerate().position(|(i, node)| {
+        !matches!(node.node_type.as_str(), "select" | "sort" | "limit")
+            && path[..i].iter().any(|earlier| {
+                evaluated_before(&node.node_type).contains(&earlier.node_type.as_str())
+            })
+    })
+}
 
-    Ok(projection)
+// Note: Cutting the path into a CTE after `at` would reorder nodes that merge in any order, such as
+// a limit before a sort, or hide the rowid a seeded shuffle hashes, so such shared nodes stay inline
+fn cte_cut_reorders(path: &[&Node], at: usize) -> bool {
+    let mut start = 0;
+    let end = loop {
+        match subquery_split_index(&path[start..]) {
+            Some(split) if start + split <= at => start += split,
+            split => break split.map_or(path.len(), |split| start + split),
+        }
+    };
+    path[at + 1..end].iter().any(|later| {
+        is_seeded_shuffle(later)
+            || path[start..=at].iter().any(|earlier| {
+                evaluated_before(&later.node_type).contains(&earlier.node_type.as_str())
+            })
+    })
 }
 
-fn aggregate_function_name(func: &AggregateFunction) -> &'static str {
-    match func {
-        AggregateFunction::CountAll => "COUNT",
-        AggregateFunction::Count => "COUNT",
-        AggregateFunction::Sum => "SUM",
-        AggregateFunction::Avg => "AVG",
-        AggregateFunction::Max => "MAX",
-        AggregateFunction::Min => "MIN",
+// Note: Checks that span several nodes run over the whole path, since CTEs and subqueries split it up
+fn check_node_order(path: &[&Node]) -> Result<(), String> {
+    let position = |node_type: &str| path.iter().position(|node| node.node_type == node_type);
+    let aggregation = position("aggregation");
+
+    if let (Some(select), Some(aggregation)) = (position("select"), aggregation) {
+        if select < aggregation {
+            return Err("Cannot use Aggregation after Select node. Please remove the Select node or reorder the nodes.".to_string());
+        }
     }
+    if aggregation.is_some() && position("drop_duplicates").is_some() {
+        return Err(
+            "Cannot combine Aggregation and Drop Duplicates nodes in one query.".to_string(),
+        );
+    }
+    Ok(())
 }
 
-fn create_aggregate_args(metric: &Metric) -> Vec<FunctionArg> {
-    match &metric.function {
-        AggregateFunction::CountAll => vec![FunctionArg::Unnamed(FunctionArgExpr::Wildcard)],
-        _ => vec![FunctionArg::Unnamed(FunctionArgExpr::Expr(
-            Expr::Identifier(Ident::new(&metric.column)),
-        ))],
-    }
+fn is_seeded_shuffle(node: &Node) -> bool {
+    node.node_type == "sort"
+        && node.data["random_seed"].is_u64()
+        && node.data["order"].as_array().is_none_or(Vec::is_empty)
 }
 
-fn create_aggregate_function(metric: &Metric) -> Result<Expr, String> {
-    let func_name = aggregate_function_name(&metric.function);
-    let args = create_aggregate_args(metric);
+fn split_into_subquery(
+    path: &[&Node],
+    split: usize,
+    source: Option<&str>,
+    strict: bool,
+    aggregated_source: bool,
+) -> Result<String, String> {
+    let inner_sql = build_select_sql(&path[..split], source, strict, aggregated_source)?;
+    let aggregated = path[..split]
+        .iter()
+        .any(|node| node.node_type == "aggregation");
+    let alias = if aggregated { "aggregated" } else { "upstream" };
+    let subquery = format!("({}) AS {}", inner_sql, alias);
+    build_select_sql(
+        &path[split..],
+        Some(&subquery),
+        strict,
+        aggregated_source || aggregated,
+    )
+}
+
+// Note: Aggregated metrics keep DuckDB's default names such as `sum(id)`, so filters on them are quoted
+fn build_select_sql(
+    path: &[&Node],
+    source: Option<&str>,
+    strict: bool,
+    aggregated_source: bool,
+) -> Result<String, String> {
+    if let Some(split) = subquery_split_index(path) {
+        return split_into_subquery(path, split, source, strict, aggregated_source);
+    }
+
+    // Note: Metric names such as `sum(id)` are only columns once the aggregation is a subquery or CTE
+    let is_metric_name = |name: &str| aggregated_source && !is_valid_identifier(name);
+    let column_ident = |name: &str| {
+        if is_metric_name(name) {
+            Ident::with_quote('"', name)
+        } else {
+            Ident::new(name)
+        }
+    };
+
+    let check_names = |names: Vec<&str>| -> Result<(), String> {
+        match names
+            .into_iter()
+            .find(|name| strict && !is_valid_identifier(name))
+        {
+            Some(name) => Err(format!(
+                "Expressions are not allowed in strict mode: {}",
+                name
+            )),
+            None => Ok(()),
+        }
+    };
+
+    let mut table_name = source.unwrap_or_default().to_string();
+    let mut schema_name: Option<String> = None;
+    let mut table_function: Option<TableFunctionNodeData> = None;
+    let mut columns = Vec::<String>::new();
+    let mut order_by_list = Vec::<OrderByData>::new();
+    let mut random_seed: Option<u64> = None;
+    let mut limit_value: Option<i64> = None;
+    let mut filter_conditions = Vec::<FilterCondition>::new();
+    let mut aggregation_data: Option<AggregationNodeData> = None;
+    let mut dedup_columns = Vec::<String>::new();
+    let mut reads_base_table = false;
+    let mut joins = Vec::<JoinNodeData>::new();
+
+    for node in path {
+        match node.node_type.as_str() {
+            "table" => {
+                let table_data: TableNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse table node data: {}", e))?;
+                if let Some(schema) = &table_data.schema {
+                    if !is_valid_identifier(schema) {
+                        return Err(format!("Invalid schema name: {}", schema));
+                    }
+                }
+                check_names(vec![&table_data.table_name])?;
+                table_name = table_data.table_name;
+                schema_name = table_data.schema;
+                table_function = None;
+                reads_base_table = true;
+            }
+            "table_function" => {
+                let function_data: TableFunctionNodeData =
+                    serde_json::from_value(node.data.clone())
+                        .map_err(|e| format!("Failed to parse table function node data: {}", e))?;
+                if !ALLOWED_TABLE_FUNCTIONS.contains(&function_data.function_name.as_str()) {
+                    return Err(format!(
+                        "Unsupported table function: {}",
+                        function_data.function_name
+                    ));
+                }
+                table_name = function_data.function_name.clone();
+                schema_name = None;
+                table_function = Some(function_data);
+                reads_base_table = false;
+            }
+            "select" => {
+                let select_data: SelectNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse select node data: {}", e))?;
+                check_names(
+                    select_data
+                        .columns
+                        .iter()
+                        .map(String::as_str)
+                        .filter(|name| !is_metric_name(name))
+                        .collect(),
+                )?;
+                columns = select_data.columns;
+            }
+            "sort" => {
+                let sort_data: SortNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse sort node data: {}", e))?;
+                check_names(
+                    sort_data
+                        .order
+                        .iter()
+                        .map(|o| o.column.as_str())
+                        .filter(|name| !is_metric_name(name))
+                        .collect(),
+                )?;
+                order_by_list = sort_data.order;
+                random_seed = sort_data.random_seed;
+            }
+            "limit" => {
+                let limit_data: LimitNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse limit node data: {}", e))?;
+                limit_value = limit_data.limit;
+            }
+            "filter" => {
+                let mut filter_data: FilterNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse filter node data: {}", e))?;
+                for condition in &mut filter_data.conditions {
+                    condition.quoted = is_metric_name(&condition.column);
+                }
+                check_names(
+                    filter_data
+                        .conditions
+                        .iter()
+                        .filter(|c| !c.quoted)
+                        .map(|c| c.column.as_str())
+                        .collect(),
+                )?;
+                filter_conditions.extend(filter_data.conditions);
+            }
+            "aggregation" => {
+                let agg_data: AggregationNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse aggregation node data: {}", e))?;
+
+                check_names(
+                    agg_data
+                        .dimensions
+                        .iter()
+                        .map(String::as_str)
+                        .chain(
+                            agg_data
+                                .metrics
+                                .iter()
+                                .filter(|m| !matches!(m.function, AggregateFunction::CountAll))
+                                .map(|m| m.column.as_str()),
+                        )
+                        .collect(),
+                )?;
+
+                aggregation_data = Some(agg_data);
+            }
+            "drop_duplicates" => {
+                let dedup_data: DropDuplicatesNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse drop duplicates node data: {}", e))?;
+                check_names(dedup_data.columns.iter().map(String::as_str).collect())?;
+                dedup_columns = dedup_data.columns;
+            }
+            "join" => {
+                let join_data: JoinNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse join node data: {}", e))?;
+                if let Some(schema) = &join_data.schema {
+                    if !is_valid_identifier(schema) {
+                        return Err(format!("Invalid schema name: {}", schema));
+                    }
+                }
+                check_names(vec![&join_data.table_name])?;
+                check_names(join_data.keys.iter().map(String::as_str).collect())?;
+                joins.push(join_data);
+            }
+            _ => {
+                return Err(format!("Unsupported node type: {}", node.node_type));
+            }
+        }
+    }
+
+    if table_name.is_empty() {
+        return Err("No table node found in path".to_string());
+    }
+
+    let dialect = DuckDbDialect {};
+    let mut base_sql = format!("SELECT * FROM {}", table_name);
+    for join in &joins {
+        base_sql.push_str(&format!(" {}", join.clause(&join.table_name)?));
+    }
+    let mut ast = Parser::parse_sql(&dialect, &base_sql)
+        .map_err(|e| format!("Failed to parse base SQL: {}", e))?;
+
+    if ast.is_empty() {
+        return Err("Failed to generate base AST".to_string());
+    }
+
+    if let Statement::Query(ref mut query) = ast[0] {
+        if let SetExpr::Select(ref mut select) = *query.body {
+            if let Some(schema) = &schema_name {
+                if let Some(TableFactor::Table { name, .. }) =
+                    select.from.first_mut().map(|t| &mut t.relation)
+                {
+                    *name = ObjectName::from(vec![Ident::new(schema), Ident::new(&table_name)]);
+                }
+            }
+
+            if let Some(from) = select.from.first_mut() {
+                for (join, data) in from.joins.iter_mut().zip(&joins) {
+                    if let (TableFactor::Table { name, .. }, Some(schema)) =
+                        (&mut join.relation, &data.schema)
+                    {
+                        *name = ObjectName::from(vec![
+                            Ident::new(schema),
+                            Ident::new(&data.table_name),
+                        ]);
+                    }
+                }
+            }
+
+            if let Some(function) = &table_function {
+                let args = function
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        parse_value(arg).map(|e| FunctionArg::Unnamed(FunctionArgExpr::Expr(e)))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if let Some(table) = select.from.first_mut() {
+                    table.relation = TableFactor::Function {
+                        lateral: false,
+                        name: ObjectName::from(vec![Ident::new(&function.function_name)]),
+                        args,
+                        alias: None,
+                    };
+                }
+            }
+
+            if let Some(agg) = &aggregation_data {
+                if !agg.dimensions.is_empty() || !agg.metrics.is_empty() {
+                    select.projection = build_aggregation_projection(agg)?;
+                }
+            } else if !columns.is_empty() {
+                select.projection = columns
+                    .iter()
+                    .map(|col| SelectItem::UnnamedExpr(Expr::Identifier(column_ident(col))))
+                    .collect();
+            }
+
+            // Note: Qualifying on the window expression itself keeps the row number out of the result
+            if !dedup_columns.is_empty() {
+                select.qualify = Some(Expr::BinaryOp {
+                    left: Box::new(row_number_expr(&dedup_columns, reads_base_table)),
+                    op: BinaryOperator::Eq,
+                    right: Box::new(Expr::Value(ValueWithSpan {
+                        value: Value::Number("1".to_string(), false),
+                        span: Span::empty(),
+                    })),
+                });
+            }
+
+            if !filter_conditions.is_empty() {
+                if let Ok(where_expr) = build_where_expr(&filter_conditions) {
+                    select.selection = Some(where_expr);
+                }
+            }
+
+            if let Some(agg) = &aggregation_data {
+                if !agg.dimensions.is_empty() {
+                    select.group_by = GroupByExpr::Expressions(
+                        agg.dimensions
+                            .iter()
+                            .map(|dim| Expr::Identifier(Ident::new(dim)))
+                            .collect(),
+                        vec![],
+                    );
+                }
+            }
+        }
+
+        if !order_by_list.is_empty() {
+            let order_by_exprs: Vec<OrderByExpr> = order_by_list
+                .iter()
+                .map(|o| OrderByExpr {
+                    expr: Expr::Identifier(column_ident(&o.column)),
+                    options: OrderByOptions {
+                        asc: Some(matches!(o.direction, OrderDirection::Asc)),
+                        nulls_first: None,
+                    },
+                    with_fill: None,
+                })
+                .collect();
+            query.order_by = Some(OrderBy {
+                kind: OrderByKind::Expressions(order_by_exprs),
+                interpolate: None,
+            });
+        } else if let Some(seed) = random_seed {
+            if !reads_base_table || aggregation_data.is_some() {
+                return Err("Sort with a random seed needs rows read straight from a table, not from an aggregation, a table function or a subquery.".to_string());
+            }
+            query.order_by = Some(OrderBy {
+                kind: OrderByKind::Expressions(vec![OrderByExpr {
+                    expr: random_order_expr(&table_name, seed),
+                    options: OrderByOptions {
+                        asc: None,
+                        nulls_first: None,
+                    },
+                    with_fill: None,
+                }]),
+                interpolate: None,
+            });
+        }
+
+        if let Some(limit) = limit_value {
+            query.limit_clause = Some(LimitClause::LimitOffset {
+                limit: Some(Expr::Value(ValueWithSpan {
+                    value: Value::Number(limit.to_string(), false),
+                    span: Span::empty(),
+                })),
+                offset: None,
+                limit_by: Vec::new(),
+            });
+        }
+    }
+
+    Ok(ast[0].to_string())
+}
+
+fn query_successors<'a>(
+    node_graph: &'a NodeGraph,
+    node_id: &str,
+) -> impl Iterator<Item = &'a str> + 'a {
+    let node_id = node_id.to_string();
+    node_graph
+        .edges
+        .iter()
+        .filter(move |e| e.source == node_id)
+        .filter(|e| {
+            node_graph
+                .nodes
+                .iter()
+                .any(|n| n.id == e.target && QUERY_NODE_TYPES.contains(&n.node_type.as_str()))
+        })
+        .map(|e| e.target.as_str())
+}
+
+// Note: Stops where the chain branches or reaches a non-query node such as a chart
+fn find_downstream_end(node_graph: &NodeGraph) -> Result<String, String> {
+    let mut current_id = node_graph.selected_node_id().to_string();
+    let mut visited = vec![current_id.clone()];
+
+    loop {
+        let mut next_ids = query_successors(node_graph, &current_id).map(str::to_string);
+
+        let (Some(next_id), None) = (next_ids.next(), next_ids.next()) else {
+            return Ok(current_id);
+        };
+        if visited.contains(&next_id) {
+            return Err(format!("Cycle detected at node: {}", next_id));
+        }
+        visited.push(next_id.clone());
+        current_id = next_id;
+    }
+}
+
+fn build_path<'a>(node_graph: &'a NodeGraph, end_node_id: &str) -> Result<Vec<&'a Node>, String> {
+    let mut path: Vec<&Node> = Vec::new();
+    let mut current_id = end_node_id.to_string();
+
+    loop {
+        let current_node = node_graph
+            .nodes
+            .iter()
+            .find(|n| n.id == current_id)
+            .ok_or_else(|| format!("Node not found: {}", current_id))?;
+
+        if path.iter().any(|n| n.id == current_id) {
+            return Err(format!("Cycle detected at node: {}", current_id));
+        }
+
+        path.push(current_node);
+
+        if let Some(edge) = node_graph.edges.iter().find(|e| e.target == current_id) {
+            current_id = edge.source.clone();
+        } else {
+            break;
+        }
+    }
+
+    path.reverse();
+
+    Ok(path)
+}
+
+// Note: Returns the table read by the selected node's path, or None when it starts from a table function
+pub fn source_table(node_graph: &NodeGraph) -> Result<Option<String>, String> {
+    let path = build_path(node_graph, node_graph.selected_node_id())?;
+
+    Ok(path
+        .iter()
+        .find(|node| node.node_type == "table")
+        .and_then(|node| node.data["table_name"].as_str())
+        .map(str::to_string))
+}
+
+// Note: Lists the tables a query with join nodes reads, as (schema, table) pairs; empty without joins
+pub fn joined_tables(node_graph: &NodeGraph) -> Result<Vec<(Option<String>, String)>, String> {
+    let path = build_path(node_graph, node_graph.selected_node_id())?;
+    if !path.iter().any(|node| node.node_type == "join") {
+        return Ok(Vec::new());
+    }
+
+    let mut tables = Vec::new();
+    for node in &path {
+        match node.node_type.as_str() {
+            "table" => {
+                let table_data: TableNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse table node data: {}", e))?;
+                tables.push((table_data.schema, table_data.table_name));
+            }
+            "join" => {
+                let join_data: JoinNodeData = serde_json::from_value(node.data.clone())
+                    .map_err(|e| format!("Failed to parse join node data: {}", e))?;
+                tables.push((join_data.schema, join_data.table_name));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(tables)
+}
+
+// Note: Not called by the backend yet; duplicating a node happens on the front-end graph
+#[allow(dead_code)]
+pub fn clone_node(node: &Node, new_id: String) -> Node {
+    Node {
+        id: new_id,
+        ..node.clone()
+    }
+}
+
+// Note: Only select, filter and sort nodes name columns directly; other nodes yield none
+pub fn referenced_columns(node: &Node) -> Result<Vec<String>, String> {
+    let columns = match node.node_type.as_str() {
+        "select" => {
+            let select_data: SelectNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse select node data: {}", e))?;
+            select_data.columns
+        }
+        "filter" => {
+            let filter_data: FilterNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse filter node data: {}", e))?;
+            filter_data
+                .conditions
+                .into_iter()
+                .map(|condition| condition.column)
+                .collect()
+        }
+        "sort" => {
+            let sort_data: SortNodeData = serde_json::from_value(node.data.clone())
+                .map_err(|e| format!("Failed to parse sort node data: {}", e))?;
+            sort_data
+                .order
+                .into_iter()
+                .map(|order| order.column)
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    Ok(columns)
+}
+
+fn build_where_expr(conditions: &[FilterCondition]) -> Result<Expr, String> {
+    if conditions.is_empty() {
+        return Err("No filter conditions provided".to_string());
+    }
+
+    let valid_conditions: Vec<&FilterCondition> = conditions
+        .iter()
+        .filter(|c| !is_empty_value(&c.value))
+        .collect();
+
+    if valid_conditions.is_empty() {
+        return Err("No valid filter conditions (all have empty values)".to_string());
+    }
+
+    let exprs: Result<Vec<Expr>, String> = valid_conditions
+        .iter()
+        .map(|c| condition_to_expr(c))
+        .collect();
+    let exprs = exprs?;
+
+    // Note: Combine all conditions with AND
+    let mut result = exprs[0].clone();
+    for expr in &exprs[1..] {
+        result = Expr::BinaryOp {
+            left: Box::new(result),
+            op: BinaryOperator::And,
+            right: Box::new(expr.clone()),
+        };
+    }
+
+    Ok(result)
+}
+
+fn is_empty_value(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::String(s) => s.is_empty(),
+        serde_json::Value::Array(arr) => arr.is_empty() || arr.iter().all(is_empty_value),
+        _ => false,
+    }
+}
+
+fn filter_operator_to_binary_operator(op: &FilterOperator) -> Option<BinaryOperator> {
+    match op {
+        FilterOperator::Eq => Some(BinaryOperator::Eq),
+        FilterOperator::NotEq => Some(BinaryOperator::NotEq),
+        FilterOperator::Gt => Some(BinaryOperator::Gt),
+        FilterOperator::Lt => Some(BinaryOperator::Lt),
+        FilterOperator::GtEq => Some(BinaryOperator::GtEq),
+        FilterOperator::LtEq => Some(BinaryOperator::LtEq),
+        FilterOperator::In => None,
+    }
+}
+
+fn condition_to_expr(condition: &FilterCondition) -> Result<Expr, String> {
+    let column_expr = if condition.quoted {
+        Expr::Identifier(Ident::with_quote('"', &condition.column))
+    } else {
+        Expr::Identifier(Ident::new(&condition.column))
+    };
+
+    let base_expr = if let Some(binary_op) = filter_operator_to_binary_operator(&condition.operator)
+    {
+        let value = parse_value(&condition.value)?;
+        Expr::BinaryOp {
+            left: Box::new(column_expr),
+            op: binary_op,
+            right: Box::new(value),
+        }
+    } else {
+        let values = parse_array_values(&condition.value)?;
+        Expr::InList {
+            expr: Box::new(column_expr),
+            list: values,
+            negated: false,
+        }
+    };
+
+    if condition.negate {
+        Ok(Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr: Box::new(base_expr),
+        })
+    } else {
+        Ok(base_expr)
+    }
+}
+
+pub fn is_valid_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn parse_value(value: &serde_json::Value) -> Result<Expr, String> {
+    match value {
+        // Note: sqlparser leaves `''` and `\'` untouched when rendering, so quotes are escaped here
+        serde_json::Value::String(s) => Ok(Expr::Value(ValueWithSpan {
+            value: Value::SingleQuotedString(s.replace('\'', "''")),
+            span: Span::empty(),
+        })),
+        serde_json::Value::Number(n) => Ok(Expr::Value(ValueWithSpan {
+            value: Value::Number(n.to_string(), false),
+            span: Span::empty(),
+        })),
+        serde_json::Value::Bool(b) => Ok(Expr::Value(ValueWithSpan {
+            value: Value::Boolean(*b),
+            span: Span::empty(),
+        })),
+        // Note: The builder renders SQL text rather than a prepared statement, so the object is inlined as a string
+        serde_json::Value::Object(_) => Ok(Expr::Cast {
+            kind: CastKind::Cast,
+            expr: Box::new(Expr::Value(ValueWithSpan {
+                value: Value::SingleQuotedString(value.to_string().replace('\'', "''")),
+                span: Span::empty(),
+            })),
+            data_type: DataType::JSON,
+            format: None,
+        }),
+        _ => Err(format!("Unsupported value type: {:?}", value)),
+    }
+}
+
+const NUMERIC_TYPES: &[&str] = &[
+    "TINYINT",
+    "SMALLINT",
+    "INTEGER",
+    "BIGINT",
+    "HUGEINT",
+    "UTINYINT",
+    "USMALLINT",
+    "UINTEGER",
+    "UBIGINT",
+    "UHUGEINT",
+    "FLOAT",
+    "DOUBLE",
+    "REAL",
+];
+
+fn is_numeric_type(column_type: &str) -> bool {
+    NUMERIC_TYPES.contains(&column_type)
+        || column_type.starts_with("DECIMAL")
+        || column_type.starts_with("NUMERIC")
+}
+
+// Note: Only string values are converted; other column types keep the value as a string literal
+pub fn typed_value_expr(value: &serde_json::Value, column_type: &str) -> Result<Expr, String> {
+    let Some(s) = value.as_str() else {
+        return parse_value(value);
+    };
+    let column_type = column_type.to_uppercase();
+    let trimmed = s.trim();
+
+    if is_numeric_type(&column_type) {
+        if !trimmed.parse::<f64>().is_ok_and(f64::is_finite) {
+            return Err(format!("Cannot convert '{}' to {}", s, column_type));
+        }
+        return Ok(Expr::Value(ValueWithSpan {
+            value: Value::Number(trimmed.to_string(), false),
+            span: Span::empty(),
+        }));
+    }
+
+    if column_type == "BOOLEAN" {
+        return match trimmed.to_lowercase().as_str() {
+            "true" => parse_value(&serde_json::Value::Bool(true)),
+            "false" => parse_value(&serde_json::Value::Bool(false)),
+            _ => Err(format!("Cannot convert '{}' to {}", s, column_type)),
+        };
+    }
+
+    parse_value(value)
+}
+
+fn parse_array_values(value: &serde_json::Value) -> Result<Vec<Expr>, String> {
+    match value {
+        serde_json::Value::Array(arr) => arr.iter().map(parse_value).collect(),
+        _ => Err("Expected array for 'in' operator".to_string()),
+    }
+}
+
+fn build_aggregation_projection(agg: &AggregationNodeData) -> Result<Vec<SelectItem>, String> {
+    let mut projection = Vec::new();
+
+    for dim in &agg.dimensions {
+        projection.push(SelectItem::UnnamedExpr(Expr::Identifier(Ident::new(dim))));
+    }
+
+    for metric in &agg.metrics {
+        let func_expr = create_aggregate_function(metric)?;
+        projection.push(SelectItem::UnnamedExpr(func_expr));
+    }
+
+    Ok(projection)
+}
+
+// Note: Only base tables have a rowid, so other sources keep an arbitrary row per key
+fn row_number_expr(partition_by: &[String], order_by_rowid: bool) -> Expr {
+    let order_by = if order_by_rowid {
+        vec![OrderByExpr {
+            expr: Expr::Identifier(Ident::new("rowid")),
+            options: OrderByOptions {
+                asc: None,
+                nulls_first: None,
+            },
+            with_fill: None,
+        }]
+    } else {
+        vec![]
+    };
+
+    Expr::Function(Function {
+        name: ObjectName(vec![sqlparser::ast::ObjectNamePart::Identifier(
+            Ident::new("ROW_NUMBER"),
+        )]),
+        parameters: sqlparser::ast::FunctionArguments::None,
+        args: FunctionArguments::List(FunctionArgumentList {
+            duplicate_treatment: None,
+            args: vec![],
+            clauses: vec![],
+        }),
+        filter: None,
+        null_treatment: None,
+        over: Some(WindowType::WindowSpec(WindowSpec {
+            window_name: None,
+            partition_by: partition_by
+                .iter()
+                .map(|col| Expr::Identifier(Ident::new(col)))
+                .collect(),
+            order_by,
+            window_frame: None,
+        })),
+        within_group: vec![],
+        uses_odbc_syntax: false,
+    })
+}
+
+// Note: Hashing the rowid with the seed gives a reproducible shuffle
+fn random_order_expr(table: &str, seed: u64) -> Expr {
+    let args = vec![
+        Expr::CompoundIdentifier(vec![Ident::new(table), Ident::new("rowid")]),
+        Expr::Value(ValueWithSpan {
+            value: Value::Number(seed.to_string(), false),
+            span: Span::empty(),
+        }),
+    ];
+
+    Expr::Function(Function {
+        name: ObjectName(vec![sqlparser::ast::ObjectNamePart::Identifier(
+            Ident::new("hash"),
+        )]),
+        parameters: sqlparser::ast::FunctionArguments::None,
+        args: FunctionArguments::List(FunctionArgumentList {
+            duplicate_treatment: None,
+            args: args
+                .into_iter()
+                .map(|arg| FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)))
+                .collect(),
+            clauses: vec![],
+        }),
+        filter: None,
+        null_treatment: None,
+        over: None,
+        within_group: vec![],
+        uses_odbc_syntax: false,
+    })
+}
+
+fn aggregate_function_name(func: &AggregateFunction) -> &'static str {
+    match func {
+        AggregateFunction::CountAll => "COUNT",
+        AggregateFunction::Count => "COUNT",
+        AggregateFunction::Sum => "SUM",
+        AggregateFunction::Avg => "AVG",
+        AggregateFunction::Max => "MAX",
+        AggregateFunction::Min => "MIN",
+    }
+}
+
+fn create_aggregate_args(metric: &Metric) -> Vec<FunctionArg> {
+    match &metric.function {
+        AggregateFunction::CountAll => vec![FunctionArg::Unnamed(FunctionArgExpr::Wildcard)],
+        _ => vec![FunctionArg::Unnamed(FunctionArgExpr::Expr(
+            Expr::Identifier(Ident::new(&metric.column)),
+        ))],
+    }
+}
+
+fn create_aggregate_function(metric: &Metric) -> Result<Expr, String> {
+    let func_name = aggregate_function_name(&metric.function);
+    let args = create_aggregate_args(metric);
+
+    Ok(Expr::Function(Function {
+        name: ObjectName(vec![sqlparser::ast::ObjectNamePart::Identifier(
+            Ident::new(func_name),
+        )]),
+        parameters: sqlparser::ast::FunctionArguments::None,
+        args: FunctionArguments::List(FunctionArgumentList {
+            duplicate_treatment: None,
+            args,
+            clauses: vec![],
+        }),
+        filter: None,
+        null_treatment: None,
+        over: None,
+        within_group: vec![],
+        uses_odbc_syntax: false,
+    }))
+}
+
+pub fn sql_to_node_graph(sql: &str) -> Result<NodeGraph, String> {
+    let dialect = DuckDbDialect {};
+    let mut statements =
+        Parser::parse_sql(&dialect, sql).map_err(|e| format!("Failed to parse SQL: {}", e))?;
+
+    let query = match statements.as_mut_slice() {
+        [Statement::Query(query)] => query,
+        [_] => return Err("Unsupported SQL: only SELECT statements can be imported".to_string()),
+        _ => return Err("Expected exactly one SQL statement".to_string()),
+    };
+    normalize_top(query);
+
+    if query.with.is_some() {
+        return Err("Unsupported SQL: CTEs (WITH clauses) are not supported".to_string());
+    }
+    if query.fetch.is_some() || !query.pipe_operators.is_empty() {
+        return Err("Unsupported SQL: only basic SELECT clauses are supported".to_string());
+    }
+
+    let select = match query.body.as_ref() {
+        SetExpr::Select(select) => select,
+        SetExpr::Query(_) => {
+            return Err("Unsupported SQL: subqueries are not supported".to_string())
+        }
+        _ => {
+            return Err(
+                "Unsupported SQL: only a single SELECT (no set operations) is supported"
+                    .to_string(),
+            )
+        }
+    };
+
+    if select.distinct.is_some() || select.top.is_some() {
+        return Err("Unsupported SQL: DISTINCT and TOP are not supported".to_string());
+    }
+    if select.having.is_some() || select.qualify.is_some() {
+        return Err("Unsupported SQL: HAVING and QUALIFY are not supported".to_string());
+    }
+
+    let mut nodes = vec![relation_to_node(select)?];
+
+    if let Some(selection) = &select.selection {
+        let mut conditions = Vec::new();
+        collect_filter_conditions(selection, &mut conditions)?;
+        nodes.push(("filter", serde_json::json!({ "conditions": conditions })));
+    }
+
+    let dimensions = match &select.group_by {
+        GroupByExpr::Expressions(exprs, modifiers) if modifiers.is_empty() => exprs
+            .iter()
+            .map(expr_to_column)
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => {
+            return Err("Unsupported SQL: GROUP BY ALL and modifiers are not supported".to_string())
+        }
+    };
+
+    let mut columns = Vec::new();
+    let mut metrics = Vec::new();
+    let mut has_wildcard = false;
+    for item in &select.projection {
+        match item {
+            SelectItem::Wildcard(_) => has_wildcard = true,
+            SelectItem::UnnamedExpr(Expr::Function(function)) => {
+                metrics.push(function_to_metric(function)?)
+            }
+            SelectItem::UnnamedExpr(expr) => columns.push(expr_to_column(expr)?),
+            _ => return Err(format!("Unsupported select item: {}", item)),
+        }
+    }
+
+    if !dimensions.is_empty() || !metrics.is_empty() {
+        if has_wildcard {
+            return Err("Unsupported SQL: * cannot be combined with aggregation".to_string());
+        }
+        if let Some(column) = columns.iter().find(|c| !dimensions.contains(c)) {
+            return Err(format!("Column must appear in GROUP BY: {}", column));
+        }
+        nodes.push((
+            "aggregation",
+            serde_json::json!({ "dimensions": dimensions, "metrics": metrics }),
+        ));
+    } else if !columns.is_empty() {
+        if has_wildcard {
+            return Err("Unsupported SQL: * cannot be combined with columns".to_string());
+        }
+        nodes.push(("select", serde_json::json!({ "columns": columns })));
+    }
+
+    if let Some(order_by) = &query.order_by {
+        let OrderByKind::Expressions(exprs) = &order_by.kind else {
+            return Err("Unsupported SQL: ORDER BY ALL is not supported".to_string());
+        };
+        let order = exprs
+            .iter()
+            .map(|o| {
+                if o.options.nulls_first.is_some() {
+                    return Err("Unsupported SQL: NULLS FIRST/LAST is not supported".to_string());
+                }
+                let direction = if o.options.asc == Some(false) {
+                    "desc"
+                } else {
+                    "asc"
+                };
+                Ok(serde_json::json!({ "column": expr_to_column(&o.expr)?, "direction": direction }))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        nodes.push(("sort", serde_json::json!({ "order": order })));
+    }
+
+    if let Some(limit_clause) = &query.limit_clause {
+        let limit = match limit_clause {
+            LimitClause::LimitOffset {
+                limit: Some(limit),
+                offset: None,
+                limit_by,
+            } if limit_by.is_empty() => expr_to_value(limit)?
+                .as_i64()
+                .ok_or_else(|| format!("Unsupported LIMIT value: {}", limit))?,
+            _ => return Err("Unsupported SQL: only a plain LIMIT is supported".to_string()),
+        };
+        nodes.push(("limit", serde_json::json!({ "limit": limit })));
+    }
+
+    let nodes: Vec<Node> = nodes
+        .into_iter()
+        .enumerate()
+        .map(|(i, (node_type, data))| Node {
+            id: (i + 1).to_string(),
+            node_type: node_type.to_string(),
+            data,
+        })
+        .collect();
+    let edges = nodes
+        .windows(2)
+        .map(|pair| Edge {
+            source: pair[0].id.clone(),
+            target: pair[1].id.clone(),
+        })
+        .collect();
+
+    Ok(NodeGraph {
+        selected_node_ids: vec![nodes.len().to_string()],
+        nodes,
+        edges,
+    })
+}
+
+// Note: SQL Server's SELECT TOP n becomes LIMIT n; TOP with PERCENT or WITH TIES stays unsupported
+fn normalize_top(query: &mut Query) {
+    if query.limit_clause.is_some() {
+        return;
+    }
+    let SetExpr::Select(select) = query.body.as_mut() else {
+        return;
+    };
+    let Some(Top {
+        with_ties: false,
+        percent: false,
+        quantity: Some(quantity),
+    }) = &select.top
+    else {
+        return;
+    };
+
+    let limit = match quantity {
+        TopQuantity::Constant(n) => Expr::Value(ValueWithSpan {
+            value: Value::Number(n.to_string(), false),
+            span: Span::empty(),
+        }),
+        TopQuantity::Expr(expr) => expr.clone(),
+    };
+    select.top = None;
+    query.limit_clause = Some(LimitClause::LimitOffset {
+        limit: Some(limit),
+        offset: None,
+        limit_by: Vec::new(),
+    });
+}
+
+fn relation_to_node(
+    select: &sqlparser::ast::Select,
+) -> Result<(&'static str, serde_json::Value), String> {
+    let table = match select.from.as_slice() {
+        [table] => table,
+        [] => return Err("Unsupported SQL: a FROM clause is required".to_string()),
+        _ => return Err("Unsupported SQL: multiple FROM tables are not supported".to_string()),
+    };
+    if !table.joins.is_empty() {
+        return Err("Unsupported SQL: joins are not supported".to_string());
+    }
+
+    let (name, args) = match &table.relation {
+        TableFactor::Table {
+            name,
+            alias: None,
+            args,
+            ..
+        } => (name, args.as_ref().map(|a| a.args.as_slice())),
+        TableFactor::Function {
+            name,
+            args,
+            alias: None,
+            ..
+        } => (name, Some(args.as_slice())),
+        TableFactor::Derived { .. } => {
+            return Err("Unsupported SQL: subqueries are not supported".to_string())
+        }
+        relation => return Err(format!("Unsupported table expression: {}", relation)),
+    };
+
+    let parts = name
+        .0
+        .iter()
+        .map(|part| part.as_ident().map(|ident| ident.value.clone()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| format!("Unsupported table name: {}", name))?;
+
+    match (parts.as_slice(), args) {
+        ([function_name], Some(args)) => {
+            let args = args
+                .iter()
+                .map(|arg| match arg {
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => expr_to_value(expr),
+                    _ => Err(format!("Unsupported table function argument: {}", arg)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((
+                "table_function",
+                serde_json::json!({ "function_name": function_name, "args": args }),
+            ))
+        }
+        ([table_name], None) => Ok(("table", serde_json::json!({ "table_name": table_name }))),
+        ([schema, table_name], None) => Ok((
+            "table",
+            serde_json::json!({ "table_name": table_name, "schema": schema }),
+        )),
+        _ => Err(format!("Unsupported table name: {}", name)),
+    }
+}
+
+fn collect_filter_conditions(
+    expr: &Expr,
+    conditions: &mut Vec<serde_json::Value>,
+) -> Result<(), String> {
+    match expr {
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            collect_filter_conditions(left, conditions)?;
+            collect_filter_conditions(right, conditions)
+        }
+        Expr::Nested(inner) => collect_filter_conditions(inner, conditions),
+        _ => {
+            conditions.push(expr_to_condition(expr, false)?);
+            Ok(())
+        }
+    }
+}
+
+fn expr_to_condition(expr: &Expr, negate: bool) -> Result<serde_json::Value, String> {
+    let (column, operator, value) = match expr {
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => return expr_to_condition(expr, !negate),
+        Expr::Nested(inner) => return expr_to_condition(inner, negate),
+        Expr::BinaryOp { left, op, right } => {
+            let operator = match op {
+                BinaryOperator::Eq => "==",
+                BinaryOperator::NotEq => "!=",
+                BinaryOperator::Gt => ">",
+                BinaryOperator::Lt => "<",
+                BinaryOperator::GtEq => ">=",
+                BinaryOperator::LtEq => "<=",
+                _ => return Err(format!("Unsupported filter expression: {}", expr)),
+            };
+            (expr_to_column(left)?, operator, expr_to_value(right)?)
+        }
+        Expr::InList {
+            expr: column,
+            list,
+            negated,
+        } => {
+            let values = list
+                .iter()
+                .map(expr_to_value)
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(serde_json::json!({
+                "column": expr_to_column(column)?,
+                "operator": "in",
+                "value": values,
+                "negate": negate != *negated,
+            }));
+        }
+        Expr::Subquery(_) | Expr::InSubquery { .. } | Expr::Exists { .. } => {
+            return Err("Unsupported SQL: subqueries are not supported".to_string())
+        }
+        _ => return Err(format!("Unsupported filter expression: {}", expr)),
+    };
+
+    Ok(serde_json::json!({
+        "column": column,
+        "operator": operator,
+        "value": value,
+        "negate": negate,
+    }))
+}
+
+fn expr_to_column(expr: &Expr) -> Result<String, String> {
+    match expr {
+        Expr::Identifier(ident) => Ok(ident.value.clone()),
+        Expr::Subquery(_) => Err("Unsupported SQL: subqueries are not supported".to_string()),
+        _ => Err(format!("Expected a column name, found: {}", expr)),
+    }
+}
+
+pub fn expr_to_value(expr: &Expr) -> Result<serde_json::Value, String> {
+    match expr {
+        Expr::Value(ValueWithSpan { value, .. }) => match value {
+            Value::SingleQuotedString(s) => Ok(serde_json::Value::String(s.clone())),
+            Value::Boolean(b) => Ok(serde_json::Value::Bool(*b)),
+            Value::Number(n, _) => number_to_value(n),
+            _ => Err(format!("Unsupported value: {}", value)),
+        },
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr: inner,
+        } => match inner.as_ref() {
+            Expr::Value(ValueWithSpan {
+                value: Value::Number(n, _),
+                ..
+            }) => number_to_value(&format!("-{}", n)),
+            _ => Err(format!("Unsupported value: {}", expr)),
+        },
+        Expr::Cast {
+            expr: inner,
+            data_type: DataType::JSON,
+            ..
+        } => match inner.as_ref() {
+            Expr::Value(ValueWithSpan {
+                value: Value::SingleQuotedString(s),
+                ..
+            }) => match serde_json::from_str(s) {
+                Ok(object @ serde_json::Value::Object(_)) => Ok(object),
+                _ => Err(format!("Unsupported value: {}", expr)),
+            },
+            _ => Err(format!("Unsupported value: {}", expr)),
+        },
+        _ => Err(format!("Unsupported value: {}", expr)),
+    }
+}
+
+fn number_to_value(n: &str) -> Result<serde_json::Value, String> {
+    if let Ok(i) = n.parse::<i64>() {
+        return Ok(serde_json::Value::from(i));
+    }
+    n.parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(serde_json::Value::Number)
+        .ok_or_else(|| format!("Unsupported number: {}", n))
+}
+
+fn function_to_metric(function: &Function) -> Result<serde_json::Value, String> {
+    let name = function.name.to_string().to_uppercase();
+    if !["COUNT", "SUM", "AVG", "MAX", "MIN"].contains(&name.as_str()) {
+        return Err(format!("Unsupported function: {}", function.name));
+    }
+    if function.filter.is_some() || function.over.is_some() {
+        return Err(format!("Unsupported aggregate: {}", function));
+    }
+
+    let args = match &function.args {
+        FunctionArguments::List(list) if list.duplicate_treatment.is_none() => &list.args,
+        _ => return Err(format!("Unsupported aggregate: {}", function)),
+    };
+
+    match args.as_slice() {
+        [FunctionArg::Unnamed(FunctionArgExpr::Wildcard)] if name == "COUNT" => {
+            Ok(serde_json::json!({ "function": "COUNT(*)", "column": "" }))
+        }
+        [FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))] => {
+            Ok(serde_json::json!({ "function": name, "column": expr_to_column(expr)? }))
+        }
+        _ => Err(format!("Unsupported aggregate: {}", function)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_generate_sql_table_only() {
+        let json = r#"{
+            "selected_node_id": "1",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}}
+            ],
+            "edges": []
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(sql, "SELECT * FROM users");
+    }
+
+    #[test]
+    fn test_generate_sql_table_with_schema() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users", "schema": "analytics"}},
+                {"id": "2", "type": "select", "data": {"columns": ["id", "name"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(sql, "SELECT id, name FROM analytics.users");
+    }
+
+    #[test]
+    fn test_generate_sql_invalid_schema() {
+        let json = r#"{
+            "selected_node_id": "1",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users", "schema": "main; DROP TABLE users"}}
+            ],
+            "edges": []
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let result = generate_sql(&node_graph, None);
+
+        assert_eq!(
+            result.unwrap_err(),
+            "Invalid schema name: main; DROP TABLE users"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_table_function() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table_function", "data": {"function_name": "read_csv_auto", "args": ["data/o'brien.csv"]}},
+                {"id": "2", "type": "limit", "data": {"limit": 10}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM read_csv_auto('data/o''brien.csv') LIMIT 10"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_table_function_numeric_args() {
+        let json = r#"{
+            "selected_node_id": "1",
+            "nodes": [
+                {"id": "1", "type": "table_function", "data": {"function_name": "range", "args": [0, 100, 10]}}
+            ],
+            "edges": []
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(sql, "SELECT * FROM range(0, 100, 10)");
+    }
+
+    #[test]
+    fn test_generate_sql_table_function_not_allowed() {
+        let json = r#"{
+            "selected_node_id": "1",
+            "nodes": [
+                {"id": "1", "type": "table_function", "data": {"function_name": "read_text", "args": ["/etc/passwd"]}}
+            ],
+            "edges": []
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let result = generate_sql(&node_graph, None);
+
+        assert_eq!(result.unwrap_err(), "Unsupported table function: read_text");
+    }
+
+    #[test]
+    fn test_generate_sql_table_with_select_and_limit() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "select", "data": {"columns": ["id", "name"]}},
+                {"id": "3", "type": "limit", "data": {"limit": 10}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(sql, "SELECT id, name FROM users LIMIT 10");
+    }
+
+    #[test]
+    fn test_generate_sql_table_with_select_sort_and_limit() {
+        let json = r#"{
+            "selected_node_id": "4",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "select", "data": {"columns": ["id", "name"]}},
+                {"id": "3", "type": "sort", "data": {"order": [{"column": "id", "direction": "desc"}]}},
+                {"id": "4", "type": "limit", "data": {"limit": 5}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(sql, "SELECT id, name FROM users ORDER BY id DESC LIMIT 5");
+    }
+
+    #[test]
+    fn test_generate_sql_sort_with_random_seed() {
+        let graph = |sort_data: &str| {
+            let json = format!(
+                r#"{{
+                    "selected_node_id": "2",
+                    "nodes": [
+                        {{"id": "1", "type": "table", "data": {{"table_name": "users", "schema": "crm"}}}},
+                        {{"id": "2", "type": "sort", "data": {}}}
+                    ],
+                    "edges": [{{"source": "1", "target": "2"}}]
+                }}"#,
+                sort_data
+            );
+            serde_json::from_str::<NodeGraph>(&json).unwrap()
+        };
+
+        let node_graph = graph(r#"{"order": [], "random_seed": 42}"#);
+        assert_eq!(
+            generate_sql(&node_graph, None).unwrap(),
+            "SELECT * FROM crm.users ORDER BY hash(users.rowid, 42)"
+        );
+        assert_eq!(
+            explain_node_graph(&node_graph).unwrap(),
+            "Takes the `crm.users` table and shuffles rows with seed 42."
+        );
+
+        let node_graph =
+            graph(r#"{"order": [{"column": "id", "direction": "asc"}], "random_seed": 42}"#);
+        assert_eq!(
+            generate_sql(&node_graph, None).unwrap(),
+            "SELECT * FROM crm.users ORDER BY id ASC"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_random_sort_without_rowid() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["status"],
+                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                }},
+                {"id": "3", "type": "sort", "data": {"random_seed": 7}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let result = generate_sql(&node_graph, None);
+
+        assert_eq!(
+            result,
+            Err("Sort with a random seed needs rows read straight from a table, not from an aggregation, a table function or a subquery.".to_string())
+        );
+
+        // Note: A shared node above the shuffle stays inline so the shuffle still sees the rowid
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "status", "operator": "==", "value": "paid"}
+                ]}},
+                {"id": "3", "type": "sort", "data": {"random_seed": 7}},
+                {"id": "4", "type": "limit", "data": {"limit": 10}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "2", "target": "4"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM orders WHERE status = 'paid' ORDER BY hash(orders.rowid, 7)"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_table_with_multiple_order() {
+        let json = r#"{
+            "selected_node_id": "4",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "select", "data": {"columns": ["id", "customer", "total"]}},
+                {"id": "3", "type": "sort", "data": {
+                    "order": [
+                        {"column": "customer", "direction": "asc"},
+                        {"column": "total", "direction": "desc"}
+                    ]
+                }},
+                {"id": "4", "type": "limit", "data": {"limit": 20}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT id, customer, total FROM orders ORDER BY customer ASC, total DESC LIMIT 20"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_select_table_node() {
+        let json = r#"{
+            "selected_node_id": "1",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "select", "data": {"columns": ["id", "name"]}},
+                {"id": "3", "type": "limit", "data": {"limit": 10}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(sql, "SELECT * FROM products");
+    }
+
+    #[test]
+    fn test_generate_sql_order_independent() {
+        let json = r#"{
+            "selected_node_id": "4",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "limit", "data": {"limit": 10}},
+                {"id": "3", "type": "sort", "data": {"order": [{"column": "name", "direction": "asc"}]}},
+                {"id": "4", "type": "select", "data": {"columns": ["id", "name"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(sql, "SELECT id, name FROM users ORDER BY name ASC LIMIT 10");
+    }
+
+    #[test]
+    fn test_generate_sql_with_single_filter() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">=", "value": 1000}]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(sql, "SELECT * FROM users WHERE price >= 1000");
+    }
+
+    #[test]
+    fn test_generate_sql_with_multiple_filters() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "price", "operator": ">=", "value": 1000},
+                    {"column": "city", "operator": "==", "value": "Tokyo"}
+                ]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM users WHERE price >= 1000 AND city = 'Tokyo'"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_with_in_operator() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "name", "operator": "in", "value": ["Taro", "Jiro", "Saburo"]}
+                ]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM users WHERE name IN ('Taro', 'Jiro', 'Saburo')"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_with_negated_condition() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "city", "operator": "==", "value": "Tokyo", "negate": true}
+                ]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(sql, "SELECT * FROM users WHERE NOT city = 'Tokyo'");
+    }
+
+    #[test]
+    fn test_typed_value_expr() {
+        let cases = [
+            (serde_json::json!("42"), "INTEGER", "42"),
+            (serde_json::json!(" -1.5 "), "double", "-1.5"),
+            (serde_json::json!("3.10"), "DECIMAL(9,2)", "3.10"),
+            (serde_json::json!("True"), "BOOLEAN", "true"),
+            (serde_json::json!("42"), "VARCHAR", "'42'"),
+            (serde_json::json!("1 day"), "INTERVAL", "'1 day'"),
+            (serde_json::json!(42), "VARCHAR", "42"),
+        ];
+        for (value, column_type, expected) in cases {
+            assert_eq!(
+                typed_value_expr(&value, column_type).unwrap().to_string(),
+                expected
+            );
+        }
+
+        for (value, column_type) in [("abc", "BIGINT"), ("inf", "DOUBLE"), ("yes", "BOOLEAN")] {
+            assert_eq!(
+                typed_value_expr(&serde_json::json!(value), column_type).unwrap_err(),
+                format!("Cannot convert '{}' to {}", value, column_type)
+            );
+        }
+    }
+
+    #[test]
+    fn test_referenced_columns() {
+        let node = |json: &str| -> Node { serde_json::from_str(json).unwrap() };
+
+        assert_eq!(
+            referenced_columns(&node(
+                r#"{"id": "1", "type": "select", "data": {"columns": ["id", "name"]}}"#
+            ))
+            .unwrap(),
+            vec!["id", "name"]
+        );
+        assert_eq!(
+            referenced_columns(&node(
+                r#"{"id": "1", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 1}]}}"#
+            ))
+            .unwrap(),
+            vec!["price"]
+        );
+        assert_eq!(
+            referenced_columns(&node(
+                r#"{"id": "1", "type": "sort", "data": {"order": [{"column": "ts", "direction": "desc"}]}}"#
+            ))
+            .unwrap(),
+            vec!["ts"]
+        );
+        assert!(referenced_columns(&node(
+            r#"{"id": "1", "type": "table", "data": {"table_name": "orders"}}"#
+        ))
+        .unwrap()
+        .is_empty());
+        assert!(referenced_columns(&node(
+            r#"{"id": "1", "type": "select", "data": {"columns": "id"}}"#
+        ))
+        .unwrap_err()
+        .starts_with("Failed to parse select node data:"));
+    }
+
+    #[test]
+    fn test_source_table() {
+        let node_graph = shared_source_graph("4");
+        assert_eq!(
+            source_table(&node_graph).unwrap(),
+            Some("orders".to_string())
+        );
+
+        let json = r#"{
+            "selected_node_id": "1",
+            "nodes": [
+                {"id": "1", "type": "table_function", "data": {"function_name": "range", "args": [3]}}
+            ],
+            "edges": []
+        }"#;
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        assert_eq!(source_table(&node_graph).unwrap(), None);
+    }
+
+    #[test]
+    fn test_clone_node() {
+        let node: Node = serde_json::from_str(
+            r#"{"id": "1", "type": "filter", "data": {"conditions": [{"column": "status", "operator": "=", "value": "paid"}]}}"#,
+        )
+        .unwrap();
+
+        let mut cloned = clone_node(&node, "2".to_string());
+        assert_eq!(cloned.id, "2");
+        assert_eq!(cloned.node_type, "filter");
+        assert_eq!(cloned.data, node.data);
+
+        cloned.data["conditions"][0]["value"] = serde_json::json!("refunded");
+        assert_eq!(node.id, "1");
+        assert_eq!(node.data["conditions"][0]["value"], "paid");
+    }
+
+    #[test]
+    fn test_generate_sql_filter_with_select_sort_limit() {
+        let json = r#"{
+            "selected_node_id": "5",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 100}]}},
+                {"id": "3", "type": "select", "data": {"columns": ["id", "name", "price"]}},
+                {"id": "4", "type": "sort", "data": {"order": [{"column": "price", "direction": "desc"}]}},
+                {"id": "5", "type": "limit", "data": {"limit": 10}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"},
+                {"source": "4", "target": "5"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT id, name, price FROM products WHERE price > 100 ORDER BY price DESC LIMIT 10"
+        );
+    }
+
+    #[test]
+    fn test_aggregation_basic() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                }}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT category, COUNT(*) FROM products GROUP BY category"
+        );
+    }
+
+    #[test]
+    fn test_aggregation_multiple_metrics() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [
+                        {"function": "COUNT(*)", "column": ""},
+                        {"function": "SUM", "column": "price"},
+                        {"function": "AVG", "column": "price"}
+                    ]
+                }}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT category, COUNT(*), SUM(price), AVG(price) FROM products GROUP BY category"
+        );
+    }
+
+    #[test]
+    fn test_aggregation_multiple_dimensions() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["category", "region"],
+                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                }}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT category, region, COUNT(*) FROM products GROUP BY category, region"
+        );
+    }
+
+    #[test]
+    fn test_aggregation_with_filter() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 100}]}},
+                {"id": "3", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                }}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT category, COUNT(*) FROM products WHERE price > 100 GROUP BY category"
+        );
+    }
+
+    #[test]
+    fn test_aggregation_then_filter() {
+        let json = r#"{
+            "selected_node_id": "5",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 100}]}},
+                {"id": "3", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                }},
+                {"id": "4", "type": "filter", "data": {"conditions": [{"column": "category", "operator": "!=", "value": "misc"}]}},
+                {"id": "5", "type": "sort", "data": {"order": [{"column": "category", "direction": "asc"}]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"},
+                {"source": "4", "target": "5"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT category, COUNT(*) FROM products WHERE price > 100 GROUP BY category) AS aggregated WHERE category <> 'misc' ORDER BY category ASC"
+        );
+    }
+
+    #[test]
+    fn test_aggregation_then_limit_then_filter() {
+        let json = r#"{
+            "selected_node_id": "4",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["customer_id"],
+                    "metrics": [{"function": "SUM", "column": "amount"}]
+                }},
+                {"id": "3", "type": "limit", "data": {"limit": 10}},
+                {"id": "4", "type": "filter", "data": {"conditions": [{"column": "customer_id", "operator": ">", "value": 5}]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, Some((50, 0))).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT * FROM (SELECT customer_id, SUM(amount) FROM orders GROUP BY customer_id LIMIT 10) AS aggregated WHERE customer_id > 5) AS subquery LIMIT 50 OFFSET 0"
+        );
+    }
+
+    #[test]
+    fn test_aggregation_then_filter_on_metric() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["customer_id"],
+                    "metrics": [
+                        {"function": "SUM", "column": "amount"},
+                        {"function": "COUNT(*)", "column": ""}
+                    ]
+                }},
+                {"id": "3", "type": "filter", "data": {"conditions": [
+                    {"column": "sum(amount)", "operator": ">", "value": 10},
+                    {"column": "count_star()", "operator": ">=", "value": 2},
+                    {"column": "customer_id", "operator": "!=", "value": 1}
+                ]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let expected = r#"SELECT * FROM (SELECT customer_id, SUM(amount), COUNT(*) FROM orders GROUP BY customer_id) AS aggregated WHERE "sum(amount)" > 10 AND "count_star()" >= 2 AND customer_id <> 1"#;
+
+        assert_eq!(generate_sql(&node_graph, None).unwrap(), expected);
+        let options = QueryOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            generate_sql_with_options(&node_graph, None, options).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_drop_duplicates() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "events"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "kind", "operator": "==", "value": "click"}]}},
+                {"id": "3", "type": "drop_duplicates", "data": {"columns": ["user_id", "session_id"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        assert!(validate_node_graph_schema(json).is_ok());
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM events WHERE kind = 'click' QUALIFY ROW_NUMBER() OVER (PARTITION BY user_id, session_id ORDER BY rowid) = 1"
+        );
+    }
+
+    #[test]
+    fn test_drop_duplicates_table_function() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table_function", "data": {"function_name": "range", "args": [10]}},
+                {"id": "2", "type": "drop_duplicates", "data": {"columns": ["range"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM range(10) QUALIFY ROW_NUMBER() OVER (PARTITION BY range) = 1"
+        );
+    }
+
+    #[test]
+    fn test_drop_duplicates_then_filter() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "events"}},
+                {"id": "2", "type": "drop_duplicates", "data": {"columns": ["user_id"]}},
+                {"id": "3", "type": "filter", "data": {"conditions": [{"column": "page", "operator": "==", "value": "pricing"}]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT * FROM events QUALIFY ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY rowid) = 1) AS upstream WHERE page = 'pricing'"
+        );
+    }
+
+    #[test]
+    fn test_limit_then_drop_duplicates() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "events"}},
+                {"id": "2", "type": "limit", "data": {"limit": 100}},
+                {"id": "3", "type": "drop_duplicates", "data": {"columns": ["user_id"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT * FROM events LIMIT 100) AS upstream QUALIFY ROW_NUMBER() OVER (PARTITION BY user_id) = 1"
+        );
+    }
+
+    #[test]
+    fn test_drop_duplicates_with_aggregation() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "events"}},
+                {"id": "2", "type": "drop_duplicates", "data": {"columns": ["user_id"]}},
+                {"id": "3", "type": "aggregation", "data": {"metrics": [{"function": "COUNT(*)"}]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let err = generate_sql(&node_graph, None).unwrap_err();
+
+        assert_eq!(
+            err,
+            "Cannot combine Aggregation and Drop Duplicates nodes in one query."
+        );
+    }
+
+    #[test]
+    fn test_natural_join() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "a"}},
+                {"id": "2", "type": "join", "data": {"table_name": "b", "join_type": "natural"}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        assert!(validate_node_graph_schema(json).is_ok());
+        assert_eq!(
+            generate_sql(&node_graph, None).unwrap(),
+            "SELECT * FROM a NATURAL JOIN b"
+        );
+        assert_eq!(
+            joined_tables(&node_graph).unwrap(),
+            vec![(None, "a".to_string()), (None, "b".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_natural_join_with_schema_and_filter() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders", "schema": "sales"}},
+                {"id": "2", "type": "join", "data": {"table_name": "customers", "schema": "crm", "join_type": "natural"}},
+                {"id": "3", "type": "filter", "data": {"conditions": [{"column": "country", "operator": "==", "value": "JP"}]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            generate_sql(&node_graph, None).unwrap(),
+            "SELECT * FROM sales.orders NATURAL JOIN crm.customers WHERE country = 'JP'"
+        );
+    }
+
+    #[test]
+    fn test_anti_join() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "customers"}},
+                {"id": "2", "type": "join", "data": {"table_name": "orders", "schema": "sales", "join_type": "anti", "keys": ["customer_id", "region"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        assert!(validate_node_graph_schema(json).is_ok());
+        assert_eq!(
+            generate_sql(&node_graph, None).unwrap(),
+            "SELECT * FROM customers ANTI JOIN sales.orders USING(customer_id, region)"
+        );
+        assert_eq!(
+            explain_node_graph(&node_graph).unwrap(),
+            "Takes the `customers` table and keeps rows with no match in `sales.orders` on `customer_id, region`."
+        );
+    }
+
+    #[test]
+    fn test_join_keys_validation() {
+        let graph = |data: serde_json::Value| -> NodeGraph {
+            serde_json::from_value(serde_json::json!({
+                "selected_node_id": "2",
+                "nodes": [
+                    {"id": "1", "type": "table", "data": {"table_name": "a"}},
+                    {"id": "2", "type": "join", "data": data}
+                ],
+                "edges": [{"source": "1", "target": "2"}]
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(
+            generate_sql(
+                &graph(serde_json::json!({"table_name": "b", "join_type": "anti"})),
+                None
+            )
+            .unwrap_err(),
+            "Anti join requires at least one key column"
+        );
+        assert_eq!(
+            generate_sql(
+                &graph(
+                    serde_json::json!({"table_name": "b", "join_type": "natural", "keys": ["id"]})
+                ),
+                None
+            )
+            .unwrap_err(),
+            "Natural join does not take key columns"
+        );
+    }
+
+    #[test]
+    fn test_join_type_required() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "a"}},
+                {"id": "2", "type": "join", "data": {"table_name": "b", "join_type": "left"}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        assert!(validate_node_graph_schema(json).is_err());
+        assert!(parse_node_graph(json)
+            .unwrap_err()
+            .starts_with("Invalid node graph:"));
+        assert!(joined_tables(
+            &serde_json::from_str(
+                r#"{
+                "selected_node_id": "1",
+                "nodes": [{"id": "1", "type": "table", "data": {"table_name": "a"}}],
+                "edges": []
+            }"#
+            )
+            .unwrap()
+        )
+        .unwrap()
+        .is_empty());
+    }
+
+    #[test]
+    fn test_aggregation_then_select() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                }},
+                {"id": "3", "type": "select", "data": {"columns": ["category"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT category, COUNT(*) FROM products GROUP BY category"
+        );
+    }
+
+    #[test]
+    fn test_select_then_aggregation_error() {
+        let json = r#"{
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "select", "data": {"columns": ["id", "name"]}},
+                {"id": "3", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                }}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let result = generate_sql(&node_graph, None);
+
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err(),
+            "Cannot use Aggregation after Select node. Please remove the Select node or reorder the nodes."
+        );
+    }
+
+    #[test]
+    fn test_aggregation_all_functions() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [
+                        {"function": "COUNT", "column": "id"},
+                        {"function": "SUM", "column": "price"},
+                        {"function": "AVG", "column": "price"},
+                        {"function": "MAX", "column": "price"},
+                        {"function": "MIN", "column": "price"}
+                    ]
+                }}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT category, COUNT(id), SUM(price), AVG(price), MAX(price), MIN(price) FROM products GROUP BY category"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_cycle_error() {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "limit", "data": {"limit": 10}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "1"}
+            ]
+        }"#;
+
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let result = generate_sql(&node_graph, None);
+
+        assert_eq!(result.unwrap_err(), "Cycle detected at node: 2");
+    }
+
+    fn middle_selected_graph() -> NodeGraph {
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "age", "operator": ">", "value": 18}]}},
+                {"id": "3", "type": "select", "data": {"columns": ["id", "name"]}},
+                {"id": "4", "type": "limit", "data": {"limit": 5}},
+                {"id": "5", "type": "barChart", "data": {"xAxis": "id", "yAxis": "name"}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"},
+                {"source": "4", "target": "5"}
+            ]
+        }"#;
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_upstream_only_excludes_downstream_nodes() {
+        let node_graph = middle_selected_graph();
+
+        let expected = "SELECT * FROM users WHERE age > 18";
+        assert_eq!(generate_sql(&node_graph, None).unwrap(), expected);
+        assert_eq!(
+            generate_sql_with_options(
+                &node_graph,
+                None,
+                QueryOptions {
+                    upstream_only: true,
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_upstream_only_false_follows_downstream_chain() {
+        let node_graph = middle_selected_graph();
+
+        let sql = generate_sql_with_options(
+            &node_graph,
+            None,
+            QueryOptions {
+                upstream_only: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(sql, "SELECT id, name FROM users WHERE age > 18 LIMIT 5");
+    }
+
+    #[test]
+    fn test_upstream_only_false_stops_at_branch() {
+        let json = r#"{
+            "selected_node_id": "1",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "limit", "data": {"limit": 5}},
+                {"id": "3", "type": "limit", "data": {"limit": 10}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "1", "target": "3"}
+            ]
+        }"#;
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+
+        let sql = generate_sql_with_options(
+            &node_graph,
+            None,
+            QueryOptions {
+                upstream_only: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(sql, "SELECT * FROM users");
+    }
+
+    #[test]
+    fn test_use_cte_for_pagination() {
+        let cte = QueryOptions {
+            use_cte_for_pagination: true,
+            ..Default::default()
+        };
+
+        let node_graph = middle_selected_graph();
+        let sql = generate_sql_with_options(&node_graph, Some((50, 100)), cte).unwrap();
+        assert_eq!(
+            sql,
+            "WITH paginated AS (SELECT * FROM users WHERE age > 18) SELECT * FROM paginated LIMIT 50 OFFSET 100"
+        );
+        assert_eq!(
+            strip_pagination_wrapper(&sql).unwrap(),
+            "SELECT * FROM users WHERE age > 18"
+        );
+        assert_eq!(
+            generate_sql_with_options(&node_graph, None, cte).unwrap(),
+            "SELECT * FROM users WHERE age > 18"
+        );
+
+        let json = r#"{
+            "selected_node_id": "2",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "amount", "operator": ">", "value": 100}]}},
+                {"id": "3", "type": "limit", "data": {"limit": 5}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "1", "target": "3"}
+            ]
+        }"#;
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let sql = generate_sql_with_options(&node_graph, Some((10, 0)), cte).unwrap();
+        assert_eq!(
+            sql,
+            "WITH cte_1 AS (SELECT * FROM orders), paginated AS (SELECT * FROM cte_1 WHERE amount > 100) SELECT * FROM paginated LIMIT 10 OFFSET 0"
+        );
+        assert_eq!(
+            strip_pagination_wrapper(&sql).unwrap(),
+            generate_sql(&node_graph, None).unwrap()
+        );
+    }
+
+    fn expression_graph(select_column: &str) -> NodeGraph {
+        let json = serde_json::json!({
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "amount", "operator": ">", "value": 10}
+                ]}},
+                {"id": "3", "type": "select", "data": {"columns": ["id", select_column]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
+            ]
+        });
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_expressions() {
+        let strict = QueryOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        let node_graph = expression_graph("upper(name)");
+        assert_eq!(
+            generate_sql(&node_graph, None).unwrap(),
+            "SELECT id, upper(name) FROM orders WHERE amount > 10"
+        );
+        assert_eq!(
+            generate_sql_with_options(&node_graph, None, strict).unwrap_err(),
+            "Expressions are not allowed in strict mode: upper(name)"
+        );
+
+        let node_graph = expression_graph("name");
+        assert_eq!(
+            generate_sql_with_options(&node_graph, None, strict).unwrap(),
+            "SELECT id, name FROM orders WHERE amount > 10"
+        );
+    }
+
+    #[test]
+    fn test_strict_mode_checks_every_name() {
+        let strict = QueryOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let graph = |node_type: &str, data: serde_json::Value| -> NodeGraph {
+            serde_json::from_value(serde_json::json!({
+                "selected_node_id": "2",
+                "nodes": [
+                    {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                    {"id": "2", "type": node_type, "data": data}
+                ],
+                "edges": [{"source": "1", "target": "2"}]
+            }))
+            .unwrap()
+        };
+
+        let cases = [
+            (
+                graph(
+                    "filter",
+                    serde_json::json!({"conditions": [
+                        {"column": "1 = 1 OR amount", "operator": ">", "value": 0}
+                    ]}),
+                ),
+                "1 = 1 OR amount",
+            ),
+            (
+                graph(
+                    "sort",
+                    serde_json::json!({"order": [{"column": "random()", "direction": "asc"}]}),
+                ),
+                "random()",
+            ),
+            (
+                graph(
+                    "aggregation",
+                    serde_json::json!({
+                        "dimensions": ["region"],
+                        "metrics": [{"function": "SUM", "column": "amount * 2"}]
+                    }),
+                ),
+                "amount * 2",
+            ),
+        ];
+        for (node_graph, expression) in cases {
+            assert!(generate_sql(&node_graph, None).is_ok());
+            assert_eq!(
+                generate_sql_with_options(&node_graph, None, strict).unwrap_err(),
+                format!("Expressions are not allowed in strict mode: {}", expression)
+            );
+        }
+
+        let node_graph = graph(
+            "aggregation",
+            serde_json::json!({
+                "dimensions": ["region"],
+                "metrics": [{"function": "COUNT(*)", "column": ""}]
+            }),
+        );
+        assert_eq!(
+            generate_sql_with_options(&node_graph, None, strict).unwrap(),
+            "SELECT region, COUNT(*) FROM orders GROUP BY region"
+        );
+    }
+
+    fn shared_source_graph(selected_node_id: &str) -> NodeGraph {
+        let json = r#"{
+            "selected_node_id": "",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "status", "operator": "==", "value": "paid"}
+                ]}},
+                {"id": "3", "type": "filter", "data": {"conditions": [
+                    {"column": "amount", "operator": ">", "value": 100}
+                ]}},
+                {"id": "4", "type": "aggregation", "data": {
+                    "dimensions": ["region"],
+                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                }},
+                {"id": "5", "type": "limit", "data": {"limit": 10}},
+                {"id": "6", "type": "bar_chart", "data": {}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "1", "target": "3"},
+                {"source": "3", "target": "4"},
+                {"source": "3", "target": "5"},
+                {"source": "2", "target": "6"}
+            ]
+        }"#;
+        let mut node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        node_graph.selected_node_ids = vec![selected_node_id.to_string()];
+        node_graph
+    }
+
+    #[test]
+    fn test_generate_sql_shared_source_becomes_cte() {
+        let sql = generate_sql(&shared_source_graph("2"), None).unwrap();
+        assert_eq!(
+            sql,
+            "WITH cte_1 AS (SELECT * FROM orders) SELECT * FROM cte_1 WHERE status = 'paid'"
+        );
+
+        let sql = generate_sql(&shared_source_graph("3"), Some((50, 0))).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM (WITH cte_1 AS (SELECT * FROM orders) SELECT * FROM cte_1 WHERE amount > 100) AS subquery LIMIT 50 OFFSET 0"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_nested_shared_nodes() {
+        let sql = generate_sql(&shared_source_graph("4"), None).unwrap();
+        assert_eq!(
+            sql,
+            "WITH cte_1 AS (SELECT * FROM orders), cte_2 AS (SELECT * FROM cte_1 WHERE amount > 100) SELECT region, COUNT(*) FROM cte_2 GROUP BY region"
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_shared_node_at_end_has_no_cte() {
+        let sql = generate_sql(&shared_source_graph("1"), None).unwrap();
+        assert_eq!(sql, "SELECT * FROM orders");
+
+        // Note: Only query nodes count as branches, so the chart below node 2 adds no CTE
+        let mut node_graph = shared_source_graph("2");
+        node_graph.edges.retain(|e| e.target != "3");
+        let sql = generate_sql(&node_graph, None).unwrap();
+        assert_eq!(sql, "SELECT * FROM orders WHERE status = 'paid'");
+    }
+
+    fn limited_graph(next: serde_json::Value, with_sibling: bool) -> NodeGraph {
+        let mut node_graph: NodeGraph = serde_json::from_value(serde_json::json!({
+            "selected_node_id": "3",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "limit", "data": {"limit": 10}},
+                next,
+                {"id": "4", "type": "select", "data": {"columns": ["id"]}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "2", "target": "4"}
+            ]
+        }))
+        .unwrap();
+        if !with_sibling {
+            node_graph.edges.retain(|e| e.target != "4");
+        }
+        node_graph
+    }
+
+    #[test]
+    fn test_generate_sql_shared_node_keeps_results() {
+        let filter = serde_json::json!({"id": "3", "type": "filter", "data": {"conditions": [
+            {"column": "status", "operator": "==", "value": "paid"}
+        ]}});
+        assert_eq!(
+            generate_sql(&limited_graph(filter.clone(), false), None).unwrap(),
+            "SELECT * FROM (SELECT * FROM orders LIMIT 10) AS upstream WHERE status = 'paid'"
+        );
+        assert_eq!(
+            generate_sql(&limited_graph(filter, true), None).unwrap(),
+            "WITH cte_1 AS (SELECT * FROM orders LIMIT 10) SELECT * FROM cte_1 WHERE status = 'paid'"
+        );
+
+        // Note: A sort merges with the limit above it, so cutting between them would change the rows
+        let sort = serde_json::json!({"id": "3", "type": "sort", "data": {"order": [
+            {"column": "amount", "direction": "desc"}
+        ]}});
+        let expected = "SELECT * FROM orders ORDER BY amount DESC LIMIT 10";
+        assert_eq!(
+            generate_sql(&limited_graph(sort.clone(), false), None).unwrap(),
+            expected
+        );
+        assert_eq!(
+            generate_sql(&limited_graph(sort, true), None).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_generate_sql_checks_node_order_across_ctes() {
+        let json = r#"{
+            "selected_node_id": "4",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "select", "data": {"columns": ["region", "amount"]}},
+                {"id": "3", "type": "filter", "data": {"conditions": [
+                    {"column": "amount", "operator": ">", "value": 100}
+                ]}},
+                {"id": "4", "type": "aggregation", "data": {
+                    "dimensions": ["region"],
+                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                }},
+                {"id": "5", "type": "limit", "data": {"limit": 10}}
+            ],
+            "edges": [
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"},
+                {"source": "2", "target": "5"}
+            ]
+        }"#;
 
-    Ok(Expr::Function(Function {
-        name: ObjectName(vec![sqlparser::ast::ObjectNamePart::Identifier(
-            Ident::new(func_name),
-        )]),
-        parameters: sqlparser::ast::FunctionArguments::None,
-        args: FunctionArguments::List(FunctionArgumentList {
-            duplicate_treatment: None,
-            args,
-            clauses: vec![],
-        }),
-        filter: None,
-        null_treatment: None,
-        over: None,
-        within_group: vec![],
-        uses_odbc_syntax: false,
-    }))
-}
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        let result = generate_sql(&node_graph, None);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        assert_eq!(
+            result,
+            Err("Cannot use Aggregation after Select node. Please remove the Select node or reorder the nodes.".to_string())
+        );
+    }
 
     #[test]
-    fn test_generate_sql_table_only() {
+    fn test_pagination_without_limit_node() {
         let json = r#"{
             "selected_node_id": "1",
             "nodes": [
@@ -482,69 +3841,64 @@ mod tests {
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
+        let sql = generate_sql(&node_graph, Some((100, 0))).unwrap();
 
-        assert_eq!(sql, "SELECT * FROM users");
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT * FROM users) AS subquery LIMIT 100 OFFSET 0"
+        );
     }
 
     #[test]
-    fn test_generate_sql_table_with_select_and_limit() {
+    fn test_pagination_with_offset() {
         let json = r#"{
-            "selected_node_id": "3",
+            "selected_node_id": "1",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "users"}},
-                {"id": "2", "type": "select", "data": {"columns": ["id", "name"]}},
-                {"id": "3", "type": "limit", "data": {"limit": 10}}
+                {"id": "1", "type": "table", "data": {"table_name": "users"}}
             ],
-            "edges": [
-                {"source": "1", "target": "2"},
-                {"source": "2", "target": "3"}
-            ]
+            "edges": []
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
+        let sql = generate_sql(&node_graph, Some((100, 200))).unwrap();
 
-        assert_eq!(sql, "SELECT id, name FROM users LIMIT 10");
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT * FROM users) AS subquery LIMIT 100 OFFSET 200"
+        );
     }
 
     #[test]
-    fn test_generate_sql_table_with_select_sort_and_limit() {
+    fn test_pagination_with_limit_node() {
         let json = r#"{
-            "selected_node_id": "4",
+            "selected_node_id": "2",
             "nodes": [
                 {"id": "1", "type": "table", "data": {"table_name": "users"}},
-                {"id": "2", "type": "select", "data": {"columns": ["id", "name"]}},
-                {"id": "3", "type": "sort", "data": {"order": [{"column": "id", "direction": "desc"}]}},
-                {"id": "4", "type": "limit", "data": {"limit": 5}}
+                {"id": "2", "type": "limit", "data": {"limit": 10}}
             ],
             "edges": [
-                {"source": "1", "target": "2"},
-                {"source": "2", "target": "3"},
-                {"source": "3", "target": "4"}
+                {"source": "1", "target": "2"}
             ]
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
+        let sql = generate_sql(&node_graph, Some((100, 0))).unwrap();
 
-        assert_eq!(sql, "SELECT id, name FROM users ORDER BY id DESC LIMIT 5");
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT * FROM users LIMIT 10) AS subquery LIMIT 100 OFFSET 0"
+        );
     }
 
     #[test]
-    fn test_generate_sql_table_with_multiple_order() {
+    fn test_pagination_with_complex_query() {
         let json = r#"{
             "selected_node_id": "4",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
-                {"id": "2", "type": "select", "data": {"columns": ["id", "customer", "total"]}},
-                {"id": "3", "type": "sort", "data": {
-                    "order": [
-                        {"column": "customer", "direction": "asc"},
-                        {"column": "total", "direction": "desc"}
-                    ]
-                }},
-                {"id": "4", "type": "limit", "data": {"limit": 20}}
+                {"id": "1", "type": "table", "data": {"table_name": "products"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 100}]}},
+                {"id": "3", "type": "sort", "data": {"order": [{"column": "price", "direction": "desc"}]}},
+                {"id": "4", "type": "select", "data": {"columns": ["id", "name", "price"]}}
             ],
             "edges": [
                 {"source": "1", "target": "2"},
@@ -554,450 +3908,898 @@ mod tests {
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
+        let sql = generate_sql(&node_graph, Some((50, 100))).unwrap();
 
         assert_eq!(
             sql,
-            "SELECT id, customer, total FROM orders ORDER BY customer ASC, total DESC LIMIT 20"
+            "SELECT * FROM (SELECT id, name, price FROM products WHERE price > 100 ORDER BY price DESC) AS subquery LIMIT 50 OFFSET 100"
         );
     }
 
     #[test]
-    fn test_generate_sql_select_table_node() {
-        let json = r#"{
-            "selected_node_id": "1",
-            "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "select", "data": {"columns": ["id", "name"]}},
-                {"id": "3", "type": "limit", "data": {"limit": 10}}
-            ],
-            "edges": [
-                {"source": "1", "target": "2"},
-                {"source": "2", "target": "3"}
-            ]
-        }"#;
+    fn test_strip_pagination_wrapper() {
+        assert_eq!(
+            strip_pagination_wrapper(
+                "SELECT * FROM (SELECT * FROM users LIMIT 10) AS subquery LIMIT 100 OFFSET 0"
+            )
+            .unwrap(),
+            "SELECT * FROM users LIMIT 10"
+        );
 
-        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
+        assert_eq!(
+            strip_pagination_wrapper(
+                "SELECT * FROM (SELECT id, name, price FROM products WHERE price > 100 ORDER BY price DESC) AS subquery LIMIT 50 OFFSET 100"
+            )
+            .unwrap(),
+            "SELECT id, name, price FROM products WHERE price > 100 ORDER BY price DESC"
+        );
+        assert_eq!(
+            strip_pagination_wrapper(
+                "SELECT * FROM (WITH cte_1 AS (SELECT * FROM orders) SELECT * FROM cte_1 WHERE amount > 100) AS subquery LIMIT 50 OFFSET 0"
+            )
+            .unwrap(),
+            "WITH cte_1 AS (SELECT * FROM orders) SELECT * FROM cte_1 WHERE amount > 100"
+        );
+    }
 
-        assert_eq!(sql, "SELECT * FROM products");
+    #[test]
+    fn test_strip_pagination_wrapper_unwrapped() {
+        for sql in [
+            "SELECT * FROM users",
+            "SELECT * FROM (SELECT * FROM users) AS subquery",
+            "SELECT * FROM (SELECT * FROM users) AS t LIMIT 10",
+            "SELECT id FROM (SELECT * FROM users) AS subquery LIMIT 10",
+            "SELECT * FROM (SELECT * FROM users) AS subquery WHERE id > 1 LIMIT 10",
+        ] {
+            assert_eq!(strip_pagination_wrapper(sql).unwrap(), sql);
+        }
+
+        assert!(strip_pagination_wrapper("SELECT * FROM")
+            .unwrap_err()
+            .starts_with("Failed to parse SQL:"));
     }
 
     #[test]
-    fn test_generate_sql_order_independent() {
+    fn test_cursor_paginate() {
+        let cursor = CursorPagination {
+            order_column: "id".to_string(),
+            cursor_value: None,
+            page_size: 50,
+        };
+        assert_eq!(
+            cursor_paginate("SELECT * FROM users", &cursor).unwrap(),
+            "SELECT * FROM users ORDER BY id LIMIT 50"
+        );
+
+        let cursor = CursorPagination {
+            cursor_value: Some(serde_json::json!(120)),
+            ..cursor
+        };
+        assert_eq!(
+            cursor_paginate(
+                "SELECT id, name FROM users WHERE age > 18 ORDER BY name DESC",
+                &cursor
+            )
+            .unwrap(),
+            "SELECT id, name FROM users WHERE (age > 18) AND id > 120 ORDER BY id LIMIT 50"
+        );
+        assert_eq!(
+            cursor_paginate(
+                "SELECT category, COUNT(*) FROM products GROUP BY category",
+                &CursorPagination {
+                    order_column: "category".to_string(),
+                    cursor_value: Some(serde_json::json!("books")),
+                    page_size: 10,
+                }
+            )
+            .unwrap(),
+            "SELECT * FROM (SELECT category, COUNT(*) FROM products GROUP BY category) AS subquery WHERE category > 'books' ORDER BY category LIMIT 10"
+        );
+        assert_eq!(
+            cursor_paginate("SELECT * FROM users LIMIT 10", &cursor).unwrap(),
+            "SELECT * FROM (SELECT * FROM users LIMIT 10) AS subquery WHERE id > 120 ORDER BY id LIMIT 50"
+        );
+    }
+
+    #[test]
+    fn test_cursor_paginate_order_column_not_selected() {
+        let cursor = CursorPagination {
+            order_column: "id".to_string(),
+            cursor_value: None,
+            page_size: 50,
+        };
+        assert_eq!(
+            cursor_paginate("SELECT name, email FROM users", &cursor).unwrap_err(),
+            "Order column is not selected by the query: id"
+        );
+        assert_eq!(
+            cursor_paginate("SELECT name, id FROM users", &cursor).unwrap(),
+            "SELECT name, id FROM users ORDER BY id LIMIT 50"
+        );
+    }
+
+    #[test]
+    fn test_cursor_paginate_invalid_order_column() {
+        let cursor = CursorPagination {
+            order_column: "id; DROP TABLE users".to_string(),
+            cursor_value: None,
+            page_size: 50,
+        };
+        assert_eq!(
+            cursor_paginate("SELECT * FROM users", &cursor).unwrap_err(),
+            "Invalid order column: id; DROP TABLE users"
+        );
+    }
+
+    #[test]
+    fn test_explain_node_graph_full_pipeline() {
         let json = r#"{
-            "selected_node_id": "4",
+            "selected_node_id": "5",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "users"}},
-                {"id": "2", "type": "limit", "data": {"limit": 10}},
-                {"id": "3", "type": "sort", "data": {"order": [{"column": "name", "direction": "asc"}]}},
-                {"id": "4", "type": "select", "data": {"columns": ["id", "name"]}}
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 100}]}},
+                {"id": "3", "type": "select", "data": {"columns": ["id", "name", "price"]}},
+                {"id": "4", "type": "sort", "data": {"order": [{"column": "price", "direction": "desc"}]}},
+                {"id": "5", "type": "limit", "data": {"limit": 10}}
             ],
             "edges": [
                 {"source": "1", "target": "2"},
                 {"source": "2", "target": "3"},
-                {"source": "3", "target": "4"}
+                {"source": "3", "target": "4"},
+                {"source": "4", "target": "5"}
             ]
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
 
-        assert_eq!(sql, "SELECT id, name FROM users ORDER BY name ASC LIMIT 10");
+        assert_eq!(
+            explain_node_graph(&node_graph).unwrap(),
+            "Takes the `orders` table, filters rows where `price > 100`, selects columns `id, name, price`, sorts by `price` descending, and returns the top 10 results."
+        );
     }
 
     #[test]
-    fn test_generate_sql_with_single_filter() {
+    fn test_explain_node_graph_table_only() {
         let json = r#"{
-            "selected_node_id": "2",
+            "selected_node_id": "1",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "users"}},
-                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">=", "value": 1000}]}}
+                {"id": "1", "type": "table", "data": {"table_name": "events", "schema": "analytics"}}
             ],
-            "edges": [
-                {"source": "1", "target": "2"}
-            ]
+            "edges": []
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
 
-        assert_eq!(sql, "SELECT * FROM users WHERE price >= 1000");
+        assert_eq!(
+            explain_node_graph(&node_graph).unwrap(),
+            "Takes the `analytics.events` table."
+        );
     }
 
     #[test]
-    fn test_generate_sql_with_multiple_filters() {
+    fn test_explain_node_graph_aggregation_and_table_function() {
         let json = r#"{
-            "selected_node_id": "2",
+            "selected_node_id": "3",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "1", "type": "table_function", "data": {"function_name": "read_csv_auto", "args": ["sales.csv"]}},
                 {"id": "2", "type": "filter", "data": {"conditions": [
-                    {"column": "price", "operator": ">=", "value": 1000},
-                    {"column": "city", "operator": "==", "value": "Tokyo"}
-                ]}}
+                    {"column": "region", "operator": "in", "value": ["EU", "US"], "negate": true},
+                    {"column": "status", "operator": "==", "value": ""}
+                ]}},
+                {"id": "3", "type": "aggregation", "data": {
+                    "dimensions": ["category"],
+                    "metrics": [{"function": "COUNT(*)"}, {"function": "SUM", "column": "amount"}]
+                }}
             ],
             "edges": [
-                {"source": "1", "target": "2"}
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
             ]
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
 
         assert_eq!(
-            sql,
-            "SELECT * FROM users WHERE price >= 1000 AND city = 'Tokyo'"
+            explain_node_graph(&node_graph).unwrap(),
+            "Takes the results of `read_csv_auto('sales.csv')`, filters rows where `NOT region IN ('EU', 'US')`, and groups by `category` and computes `COUNT(*), SUM(amount)`."
         );
     }
 
     #[test]
-    fn test_generate_sql_with_in_operator() {
+    fn test_explain_node_graph_skips_unconfigured_nodes() {
         let json = r#"{
-            "selected_node_id": "2",
+            "selected_node_id": "3",
             "nodes": [
                 {"id": "1", "type": "table", "data": {"table_name": "users"}},
-                {"id": "2", "type": "filter", "data": {"conditions": [
-                    {"column": "name", "operator": "in", "value": ["Taro", "Jiro", "Saburo"]}
+                {"id": "2", "type": "select", "data": {"columns": []}},
+                {"id": "3", "type": "sort", "data": {"order": [
+                    {"column": "name", "direction": "asc"},
+                    {"column": "age", "direction": "desc"}
                 ]}}
             ],
             "edges": [
-                {"source": "1", "target": "2"}
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"}
             ]
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
 
         assert_eq!(
-            sql,
-            "SELECT * FROM users WHERE name IN ('Taro', 'Jiro', 'Saburo')"
+            explain_node_graph(&node_graph).unwrap(),
+            "Takes the `users` table and sorts by `name` ascending, then `age` descending."
         );
     }
 
     #[test]
-    fn test_generate_sql_with_negated_condition() {
+    fn test_explain_node_graph_errors() {
         let json = r#"{
             "selected_node_id": "2",
             "nodes": [
                 {"id": "1", "type": "table", "data": {"table_name": "users"}},
-                {"id": "2", "type": "filter", "data": {"conditions": [
-                    {"column": "city", "operator": "==", "value": "Tokyo", "negate": true}
-                ]}}
+                {"id": "2", "type": "pivot", "data": {}}
             ],
-            "edges": [
-                {"source": "1", "target": "2"}
-            ]
+            "edges": [{"source": "1", "target": "2"}]
         }"#;
-
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
-
-        assert_eq!(sql, "SELECT * FROM users WHERE NOT city = 'Tokyo'");
-    }
+        assert_eq!(
+            explain_node_graph(&node_graph).unwrap_err(),
+            "Unsupported node type: pivot"
+        );
 
-    #[test]
-    fn test_generate_sql_filter_with_select_sort_limit() {
         let json = r#"{
-            "selected_node_id": "5",
-            "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 100}]}},
-                {"id": "3", "type": "select", "data": {"columns": ["id", "name", "price"]}},
-                {"id": "4", "type": "sort", "data": {"order": [{"column": "price", "direction": "desc"}]}},
-                {"id": "5", "type": "limit", "data": {"limit": 10}}
-            ],
-            "edges": [
-                {"source": "1", "target": "2"},
-                {"source": "2", "target": "3"},
-                {"source": "3", "target": "4"},
-                {"source": "4", "target": "5"}
-            ]
+            "selected_node_id": "1",
+            "nodes": [{"id": "1", "type": "limit", "data": {"limit": 5}}],
+            "edges": []
         }"#;
-
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
-
         assert_eq!(
-            sql,
-            "SELECT id, name, price FROM products WHERE price > 100 ORDER BY price DESC LIMIT 10"
+            explain_node_graph(&node_graph).unwrap_err(),
+            "No table node found in path"
         );
     }
 
     #[test]
-    fn test_aggregation_basic() {
+    fn test_node_graph_to_mermaid_linear() {
         let json = r#"{
-            "selected_node_id": "2",
+            "selected_node_id": "4",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "aggregation", "data": {
-                    "dimensions": ["category"],
-                    "metrics": [{"function": "COUNT(*)", "column": ""}]
-                }}
+                {"id": "1", "type": "table", "data": {"table_name": "orders", "schema": "sales"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "status", "operator": "==", "value": "paid"},
+                    {"column": "note", "operator": "==", "value": ""}
+                ]}},
+                {"id": "3", "type": "aggregation", "data": {
+                    "dimensions": ["region"],
+                    "metrics": [{"function": "SUM", "column": "amount"}]
+                }},
+                {"id": "4", "type": "limit", "data": {"limit": 5}}
             ],
             "edges": [
-                {"source": "1", "target": "2"}
+                {"source": "1", "target": "2"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"}
             ]
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
 
         assert_eq!(
-            sql,
-            "SELECT category, COUNT(*) FROM products GROUP BY category"
+            node_graph_to_mermaid(&node_graph).unwrap(),
+            [
+                "graph LR",
+                "    n1[\"table: sales.orders\"]",
+                "    n2[\"filter: status = 'paid'\"]",
+                "    n3[\"aggregation: GROUP BY region, SUM(amount)\"]",
+                "    n4[\"limit: 5\"]",
+                "    n1 --> n2",
+                "    n2 --> n3",
+                "    n3 --> n4",
+            ]
+            .join("\n")
         );
     }
 
     #[test]
-    fn test_aggregation_multiple_metrics() {
+    fn test_node_graph_to_mermaid_branching() {
         let json = r#"{
             "selected_node_id": "2",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "aggregation", "data": {
-                    "dimensions": ["category"],
-                    "metrics": [
-                        {"function": "COUNT(*)", "column": ""},
-                        {"function": "SUM", "column": "price"},
-                        {"function": "AVG", "column": "price"}
-                    ]
-                }}
+                {"id": "src-1", "type": "table_function", "data": {"function_name": "read_csv_auto", "args": ["a \"b\".csv"]}},
+                {"id": "sel-2", "type": "select", "data": {"columns": ["id", "name"]}},
+                {"id": "sort-3", "type": "sort", "data": {"order": [{"column": "id", "direction": "desc"}]}},
+                {"id": "chart-4", "type": "bar_chart", "data": {"x": "id"}},
+                {"id": "sel-5", "type": "select", "data": {"columns": []}}
             ],
             "edges": [
-                {"source": "1", "target": "2"}
+                {"source": "src-1", "target": "sel-2"},
+                {"source": "src-1", "target": "sort-3"},
+                {"source": "sort-3", "target": "chart-4"},
+                {"source": "sort-3", "target": "sel-5"}
             ]
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
 
         assert_eq!(
-            sql,
-            "SELECT category, COUNT(*), SUM(price), AVG(price) FROM products GROUP BY category"
+            node_graph_to_mermaid(&node_graph).unwrap(),
+            [
+                "graph LR",
+                "    n1[\"table_function: read_csv_auto('a #quot;b#quot;.csv')\"]",
+                "    n2[\"select: id, name\"]",
+                "    n3[\"sort: id DESC\"]",
+                "    n4[\"bar_chart\"]",
+                "    n5[\"select\"]",
+                "    n1 --> n2",
+                "    n1 --> n3",
+                "    n3 --> n4",
+                "    n3 --> n5",
+            ]
+            .join("\n")
         );
     }
 
     #[test]
-    fn test_aggregation_multiple_dimensions() {
+    fn test_node_graph_to_mermaid_unknown_edge() {
         let json = r#"{
-            "selected_node_id": "2",
-            "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "aggregation", "data": {
-                    "dimensions": ["category", "region"],
-                    "metrics": [{"function": "COUNT(*)", "column": ""}]
-                }}
-            ],
-            "edges": [
-                {"source": "1", "target": "2"}
-            ]
+            "selected_node_id": "1",
+            "nodes": [{"id": "1", "type": "table", "data": {"table_name": "orders"}}],
+            "edges": [{"source": "1", "target": "9"}]
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
 
         assert_eq!(
-            sql,
-            "SELECT category, region, COUNT(*) FROM products GROUP BY category, region"
+            node_graph_to_mermaid(&node_graph).unwrap_err(),
+            "Edge references unknown node: 9"
         );
     }
 
+    // Note: Splits DOT into identifiers, quoted strings (unescaped) and punctuation
+    fn tokenize_dot(dot: &str) -> Result<Vec<String>, String> {
+        let mut tokens = Vec::new();
+        let mut chars = dot.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                c if c.is_whitespace() => {}
+                '{' | '}' | '[' | ']' | ';' | '=' | ',' => tokens.push(c.to_string()),
+                '-' if chars.next_if_eq(&'>').is_some() => tokens.push("->".to_string()),
+                '"' => {
+                    let mut text = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('\\') => text.extend(chars.next()),
+                            Some('"') => break,
+                            Some(c) => text.push(c),
+                            None => return Err("Unterminated string".to_string()),
+                        }
+                    }
+                    tokens.push(text);
+                }
+                c if c.is_alphanumeric() || c == '_' => {
+                    let mut ident = c.to_string();
+                    while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                        ident.push(c);
+                    }
+                    tokens.push(ident);
+                }
+                c => return Err(format!("Unexpected character: {}", c)),
+            }
+        }
+        Ok(tokens)
+    }
+
     #[test]
-    fn test_aggregation_with_filter() {
+    fn test_complexity_score() {
+        let node_graph: NodeGraph =
+            serde_json::from_str(r#"{"selected_node_id": "1", "nodes": [], "edges": []}"#).unwrap();
+        assert_eq!(complexity_score(&node_graph), 0);
+
         let json = r#"{
             "selected_node_id": "3",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 100}]}},
-                {"id": "3", "type": "aggregation", "data": {
-                    "dimensions": ["category"],
-                    "metrics": [{"function": "COUNT(*)", "column": ""}]
-                }}
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "select", "data": {"columns": ["id"]}},
+                {"id": "3", "type": "limit", "data": {"limit": 10}}
             ],
             "edges": [
                 {"source": "1", "target": "2"},
                 {"source": "2", "target": "3"}
             ]
         }"#;
-
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
+        assert_eq!(complexity_score(&node_graph), 3);
 
-        assert_eq!(
-            sql,
-            "SELECT category, COUNT(*) FROM products WHERE price > 100 GROUP BY category"
-        );
+        let json = r#"{
+            "selected_node_id": "5",
+            "nodes": [
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "table", "data": {"table_name": "customers"}},
+                {"id": "3", "type": "join", "data": {}},
+                {"id": "4", "type": "filter", "data": {"conditions": [
+                    {"column": "amount", "operator": ">", "value": 100},
+                    {"column": "status", "operator": "==", "value": "paid"}
+                ]}},
+                {"id": "5", "type": "aggregation", "data": {
+                    "dimensions": ["country"],
+                    "metrics": [{"function": "COUNT(*)"}]
+                }}
+            ],
+            "edges": [
+                {"source": "1", "target": "3"},
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"},
+                {"source": "4", "target": "5"}
+            ]
+        }"#;
+        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
+        assert_eq!(complexity_score(&node_graph), 5 + 4 + 3 + 5);
     }
 
     #[test]
-    fn test_aggregation_then_select() {
+    fn test_node_graph_to_dot() {
         let json = r#"{
-            "selected_node_id": "3",
+            "selected_node_id": "4",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "aggregation", "data": {
-                    "dimensions": ["category"],
-                    "metrics": [{"function": "COUNT(*)", "column": ""}]
+                {"id": "1", "type": "table", "data": {"table_name": "orders"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "note", "operator": "==", "value": "say \"hi\" \\ bye"}
+                ]}},
+                {"id": "3", "type": "aggregation", "data": {
+                    "dimensions": [],
+                    "metrics": [{"function": "COUNT(*)"}]
                 }},
-                {"id": "3", "type": "select", "data": {"columns": ["category"]}}
+                {"id": "4", "type": "bar_chart", "data": {}}
             ],
             "edges": [
                 {"source": "1", "target": "2"},
-                {"source": "2", "target": "3"}
+                {"source": "2", "target": "3"},
+                {"source": "2", "target": "4"}
             ]
         }"#;
 
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
+        let dot = node_graph_to_dot(&node_graph).unwrap();
 
         assert_eq!(
-            sql,
-            "SELECT category, COUNT(*) FROM products GROUP BY category"
+            dot,
+            [
+                "digraph node_graph {",
+                "    rankdir=LR;",
+                "    n1 [label=\"table: orders\", shape=box];",
+                "    n2 [label=\"filter: note = 'say \\\"hi\\\" \\\\ bye'\", shape=diamond];",
+                "    n3 [label=\"aggregation: COUNT(*)\", shape=ellipse];",
+                "    n4 [label=\"bar_chart\", shape=box, style=rounded];",
+                "    n1 -> n2;",
+                "    n2 -> n3;",
+                "    n2 -> n4;",
+                "}",
+            ]
+            .join("\n")
+        );
+
+        let tokens = tokenize_dot(&dot).unwrap();
+        assert_eq!(tokens[..3], ["digraph", "node_graph", "{"]);
+        assert_eq!(tokens.last().unwrap(), "}");
+        assert!(tokens.contains(&"filter: note = 'say \"hi\" \\ bye'".to_string()));
+        assert_eq!(tokens.iter().filter(|t| *t == "->").count(), 3);
+        assert_eq!(
+            tokens.iter().filter(|t| *t == "[").count(),
+            tokens.iter().filter(|t| *t == "]").count()
         );
     }
 
     #[test]
-    fn test_select_then_aggregation_error() {
+    fn test_validate_node_graph_schema_valid() {
         let json = r#"{
             "selected_node_id": "3",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "select", "data": {"columns": ["id", "name"]}},
-                {"id": "3", "type": "aggregation", "data": {
-                    "dimensions": ["category"],
-                    "metrics": [{"function": "COUNT(*)", "column": ""}]
-                }}
+                {"id": "1", "type": "table", "data": {"table_name": "users", "schema": "main"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "id", "operator": "in", "value": [1, "2"], "negate": true}
+                ]}},
+                {"id": "3", "type": "limit", "data": {"limit": null}},
+                {"id": "4", "type": "barChart", "data": {"xAxis": "id", "yAxis": ""}}
             ],
             "edges": [
                 {"source": "1", "target": "2"},
-                {"source": "2", "target": "3"}
+                {"source": "2", "target": "3"},
+                {"source": "3", "target": "4"}
             ]
         }"#;
 
-        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let result = generate_sql(&node_graph, None);
+        assert_eq!(validate_node_graph_schema(json), Ok(()));
+    }
 
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err(),
-            "Cannot use Aggregation after Select node. Please remove the Select node or reorder the nodes."
-        );
+    #[test]
+    fn test_node_graph_selected_node_ids() {
+        let single: NodeGraph =
+            serde_json::from_str(r#"{"selected_node_id": "2", "nodes": [], "edges": []}"#).unwrap();
+        assert_eq!(single.selected_node_ids, vec!["2"]);
+        assert_eq!(single.selected_node_id(), "2");
+
+        let multiple: NodeGraph =
+            serde_json::from_str(r#"{"selected_node_ids": ["1", "3"], "nodes": [], "edges": []}"#)
+                .unwrap();
+        assert_eq!(multiple.selected_node_ids, vec!["1", "3"]);
+        assert_eq!(multiple.selected_node_id(), "1");
+
+        let json = serde_json::to_string(&multiple).unwrap();
+        assert!(json.contains(r#""selected_node_ids":["1","3"]"#));
+        assert_eq!(validate_node_graph_schema(&json), Ok(()));
+
+        let err = serde_json::from_str::<NodeGraph>(
+            r#"{"selected_node_ids": [], "nodes": [], "edges": []}"#,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("at least one node must be selected"));
+
+        assert!(validate_node_graph_schema(r#"{"nodes": [], "edges": []}"#).is_err());
+        assert!(validate_node_graph_schema(
+            r#"{"selected_node_id": "1", "selected_node_ids": ["1"], "nodes": [], "edges": []}"#
+        )
+        .is_err());
     }
 
     #[test]
-    fn test_aggregation_all_functions() {
+    fn test_validate_node_graph_schema_invalid() {
         let json = r#"{
             "selected_node_id": "2",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "aggregation", "data": {
-                    "dimensions": ["category"],
-                    "metrics": [
-                        {"function": "COUNT", "column": "id"},
-                        {"function": "SUM", "column": "price"},
-                        {"function": "AVG", "column": "price"},
-                        {"function": "MAX", "column": "price"},
-                        {"function": "MIN", "column": "price"}
-                    ]
-                }}
+                {"id": "1", "type": "table", "data": {"table": "users"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "id", "operator": "like", "value": 1}
+                ]}}
             ],
-            "edges": [
-                {"source": "1", "target": "2"}
-            ]
+            "edges": [{"source": "1", "target": "2", "label": "x"}],
+            "viewport": {}
         }"#;
 
-        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, None).unwrap();
+        let errors = validate_node_graph_schema(json).unwrap_err();
+        for path in [
+            "at ''",
+            "at '/nodes/0/data'",
+            "at '/nodes/1/data/conditions/0/operator'",
+            "at '/edges/0'",
+        ] {
+            assert!(
+                errors.iter().any(|e| e.ends_with(path)),
+                "no error {} in {:?}",
+                path,
+                errors
+            );
+        }
+    }
 
-        assert_eq!(
-            sql,
-            "SELECT category, COUNT(id), SUM(price), AVG(price), MAX(price), MIN(price) FROM products GROUP BY category"
-        );
+    #[test]
+    fn test_validate_node_graph_schema_malformed_json() {
+        let errors = validate_node_graph_schema("{").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Failed to parse node graph"));
     }
 
     #[test]
-    fn test_pagination_without_limit_node() {
+    fn test_parse_node_graph_rejects_unknown_fields() {
         let json = r#"{
-            "selected_node_id": "1",
+            "selected_node_id": "2",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "users"}}
+                {"id": "1", "type": "table", "data": {"table_name": "users"}},
+                {"id": "2", "type": "sort", "data": {"order": [{"column": "id", "direction": "asc", "nulls": "first"}]}}
             ],
-            "edges": []
+            "edges": [{"source": "1", "target": "2"}]
         }"#;
 
-        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, Some((100, 0))).unwrap();
+        let err = parse_node_graph(json).unwrap_err();
+        assert!(err.starts_with("Invalid node graph: "), "{}", err);
+        assert!(err.contains("/nodes/1/data/order/0"), "{}", err);
+
+        // Note: serde ignores these fields, so only the raw JSON shows them
+        let json = r#"{
+            "selected_node_id": "1",
+            "nodes": [{"id": "1", "type": "table", "data": {"table_name": "users"}, "position": {"x": 0}}],
+            "edges": [],
+            "viewport": {}
+        }"#;
+
+        let err = parse_node_graph(json).unwrap_err();
+        assert!(err.contains("'/nodes/0'"), "{}", err);
+        assert!(err.contains("viewport"), "{}", err);
+        assert!(parse_node_graph("{")
+            .unwrap_err()
+            .contains("Failed to parse node graph"));
+    }
+
+    fn round_trip(sql: &str) -> String {
+        let node_graph = sql_to_node_graph(sql).unwrap();
+        generate_sql(&node_graph, None).unwrap()
+    }
 
+    #[test]
+    fn test_sql_to_node_graph_table_only() {
+        let node_graph = sql_to_node_graph("SELECT * FROM users").unwrap();
+
+        assert_eq!(node_graph.selected_node_id(), "1");
+        assert_eq!(node_graph.nodes.len(), 1);
+        assert_eq!(node_graph.nodes[0].node_type, "table");
         assert_eq!(
-            sql,
-            "SELECT * FROM (SELECT * FROM users) AS subquery LIMIT 100 OFFSET 0"
+            node_graph.nodes[0].data,
+            serde_json::json!({"table_name": "users"})
         );
+        assert!(node_graph.edges.is_empty());
     }
 
     #[test]
-    fn test_pagination_with_offset() {
-        let json = r#"{
-            "selected_node_id": "1",
-            "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "users"}}
-            ],
-            "edges": []
-        }"#;
+    fn test_sql_to_node_graph_builds_chain() {
+        let node_graph = sql_to_node_graph(
+            "SELECT id, name FROM products WHERE price > 10 ORDER BY name DESC LIMIT 5",
+        )
+        .unwrap();
 
-        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, Some((100, 200))).unwrap();
+        let types: Vec<&str> = node_graph
+            .nodes
+            .iter()
+            .map(|n| n.node_type.as_str())
+            .collect();
+        assert_eq!(types, vec!["table", "filter", "select", "sort", "limit"]);
+        assert_eq!(node_graph.selected_node_id(), "5");
+        assert_eq!(node_graph.edges.len(), 4);
+        assert_eq!(node_graph.edges[3].source, "4");
+        assert_eq!(node_graph.edges[3].target, "5");
+    }
+
+    #[test]
+    fn test_sql_to_node_graph_round_trip() {
+        for sql in [
+            "SELECT id, name FROM products WHERE price > 10 ORDER BY name DESC LIMIT 5",
+            "SELECT * FROM users WHERE status = 'it''s' AND NOT age < -3 AND id IN (1, 2)",
+            "SELECT * FROM users WHERE NOT id IN (1.5, 'a', true)",
+            "SELECT category, COUNT(*), SUM(price) FROM products WHERE price > 0 GROUP BY category",
+            "SELECT * FROM analytics.events ORDER BY ts ASC",
+            "SELECT * FROM read_csv_auto('data.csv') LIMIT 10",
+        ] {
+            assert_eq!(round_trip(sql), sql);
+        }
+    }
+
+    #[test]
+    fn test_sql_to_node_graph_not_in_becomes_negated_condition() {
+        let node_graph = sql_to_node_graph("SELECT * FROM users WHERE id NOT IN (1, 2)").unwrap();
 
         assert_eq!(
-            sql,
-            "SELECT * FROM (SELECT * FROM users) AS subquery LIMIT 100 OFFSET 200"
+            node_graph.nodes[1].data,
+            serde_json::json!({"conditions": [
+                {"column": "id", "operator": "in", "value": [1, 2], "negate": true}
+            ]})
+        );
+        assert_eq!(
+            round_trip("SELECT * FROM users WHERE id NOT IN (1, 2)"),
+            "SELECT * FROM users WHERE NOT id IN (1, 2)"
         );
     }
 
     #[test]
-    fn test_pagination_with_limit_node() {
+    fn test_filter_json_object_value() {
         let json = r#"{
             "selected_node_id": "2",
             "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "users"}},
-                {"id": "2", "type": "limit", "data": {"limit": 10}}
+                {"id": "1", "type": "table", "data": {"table_name": "events"}},
+                {"id": "2", "type": "filter", "data": {"conditions": [
+                    {"column": "payload", "operator": "==", "value": {"kind": "click", "note": "it's"}},
+                    {"column": "meta", "operator": "in", "value": [{"a": 1}, "{}"]}
+                ]}}
             ],
             "edges": [
                 {"source": "1", "target": "2"}
             ]
         }"#;
 
+        assert!(validate_node_graph_schema(json).is_ok());
         let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, Some((100, 0))).unwrap();
+        let sql = generate_sql(&node_graph, None).unwrap();
 
         assert_eq!(
             sql,
-            "SELECT * FROM (SELECT * FROM users LIMIT 10) AS subquery LIMIT 100 OFFSET 0"
+            r#"SELECT * FROM events WHERE payload = CAST('{"kind":"click","note":"it''s"}' AS JSON) AND meta IN (CAST('{"a":1}' AS JSON), '{}')"#
+        );
+        assert!(Parser::parse_sql(&DuckDbDialect {}, &sql).is_ok());
+
+        let round_trip = sql_to_node_graph(&sql).unwrap();
+        let filter = round_trip
+            .nodes
+            .iter()
+            .find(|n| n.node_type == "filter")
+            .unwrap();
+        assert_eq!(
+            filter.data["conditions"][0]["value"],
+            serde_json::json!({"kind": "click", "note": "it's"})
         );
     }
 
     #[test]
-    fn test_pagination_with_complex_query() {
-        let json = r#"{
-            "selected_node_id": "4",
-            "nodes": [
-                {"id": "1", "type": "table", "data": {"table_name": "products"}},
-                {"id": "2", "type": "filter", "data": {"conditions": [{"column": "price", "operator": ">", "value": 100}]}},
-                {"id": "3", "type": "sort", "data": {"order": [{"column": "price", "direction": "desc"}]}},
-                {"id": "4", "type": "select", "data": {"columns": ["id", "name", "price"]}}
-            ],
-            "edges": [
-                {"source": "1", "target": "2"},
-                {"source": "2", "target": "3"},
-                {"source": "3", "target": "4"}
-            ]
-        }"#;
-
-        let node_graph: NodeGraph = serde_json::from_str(json).unwrap();
-        let sql = generate_sql(&node_graph, Some((50, 100))).unwrap();
-
+    fn test_sql_to_node_graph_rewrites_top_to_limit() {
         assert_eq!(
-            sql,
-            "SELECT * FROM (SELECT id, name, price FROM products WHERE price > 100 ORDER BY price DESC) AS subquery LIMIT 50 OFFSET 100"
+            round_trip("SELECT TOP 10 id, name FROM users"),
+            "SELECT id, name FROM users LIMIT 10"
+        );
+        assert_eq!(
+            round_trip("SELECT TOP (5) * FROM users WHERE age > 30 ORDER BY age DESC"),
+            "SELECT * FROM users WHERE age > 30 ORDER BY age DESC LIMIT 5"
+        );
+        assert_eq!(
+            round_trip("SELECT id, name FROM users ORDER BY id ASC LIMIT 3"),
+            "SELECT id, name FROM users ORDER BY id ASC LIMIT 3"
         );
+
+        for sql in [
+            "SELECT TOP 10 PERCENT * FROM users",
+            "SELECT TOP 5 WITH TIES * FROM users ORDER BY id",
+            "SELECT TOP 5 * FROM users LIMIT 3",
+        ] {
+            let err = sql_to_node_graph(sql).unwrap_err();
+            assert!(err.contains("TOP"), "{}: {}", sql, err);
+        }
+    }
+
+    #[test]
+    fn test_sql_to_node_graph_rejects_unsupported_constructs() {
+        let cases = [
+            ("WITH t AS (SELECT 1) SELECT * FROM t", "CTEs"),
+            ("SELECT * FROM (SELECT * FROM users)", "subqueries"),
+            (
+                "SELECT * FROM users WHERE id IN (SELECT id FROM admins)",
+                "subqueries",
+            ),
+            (
+                "SELECT * FROM users JOIN orders ON users.id = orders.user_id",
+                "joins",
+            ),
+            ("SELECT * FROM a UNION SELECT * FROM b", "set operations"),
+            ("SELECT 1; SELECT 2", "exactly one SQL statement"),
+            ("DELETE FROM users", "only SELECT statements"),
+            ("SELECT name FROM users GROUP BY category", "GROUP BY: name"),
+            ("SELECT * FROM users LIMIT 5 OFFSET 10", "plain LIMIT"),
+        ];
+
+        for (sql, expected) in cases {
+            let err = sql_to_node_graph(sql).unwrap_err();
+            assert!(err.contains(expected), "{}: {}", sql, err);
+        }
+    }
+
+    fn column_strategy() -> impl Strategy<Value = String> {
+        "c_[a-z0-9_]{0,8}"
+    }
+
+    fn operator_strategy(include_in: bool) -> impl Strategy<Value = FilterOperator> {
+        let max = if include_in { 6 } else { 5 };
+        (0..=max).prop_map(|i| match i {
+            0 => FilterOperator::Eq,
+            1 => FilterOperator::NotEq,
+            2 => FilterOperator::Gt,
+            3 => FilterOperator::Lt,
+            4 => FilterOperator::GtEq,
+            5 => FilterOperator::LtEq,
+            _ => FilterOperator::In,
+        })
+    }
+
+    fn scalar_strategy() -> impl Strategy<Value = serde_json::Value> {
+        prop_oneof![
+            any::<String>().prop_map(serde_json::Value::String),
+            any::<u32>().prop_map(|n| serde_json::json!(n)),
+            any::<bool>().prop_map(serde_json::Value::Bool),
+        ]
+    }
+
+    fn condition_strategy() -> impl Strategy<Value = FilterCondition> {
+        (
+            column_strategy(),
+            operator_strategy(true),
+            scalar_strategy(),
+            prop::collection::vec(scalar_strategy(), 0..4),
+            any::<bool>(),
+        )
+            .prop_map(|(column, operator, scalar, list, negate)| {
+                let value = match operator {
+                    FilterOperator::In => serde_json::Value::Array(list),
+                    _ => scalar,
+                };
+                FilterCondition {
+                    column,
+                    operator,
+                    value,
+                    negate,
+                    quoted: false,
+                }
+            })
+    }
+
+    fn empty_condition_strategy() -> impl Strategy<Value = FilterCondition> {
+        (
+            column_strategy(),
+            operator_strategy(true),
+            0..4usize,
+            any::<bool>(),
+        )
+            .prop_map(|(column, operator, empty_len, negate)| {
+                let value =
+                    match operator {
+                        FilterOperator::In => serde_json::Value::Array(vec![
+                        serde_json::Value::String(String::new());
+                        empty_len
+                    ]),
+                        _ => serde_json::Value::String(String::new()),
+                    };
+                FilterCondition {
+                    column,
+                    operator,
+                    value,
+                    negate,
+                    quoted: false,
+                }
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_build_where_expr_ok_with_non_empty_value(
+            conditions in prop::collection::vec(condition_strategy(), 1..8)
+        ) {
+            prop_assume!(conditions.iter().any(|c| !is_empty_value(&c.value)));
+            prop_assert!(build_where_expr(&conditions).is_ok());
+        }
+
+        #[test]
+        fn prop_build_where_expr_err_when_all_empty(
+            conditions in prop::collection::vec(empty_condition_strategy(), 0..8)
+        ) {
+            prop_assert!(build_where_expr(&conditions).is_err());
+        }
+
+        #[test]
+        fn prop_build_where_expr_string_values_cannot_inject(
+            column in column_strategy(),
+            operator in operator_strategy(false),
+            value in any::<String>().prop_filter("non-empty", |s| !s.is_empty()),
+            negate in any::<bool>(),
+        ) {
+            let condition = FilterCondition {
+                column: column.clone(),
+                operator,
+                value: serde_json::Value::String(value.clone()),
+                negate,
+                quoted: false,
+            };
+            let sql = build_where_expr(&[condition]).unwrap().to_string();
+
+            // Note: Re-parsing the rendered SQL must yield a single comparison whose literal
+            // is the original value, otherwise the value escaped its string literal
+            let parsed = Parser::new(&DuckDbDialect {})
+                .try_with_sql(&sql)
+                .and_then(|mut p| p.parse_expr())
+                .unwrap();
+            let comparison = match parsed {
+                Expr::UnaryOp {
+                    op: UnaryOperator::Not,
+                    expr,
+                } if negate => *expr,
+                other => other,
+            };
+            match comparison {
+                Expr::BinaryOp { left, right, .. } => {
+                    prop_assert_eq!(*left, Expr::Identifier(Ident::new(column)));
+                    prop_assert_eq!(
+                        *right,
+                        Expr::Value(ValueWithSpan {
+                            value: Value::SingleQuotedString(value),
+                            span: Span::empty(),
+                        })
+                    );
+                }
+                other => prop_assert!(false, "Unexpected expression: {}", other),
+            }
+        }
     }
 }
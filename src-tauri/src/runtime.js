@@ -41,15 +41,189 @@ async function inferSchemaFromSQL(sql) {
   };
 }
 
+// Note: path may be a list of files; a column is flagged if it is too wide in any of them
+async function flagLargeColumns(schema, path, format, threshold) {
+  const limit = Number(threshold);
+  if (!threshold || !Number.isFinite(limit) || limit <= 0) {
+    return schema;
+  }
+
+  const widths = new Map();
+  for (const filePath of Array.isArray(path) ? path : [path]) {
+    const { columns } = await core.ops.op_sample_column_widths(
+      filePath,
+      format,
+      100
+    );
+    for (const column of columns) {
+      widths.set(
+        column.name,
+        Math.max(widths.get(column.name) ?? 0, column.width)
+      );
+    }
+  }
+
+  return {
+    ...schema,
+    columns: schema.columns.map((column) =>
+      widths.get(column.name) > limit ? { ...column, skip: true } : column
+    )
+  };
+}
+
+function selectColumns(schema) {
+  const skipped = (schema?.columns ?? [])
+    .filter((column) => column.skip)
+    .map((column) => `"${column.name.replace(/"/g, '""')}"`);
+
+  return skipped.length > 0 ? `* EXCLUDE (${skipped.join(', ')})` : '*';
+}
+
+function createRowStream(rid) {
+  let done = false;
+
+  const close = () => {
+    done = true;
+    core.tryClose(rid);
+  };
+
+  const read = async () => {
+    if (done) {
+      return { done: true, value: undefined };
+    }
+    let row;
+    try {
+      row = await core.ops.op_run_sql_stream_next(rid);
+    } catch (e) {
+      close();
+      throw e;
+    }
+    if (row === null) {
+      close();
+      return { done: true, value: undefined };
+    }
+    return { done: false, value: row };
+  };
+
+  if (typeof globalThis.ReadableStream === 'function') {
+    return new ReadableStream({
+      async pull(controller) {
+        try {
+          const { done, value } = await read();
+          if (done) {
+            controller.close();
+          } else {
+            controller.enqueue(value);
+          }
+        } catch (e) {
+          controller.error(e);
+        }
+      },
+      cancel: close
+    });
+  }
+
+  // Note: The bare runtime has no ReadableStream, so only the reader and
+  // async iterator parts of its interface are provided
+  return {
+    getReader() {
+      return {
+        read,
+        async cancel() {
+          close();
+        },
+        releaseLock() {}
+      };
+    },
+    async cancel() {
+      close();
+    },
+    [Symbol.asyncIterator]() {
+      return {
+        next: read,
+        async return() {
+          close();
+          return { done: true, value: undefined };
+        }
+      };
+    }
+  };
+}
+
 globalThis.streaksight = {
   async readFile(path) {
     return await core.ops.op_read_file(path);
   },
+  async glob(dir, pattern) {
+    return await core.ops.op_glob(dir, pattern);
+  },
+  async readFileWithEncoding(path, encoding) {
+    return await core.ops.op_read_file_with_encoding(path, encoding);
+  },
+  // Note: With an offset this resolves to { chunk, nextOffset }, since chunks
+  // are trimmed to a character boundary and can be shorter than chunkSize
+  async readFileChunked(path, chunkSize, offset) {
+    if (offset === undefined) {
+      return await core.ops.op_read_file_chunked(path, chunkSize);
+    }
+    return await core.ops.op_read_file_chunked_offset(path, chunkSize, offset);
+  },
+  tempDir() {
+    return core.ops.op_temp_dir();
+  },
+  getSystemInfo() {
+    return core.ops.op_get_system_info();
+  },
   async writeFile(path, contents) {
     return await core.ops.op_write_file(path, contents);
   },
+  async removeFile(path) {
+    return await core.ops.op_remove_file(path);
+  },
   async runSql(sql) {
     return await core.ops.op_run_sql(sql);
   },
-  inferSchemaFromSQL
+  async runSqlBatch(statements) {
+    return await core.ops.op_run_sql_batch(statements);
+  },
+  async runPragma(name) {
+    return await core.ops.op_run_duckdb_pragma(name);
+  },
+  async inferCsvSchema(
+    path,
+    delimiter = ',',
+    hasHeader = true,
+    sampleRows = 100
+  ) {
+    return await core.ops.op_infer_csv_schema(
+      path,
+      delimiter,
+      hasHeader,
+      sampleRows
+    );
+  },
+  async sampleRows(path, format = 'csv', n = 100) {
+    return await core.ops.op_sample_n_rows(path, format, n);
+  },
+  async readJsonFiles(paths) {
+    return await core.ops.op_read_json_files(paths);
+  },
+  async detectDelimiter(path) {
+    return await core.ops.op_detect_delimiter(path);
+  },
+  async detectEncoding(path) {
+    return await core.ops.op_detect_encoding(path);
+  },
+  watchFile(path, debounceMs = 500) {
+    core.ops.op_watch_file(path, debounceMs);
+  },
+  unwatchFile(path) {
+    core.ops.op_unwatch_file(path);
+  },
+  runSQLStream(sql) {
+    return createRowStream(core.ops.op_run_sql_stream(sql));
+  },
+  inferSchemaFromSQL,
+  flagLargeColumns,
+  selectColumns
 };
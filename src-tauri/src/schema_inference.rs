@@ -0,0 +1,572 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum InferredType {
+    Boolean,
+    Integer,
+    Float,
+    String,
+}
+
+impl InferredType {
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        match value {
+            serde_json::Value::Null => None,
+            serde_json::Value::Bool(_) => Some(InferredType::Boolean),
+            serde_json::Value::Number(n) if n.is_i64() || n.is_u64() => Some(InferredType::Integer),
+            serde_json::Value::Number(_) => Some(InferredType::Float),
+            // Note: Nested arrays and objects are stored as their JSON text
+            _ => Some(InferredType::String),
+        }
+    }
+
+    fn column_type(self) -> &'static str {
+        match self {
+            InferredType::Boolean => "boolean",
+            InferredType::Integer | InferredType::Float => "number",
+            InferredType::String => "string",
+        }
+    }
+}
+
+pub fn infer_json_schema(sample: &[serde_json::Value]) -> serde_json::Value {
+    let mut fields: Vec<(String, Option<InferredType>)> = Vec::new();
+
+    for object in sample.iter().filter_map(|v| v.as_object()) {
+        for (key, value) in object {
+            let inferred = InferredType::from_json(value);
+            match fields.iter_mut().find(|(name, _)| name == key) {
+                Some((_, ty)) => *ty = (*ty).max(inferred),
+                None => fields.push((key.clone(), inferred)),
+            }
+        }
+    }
+
+    let columns: Vec<serde_json::Value> = fields
+        .into_iter()
+        .map(|(name, ty)| {
+            serde_json::json!({
+                "name": name,
+                "type": ty.unwrap_or(InferredType::String).column_type()
+            })
+        })
+        .collect();
+
+    serde_json::json!({ "columns": columns })
+}
+
+const JSON_SAMPLE_RECORDS: usize = 100;
+
+// Note: Accepts a JSON array, a single object or newline-delimited JSON
+fn parse_json_records(contents: &str) -> Result<Vec<serde_json::Value>, String> {
+    match serde_json::from_str::<serde_json::Value>(contents) {
+        Ok(serde_json::Value::Array(records)) => Ok(records),
+        Ok(value) => Ok(vec![value]),
+        Err(_) => contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| format!("Invalid JSON: {}", e)))
+            .collect(),
+    }
+}
+
+pub fn infer_json_files_schema(paths: &[String]) -> Result<serde_json::Value, String> {
+    if paths.is_empty() {
+        return Err("No JSON files given".to_string());
+    }
+
+    let mut sample = Vec::new();
+    for path in paths {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let records = parse_json_records(&contents).map_err(|e| format!("{} in {}", e, path))?;
+        sample.extend(records.into_iter().take(JSON_SAMPLE_RECORDS));
+    }
+
+    Ok(infer_json_schema(&sample))
+}
+
+const CANDIDATE_DELIMITERS: [char; 4] = [',', ';', '|', '\t'];
+const DELIMITER_SAMPLE_LINES: usize = 10;
+
+pub fn detect_delimiter(sample: &str) -> char {
+    let mut counts = [0usize; CANDIDATE_DELIMITERS.len()];
+
+    for line in sample.lines().take(DELIMITER_SAMPLE_LINES) {
+        let mut in_quotes = false;
+        for c in line.chars() {
+            if c == '"' {
+                in_quotes = !in_quotes;
+            } else if !in_quotes {
+                if let Some(i) = CANDIDATE_DELIMITERS.iter().position(|d| *d == c) {
+                    counts[i] += 1;
+                }
+            }
+        }
+    }
+
+    // Note: Ties and samples without any candidate fall back to the earliest candidate (comma)
+    let mut best = 0;
+    for (i, count) in counts.iter().enumerate() {
+        if *count > counts[best] {
+            best = i;
+        }
+    }
+
+    CANDIDATE_DELIMITERS[best]
+}
+
+// Note: A file without a BOM that is not valid UTF-8 is taken to be Latin-1, read as its superset windows-1252
+pub fn detect_encoding(sample: &[u8]) -> &'static str {
+    if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(sample) {
+        return encoding.name();
+    }
+
+    // Note: UTF-16 text without a BOM shows up as a NUL byte in every other position
+    let pairs = sample.len() / 2;
+    if pairs > 0 {
+        let even_nuls = sample.iter().step_by(2).filter(|b| **b == 0).count();
+        let odd_nuls = sample
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count();
+        if odd_nuls * 2 > pairs && even_nuls * 10 < pairs {
+            return encoding_rs::UTF_16LE.name();
+        }
+        if even_nuls * 2 > pairs && odd_nuls * 10 < pairs {
+            return encoding_rs::UTF_16BE.name();
+        }
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => encoding_rs::UTF_8.name(),
+        // Note: The sample may end partway through a multi-byte character
+        Err(e) if e.error_len().is_none() => encoding_rs::UTF_8.name(),
+        Err(_) => encoding_rs::WINDOWS_1252.name(),
+    }
+}
+
+fn sql_string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn connector_type(duckdb_type: &str) -> &'static str {
+    let type_upper = duckdb_type.to_uppercase();
+
+    if type_upper.contains("VARCHAR") || type_upper.contains("TEXT") {
+        "string"
+    } else if [
+        "DOUBLE", "INTEGER", "BIGINT", "DECIMAL", "FLOAT", "TINYINT", "SMALLINT",
+    ]
+    .iter()
+    .any(|t| type_upper.contains(t))
+    {
+        "number"
+    } else if type_upper.contains("BOOLEAN") {
+        "boolean"
+    } else if type_upper.contains("DATE") || type_upper.contains("TIMESTAMP") {
+        "date"
+    } else {
+        "string"
+    }
+}
+
+pub fn infer_csv_schema(
+    path: &str,
+    delimiter: char,
+    has_header: bool,
+    sample_rows: usize,
+) -> Result<serde_json::Value, String> {
+    if sample_rows == 0 {
+        return Err("sample_rows must be greater than 0".to_string());
+    }
+
+    let conn = crate::duckdb_connect().map_err(|e| e.to_string())?;
+
+    let query = format!(
+        "SUMMARIZE SELECT * FROM read_csv_auto({}, delim = {}, header = {}, sample_size = {})",
+        sql_string_literal(path),
+        sql_string_literal(&delimiter.to_string()),
+        has_header,
+        sample_rows
+    );
+    let mut stmt = conn
+        .prepare(&query)
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let columns: Vec<serde_json::Value> = stmt
+        .query_map([], |row| {
+            let name: String = row.get("column_name")?;
+            let column_type: String = row.get("column_type")?;
+            Ok(serde_json::json!({
+                "name": name,
+                "type": connector_type(&column_type)
+            }))
+        })
+        .map_err(|e| format!("Failed to summarize CSV file: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect results: {}", e))?;
+
+    Ok(serde_json::json!({ "columns": columns }))
+}
+
+pub fn file_reader(format: &str) -> Result<&'static str, String> {
+    match format {
+        "csv" => Ok("read_csv_auto"),
+        "json" => Ok("read_json_auto"),
+        "parquet" => Ok("read_parquet"),
+        _ => Err(format!("Unsupported file format: {}", format)),
+    }
+}
+
+pub fn sample_rows(path: &str, format: &str, n: usize) -> Result<serde_json::Value, String> {
+    let reader = file_reader(format)?;
+
+    let conn = crate::duckdb_connect().map_err(|e| e.to_string())?;
+
+    let sql = format!(
+        "SELECT * FROM {}({}) LIMIT {}",
+        reader,
+        sql_string_literal(path),
+        n
+    );
+    let mut result = crate::query_result_json(&conn, &sql).map_err(|e| e.to_string())?;
+
+    Ok(result["rows"].take())
+}
+
+pub fn sample_column_widths(
+    path: &str,
+    format: &str,
+    sample_rows: usize,
+) -> Result<serde_json::Value, String> {
+    let reader = file_reader(format)?;
+    if sample_rows == 0 {
+        return Err("sample_rows must be greater than 0".to_string());
+    }
+
+    let conn = crate::duckdb_connect().map_err(|e| e.to_string())?;
+
+    let source = format!(
+        "SELECT * FROM {}({}) LIMIT {}",
+        reader,
+        sql_string_literal(path),
+        sample_rows
+    );
+    let column_names: Vec<String> = conn
+        .prepare(&format!("SELECT column_name FROM (DESCRIBE ({}))", source))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to describe file: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect results: {}", e))?;
+
+    if column_names.is_empty() {
+        return Ok(serde_json::json!({ "columns": [] }));
+    }
+
+    // Note: strlen counts bytes, not characters
+    let widths_sql = format!(
+        "SELECT {} FROM ({})",
+        column_names
+            .iter()
+            .map(|name| format!(
+                "COALESCE(MAX(strlen(CAST({} AS VARCHAR))), 0)",
+                crate::quote_identifier(name)
+            ))
+            .collect::<Vec<_>>()
+            .join(", "),
+        source
+    );
+    let widths: Vec<i64> = conn
+        .query_row(&widths_sql, [], |row| {
+            (0..column_names.len()).map(|i| row.get(i)).collect()
+        })
+        .map_err(|e| format!("Failed to sample column widths: {}", e))?;
+
+    let columns: Vec<serde_json::Value> = column_names
+        .into_iter()
+        .zip(widths)
+        .map(|(name, width)| serde_json::json!({ "name": name, "width": width }))
+        .collect();
+
+    Ok(serde_json::json!({ "columns": columns }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column_types(schema: &serde_json::Value) -> Vec<(String, String)> {
+        schema["columns"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| {
+                (
+                    c["name"].as_str().unwrap().to_string(),
+                    c["type"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_infer_json_schema_empty_sample() {
+        assert_eq!(infer_json_schema(&[]), json!({ "columns": [] }));
+    }
+
+    #[test]
+    fn test_infer_json_schema_single_object() {
+        let schema = infer_json_schema(&[json!({
+            "id": 1,
+            "score": 9.5,
+            "active": true,
+            "name": "Alice"
+        })]);
+
+        assert_eq!(
+            schema,
+            json!({
+                "columns": [
+                    { "name": "active", "type": "boolean" },
+                    { "name": "id", "type": "number" },
+                    { "name": "name", "type": "string" },
+                    { "name": "score", "type": "number" }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_infer_json_schema_union_of_keys() {
+        let schema = infer_json_schema(&[
+            json!({ "id": 1, "name": "Alice" }),
+            json!({ "id": 2, "email": "bob@example.com" }),
+            json!({ "tags": ["a"] }),
+        ]);
+
+        assert_eq!(
+            column_types(&schema),
+            vec![
+                ("id".to_string(), "number".to_string()),
+                ("name".to_string(), "string".to_string()),
+                ("email".to_string(), "string".to_string()),
+                ("tags".to_string(), "string".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_infer_json_schema_widening() {
+        let cases = [
+            (json!(true), json!(false), "boolean"),
+            (json!(true), json!(1), "number"),
+            (json!(1), json!(true), "number"),
+            (json!(1), json!(2.5), "number"),
+            (json!(2.5), json!(true), "number"),
+            (json!(1), json!("1"), "string"),
+            (json!("x"), json!(1.5), "string"),
+            (json!(false), json!({ "nested": 1 }), "string"),
+            (json!(1), json!([1, 2]), "string"),
+        ];
+
+        for (first, second, expected) in cases {
+            let schema = infer_json_schema(&[json!({ "v": first }), json!({ "v": second })]);
+            assert_eq!(
+                schema["columns"][0]["type"], expected,
+                "{} + {}",
+                first, second
+            );
+        }
+    }
+
+    #[test]
+    fn test_infer_json_schema_widening_order() {
+        assert!(InferredType::Boolean < InferredType::Integer);
+        assert!(InferredType::Integer < InferredType::Float);
+        assert!(InferredType::Float < InferredType::String);
+
+        let schema = infer_json_schema(&[
+            json!({ "v": true }),
+            json!({ "v": 1 }),
+            json!({ "v": 1.5 }),
+            json!({ "v": 2 }),
+        ]);
+        assert_eq!(schema["columns"][0]["type"], "number");
+    }
+
+    #[test]
+    fn test_infer_json_schema_nulls() {
+        let schema = infer_json_schema(&[
+            json!({ "id": null, "missing": null }),
+            json!({ "id": 3, "missing": null }),
+            json!({ "id": null }),
+        ]);
+
+        assert_eq!(
+            column_types(&schema),
+            vec![
+                ("id".to_string(), "number".to_string()),
+                ("missing".to_string(), "string".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_infer_json_schema_ignores_non_objects() {
+        let schema = infer_json_schema(&[
+            json!(1),
+            json!("row"),
+            json!([{ "id": 1 }]),
+            json!({ "id": 1 }),
+        ]);
+
+        assert_eq!(
+            column_types(&schema),
+            vec![("id".to_string(), "number".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_infer_json_schema_large_unsigned_integer() {
+        let schema = infer_json_schema(&[json!({ "id": u64::MAX })]);
+        assert_eq!(schema["columns"][0]["type"], "number");
+    }
+
+    #[test]
+    fn test_parse_json_records() {
+        assert_eq!(
+            parse_json_records(r#"[{"a": 1}, {"b": 2}]"#).unwrap(),
+            vec![json!({ "a": 1 }), json!({ "b": 2 })]
+        );
+        assert_eq!(
+            parse_json_records(r#"{"a": 1}"#).unwrap(),
+            vec![json!({ "a": 1 })]
+        );
+        assert_eq!(
+            parse_json_records("{\"a\": 1}\n\n{\"b\": true}\n").unwrap(),
+            vec![json!({ "a": 1 }), json!({ "b": true })]
+        );
+        assert!(parse_json_records("{\"a\": 1}\n{oops}")
+            .unwrap_err()
+            .starts_with("Invalid JSON"));
+    }
+
+    #[test]
+    fn test_infer_json_files_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.json");
+        let second = dir.path().join("second.jsonl");
+        std::fs::write(&first, r#"[{"id": 1, "name": "Alice"}]"#).unwrap();
+        std::fs::write(&second, "{\"id\": 2.5, \"active\": true}\n").unwrap();
+
+        let paths = [first, second].map(|p| p.to_string_lossy().to_string());
+        let schema = infer_json_files_schema(&paths).unwrap();
+
+        assert_eq!(
+            column_types(&schema),
+            vec![
+                ("id".to_string(), "number".to_string()),
+                ("name".to_string(), "string".to_string()),
+                ("active".to_string(), "boolean".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            infer_json_files_schema(&[]).unwrap_err(),
+            "No JSON files given"
+        );
+        let missing = dir
+            .path()
+            .join("missing.json")
+            .to_string_lossy()
+            .to_string();
+        assert!(infer_json_files_schema(&[missing])
+            .unwrap_err()
+            .starts_with("Failed to read"));
+    }
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert_eq!(detect_delimiter("id,name,score\n1,Alice,95\n"), ',');
+        assert_eq!(detect_delimiter("id;name;score\n1;Alice;95,5\n"), ';');
+        assert_eq!(detect_delimiter("id|name|score\n1|Alice|95\n"), '|');
+        assert_eq!(detect_delimiter("id\tname\tscore\n1\tAlice\t95\n"), '\t');
+    }
+
+    #[test]
+    fn test_detect_delimiter_ignores_quoted_values() {
+        let sample = "id;note\n1;\"a, b, c\"\n2;\"d, e\"\n";
+        assert_eq!(detect_delimiter(sample), ';');
+    }
+
+    #[test]
+    fn test_detect_delimiter_only_first_lines() {
+        let mut sample = "a;b\n".repeat(DELIMITER_SAMPLE_LINES);
+        sample.push_str(&"a,b,c,d,e,f\n".repeat(20));
+        assert_eq!(detect_delimiter(&sample), ';');
+    }
+
+    #[test]
+    fn test_detect_delimiter_defaults_to_comma() {
+        assert_eq!(detect_delimiter(""), ',');
+        assert_eq!(detect_delimiter("single_column\nvalue\n"), ',');
+        assert_eq!(detect_delimiter("a,b;c\n"), ',');
+    }
+
+    #[test]
+    fn test_detect_encoding_utf8() {
+        assert_eq!(
+            detect_encoding("name,city\nZoë,Zürich\n".as_bytes()),
+            "UTF-8"
+        );
+        assert_eq!(detect_encoding(b"id,name\n1,plain\n"), "UTF-8");
+        assert_eq!(detect_encoding(b""), "UTF-8");
+        assert_eq!(detect_encoding(b"\xEF\xBB\xBFid,name\n"), "UTF-8");
+
+        let truncated = "name\nZoë".as_bytes();
+        assert_eq!(detect_encoding(&truncated[..truncated.len() - 1]), "UTF-8");
+    }
+
+    #[test]
+    fn test_detect_encoding_utf16() {
+        let text: Vec<u16> = "name,city\nZoë,Zürich\n".encode_utf16().collect();
+        let mut le = vec![0xFF, 0xFE];
+        le.extend(text.iter().flat_map(|u| u.to_le_bytes()));
+        let mut be = vec![0xFE, 0xFF];
+        be.extend(text.iter().flat_map(|u| u.to_be_bytes()));
+
+        assert_eq!(detect_encoding(&le), "UTF-16LE");
+        assert_eq!(detect_encoding(&be), "UTF-16BE");
+        assert_eq!(detect_encoding(&le[2..]), "UTF-16LE");
+        assert_eq!(detect_encoding(&be[2..]), "UTF-16BE");
+    }
+
+    #[test]
+    fn test_detect_encoding_latin1() {
+        let (latin1, _, _) = encoding_rs::WINDOWS_1252.encode("name,city\nZoë,Zürich\n");
+        assert_eq!(detect_encoding(&latin1), "windows-1252");
+    }
+
+    #[test]
+    fn test_connector_type() {
+        assert_eq!(connector_type("VARCHAR"), "string");
+        assert_eq!(connector_type("BIGINT"), "number");
+        assert_eq!(connector_type("DOUBLE"), "number");
+        assert_eq!(connector_type("DECIMAL(18,3)"), "number");
+        assert_eq!(connector_type("BOOLEAN"), "boolean");
+        assert_eq!(connector_type("DATE"), "date");
+        assert_eq!(connector_type("TIMESTAMP WITH TIME ZONE"), "date");
+        assert_eq!(connector_type("BLOB"), "string");
+    }
+
+    #[test]
+    fn test_infer_csv_schema_zero_sample_rows() {
+        assert_eq!(
+            infer_csv_schema("data.csv", ',', true, 0).unwrap_err(),
+            "sample_rows must be greater than 0"
+        );
+    }
+}
@@ -13,6 +13,7 @@ export type Schema = {
   columns: Array<{
     name: string;
     type: SchemaType;
+    skip?: boolean;
   }>;
 };
 